@@ -24,6 +24,18 @@ var<uniform> g: f32;
 var<uniform> b: f32;
 @group(2) @binding(12)
 var<uniform> rot: f32;
+@group(2) @binding(13)
+var<uniform> a: f32;
+@group(2) @binding(14)
+var<uniform> well: f32;
+@group(2) @binding(15)
+var<uniform> well_x: f32;
+@group(2) @binding(16)
+var<uniform> well_y: f32;
+
+// How many times the texture repeats going around a well
+const WELL_SPOKES: f32 = 16.0;
+const TAU: f32 = 6.28318530718;
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -31,7 +43,15 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let cs = cos(rot);
     let sn = sin(rot);
     let scaled = vec2<f32>(shifted.x * x_repetitions, shifted.y * y_repetitions);
-    let rotated = vec2<f32>(cs * scaled.x - sn * scaled.y, sn * scaled.x + cs * scaled.y);
+    var rotated = vec2<f32>(cs * scaled.x - sn * scaled.y, sn * scaled.x + cs * scaled.y);
+    if (abs(well) > 0.5) {
+        // Polar coordinates around the well, so the texture's x runs toward (or away from) it
+        let well_shifted = vec2<f32>((well_x - 0.5) * 2.0, (well_y - 0.5) * 2.0);
+        let well_scaled = vec2<f32>(well_shifted.x * x_repetitions, well_shifted.y * y_repetitions);
+        let diff = scaled - well_scaled;
+        let around = atan2(diff.y, diff.x) / TAU;
+        rotated = vec2<f32>(-well * length(diff), around * WELL_SPOKES * 2.0);
+    }
     let unshifted = vec2<f32>(rotated.x / 2.0 + 0.5, rotated.y / 2.0 + 0.5);
     // Adding 20.0 here because it works, no idea why
     let input_x = (-x_offset + 20.0 + unshifted.x) % 1.0;
@@ -41,5 +61,5 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let out_uv = vec2<f32>(index_lower + (index_upper - index_lower) * input_x, input_y);
     let out_rgba = textureSample(texture, splr, out_uv);
 
-    return vec4<f32>(out_rgba[0] * r, out_rgba[1] * g, out_rgba[2] * b, out_rgba[3]);
+    return vec4<f32>(out_rgba[0] * r, out_rgba[1] * g, out_rgba[2] * b, out_rgba[3] * a);
 }
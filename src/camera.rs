@@ -1,5 +1,6 @@
 use crate::{
     drawing::layering::{LayeringPlugin, LightCameraMarker, SpriteCameraMarker},
+    environment::portal::PortalTravel,
     input::{CameraControlState, CameraZoomEvent, SetCameraModeEvent, SwitchCameraModeEvent},
     math::Spleen,
     meta::{
@@ -156,6 +157,7 @@ pub fn camera_movement(
     mut marker: Query<(&mut IntMoveable, &mut CameraMarker)>,
     control_state: Res<CameraControlState>,
     mut zooms: EventReader<CameraZoomEvent>,
+    mut travels: EventReader<PortalTravel>,
     mut light_camera: Query<
         (&mut Transform, &mut OrthographicProjection),
         (With<LightCameraMarker>, Without<SpriteCameraMarker>),
@@ -181,6 +183,14 @@ pub fn camera_movement(
             let Ok(root) = level_root.get_single() else {
                 return;
             };
+            if travels.read().count() > 0 && dislodgement.is_none() {
+                // Glide after a ship that went through a portal instead of jumping
+                let start_pos = moveable.fpos.truncate() - root.translation().truncate();
+                *dislodgement = Some(CameraMarker::get_dislodgement(
+                    start_pos.round().as_ivec2(),
+                    dyno.get_ipos().truncate(),
+                ));
+            }
             let end_dislodgement = match dislodgement {
                 Some(dislodgement) => {
                     // We are trying to follow the ship but just started doing so
@@ -0,0 +1,215 @@
+//! Command line tools that run instead of the game, i.e. `zenith solve basic_1`.
+//! Running with no subcommand just launches the game like normal, and `zenith replay <id>`
+//! launches the game straight into playing back a replay.
+//! `zenith upgrade` rewrites levels (and old editor scenes) as the current level version.
+//! `zenith lint-levels` checks levels for mistakes, exiting non-zero if it finds any.
+
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::{
+    meta::{
+        level_data::{upgrade_file, Upgrade, LEVEL_VERSION},
+        level_lint::lint_level,
+        old_level_data::LevelData,
+    },
+    physics::{
+        headless::HeadlessSim,
+        solver::{solve, SolverConfig},
+    },
+    replay::Replay,
+};
+
+/// What `main` should do after parsing the command line
+pub enum CliAction {
+    /// A tool ran, exit with this code
+    Exit(i32),
+    /// Start the game, optionally playing back a replay
+    Play(Option<Replay>),
+}
+
+fn command() -> Command {
+    Command::new("zenith")
+        .subcommand(
+            Command::new("solve")
+                .about("Find the fewest shots needed to beat levels (all levels if none given)")
+                .arg(Arg::new("ids").action(ArgAction::Append))
+                .arg(
+                    Arg::new("shots")
+                        .long("shots")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Give up after this many shots"),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Play back a replay file, or the last attempt at a level id")
+                .arg(Arg::new("replay").required(true)),
+        )
+        .subcommand(
+            Command::new("lint-levels")
+                .about("Check levels for mistakes (all levels if none given)")
+                .arg(Arg::new("ids").action(ArgAction::Append)),
+        )
+        .subcommand(
+            Command::new("upgrade")
+                .about("Migrate level files (and old .scn.ron scenes) to the current level version")
+                .arg(Arg::new("paths").action(ArgAction::Append).help(
+                    "Files to upgrade (every level and scene in assets/levels if none given)",
+                ))
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .action(ArgAction::SetTrue)
+                        .help("Don't write anything, just fail if something needs upgrading"),
+                ),
+        )
+}
+
+pub fn run_cli() -> CliAction {
+    let matches = command().get_matches();
+    match matches.subcommand() {
+        Some(("solve", sub)) => CliAction::Exit(run_solve(sub)),
+        Some(("lint-levels", sub)) => CliAction::Exit(run_lint_levels(sub)),
+        Some(("upgrade", sub)) => CliAction::Exit(run_upgrade(sub)),
+        Some(("replay", sub)) => {
+            let path_or_id = sub.get_one::<String>("replay").unwrap();
+            match Replay::read_from_disk(path_or_id) {
+                Ok(replay) => CliAction::Play(Some(replay)),
+                Err(e) => {
+                    eprintln!("Can't load replay: {e}");
+                    CliAction::Exit(1)
+                }
+            }
+        }
+        _ => CliAction::Play(None),
+    }
+}
+
+/// Ids passed on the command line, or every level on disk if there weren't any
+fn level_ids(matches: &ArgMatches) -> Vec<String> {
+    match matches.get_many::<String>("ids") {
+        Some(ids) => ids.cloned().collect(),
+        None => LevelData::ids_on_disk(),
+    }
+}
+
+fn run_solve(matches: &ArgMatches) -> i32 {
+    let mut config = SolverConfig::default();
+    if let Some(shots) = matches.get_one::<usize>("shots") {
+        config.max_shots = *shots;
+    }
+    let mut num_unsolved = 0;
+    for id in level_ids(matches) {
+        let level_data = match LevelData::read_from_disk(&id) {
+            Ok(level_data) => level_data,
+            Err(e) => {
+                eprintln!("{id}: ERROR {e}");
+                num_unsolved += 1;
+                continue;
+            }
+        };
+        match solve(&HeadlessSim::new(level_data), &config) {
+            Some(solution) => {
+                let shots: Vec<String> = solution
+                    .shots
+                    .iter()
+                    .map(|vel| format!("({:.2}, {:.2})", vel.x, vel.y))
+                    .collect();
+                println!("{id}: par {} [{}]", solution.par(), shots.join(", "));
+            }
+            None => {
+                println!("{id}: UNSOLVED within {} shots", config.max_shots);
+                num_unsolved += 1;
+            }
+        }
+    }
+    if num_unsolved > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+fn run_lint_levels(matches: &ArgMatches) -> i32 {
+    let mut num_issues = 0;
+    for id in level_ids(matches) {
+        let path = format!("assets/levels/{id}.level.ron");
+        let level_data = match LevelData::read_from_disk(&id) {
+            Ok(level_data) => level_data,
+            Err(e) => {
+                eprintln!("ERROR {e}");
+                num_issues += 1;
+                continue;
+            }
+        };
+        for issue in lint_level(&level_data) {
+            println!("{path}:{}: {}", issue.entity, issue.message);
+            num_issues += 1;
+        }
+    }
+    if num_issues > 0 {
+        println!("{num_issues} problem(s) found");
+        1
+    } else {
+        0
+    }
+}
+
+/// Every level and old editor scene under `assets/levels`, sorted
+fn level_paths_on_disk() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    for dir in ["assets/levels", "assets/levels/editing"] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".level.ron") || name.ends_with(".scn.ron") {
+                paths.push(entry.path());
+            }
+        }
+    }
+    paths.sort();
+    paths
+}
+
+fn run_upgrade(matches: &ArgMatches) -> i32 {
+    let check = matches.get_flag("check");
+    let paths = match matches.get_many::<String>("paths") {
+        Some(paths) => paths.map(PathBuf::from).collect(),
+        None => level_paths_on_disk(),
+    };
+    let mut num_failed = 0;
+    let mut num_outdated = 0;
+    for path in paths {
+        let name = path.display();
+        match upgrade_file(&path, check) {
+            Ok(Upgrade::UpToDate) => (),
+            Ok(Upgrade::SceneAlreadyConverted { level_path }) => {
+                println!(
+                    "{name}: skipped, already converted to {}",
+                    level_path.display()
+                );
+            }
+            Ok(Upgrade::Migrated { from }) => {
+                num_outdated += 1;
+                println!("{name}: v{from} -> v{LEVEL_VERSION}");
+            }
+            Ok(Upgrade::FromScene { level_path }) => {
+                num_outdated += 1;
+                println!("{name}: scene -> {}", level_path.display());
+            }
+            Err(e) => {
+                num_failed += 1;
+                eprintln!("{name}: ERROR {e}");
+            }
+        }
+    }
+    if num_failed > 0 || (check && num_outdated > 0) {
+        1
+    } else {
+        0
+    }
+}
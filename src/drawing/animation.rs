@@ -59,6 +59,10 @@ pub struct AnimationManager {
     offset: IVec3,
     // Material rotation
     mat_rot: f32,
+    // Makes the material point toward (or with `true`, away from) a point instead of along
+    // `mat_rot`. In the same space as `points`
+    #[serde(default)]
+    mat_well: Option<(Vec2, bool)>,
     // Rotation of the transform
     tran_angle: f32,
     render_layers_u8: Vec<u8>,
@@ -119,6 +123,15 @@ impl AnimationManager {
         self.is_changed = true;
     }
 
+    pub fn set_mat_well(&mut self, well: Option<(Vec2, bool)>) {
+        if self.mat_well == well {
+            // Do nothing
+            return;
+        }
+        self.mat_well = well;
+        self.is_changed = true;
+    }
+
     pub fn set_tran_angle(&mut self, angle: f32) {
         if (self.tran_angle - angle).abs() < 0.001 {
             // Do nothing
@@ -274,6 +287,7 @@ impl Default for AnimationManager {
             scale: AnimationScale::Repeat,
             offset: IVec3::ZERO,
             mat_rot: 0.0,
+            mat_well: None,
             tran_angle: 0.0,
             render_layers_u8: vec![sprite_layer_u8()],
             hidden: false,
@@ -610,6 +624,18 @@ fn update_animation_bodies(
             current_node.sprite.color,
         );
         mat.rot = manager.mat_rot;
+        if let Some((center, repel)) = manager.mat_well {
+            // Same mapping as the uvs in `points_to_mesh`
+            let min = fpoints
+                .iter()
+                .copied()
+                .reduce(Vec2::min)
+                .unwrap_or_default();
+            let size = Vec2::new(mesh_size.x.max(1) as f32, mesh_size.y.max(1) as f32);
+            mat.well = if repel { -1.0 } else { 1.0 };
+            mat.well_x = (center.x - min.x) / size.x;
+            mat.well_y = 1.0 - (center.y - min.y) / size.y;
+        }
         mat.ephemeral = manager.ephemeral;
         let mat_ass = mats.add(mat);
         let mesh = points_to_mesh(&fpoints, &mut meshes);
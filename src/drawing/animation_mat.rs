@@ -41,6 +41,16 @@ pub struct AnimationMaterial {
     pub b: f32,
     #[uniform(12)]
     pub rot: f32,
+    #[uniform(13)]
+    pub a: f32,
+    /// 1 points the texture toward (`well_x`, `well_y`) in uv space, -1 away from it, and 0 uses
+    /// `rot` like normal
+    #[uniform(14)]
+    pub well: f32,
+    #[uniform(15)]
+    pub well_x: f32,
+    #[uniform(16)]
+    pub well_y: f32,
     pub ephemeral: bool,
 }
 impl AnimationMaterial {
@@ -62,6 +72,10 @@ impl AnimationMaterial {
             g: color.g(),
             b: color.b(),
             rot: 0.0,
+            a: color.a(),
+            well: 0.0,
+            well_x: 0.0,
+            well_y: 0.0,
             ephemeral: false,
         }
     }
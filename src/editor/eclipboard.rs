@@ -0,0 +1,686 @@
+//! Copy/paste/duplicate, plus mirroring and rotating whatever is selected.
+//! The clipboard is just a `LevelData` (minus the start/goal) centered on the origin.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    math::ToIVec2,
+    meta::{
+        game_state::{EditingMode, SetMetaState},
+        old_level_data::{
+            ExportedField, ExportedPortal, ExportedReplenish, ExportedRock, ExportedSegment,
+            ExportedStardust, ExportedSwitch, LevelData,
+        },
+    },
+    physics::dyno::IntMoveable,
+    uid::fresh_uid,
+};
+
+use super::{
+    efield::EField,
+    egoodies::{EPortal, EReplenish, ESegment, EStardust, ESwitch},
+    ehistory::{record_edit, EEdit, GroupSnapshot},
+    epoint::{EPointGroup, ESelected},
+    erock::ERock,
+    esave::spawn_pieces,
+    help::{say, HelpBarData},
+    transitions::ERootEid,
+};
+
+/// How far a duplicate ends up from the original
+const DUPLICATE_OFFSET: IVec2 = IVec2::new(16, -16);
+
+/// A selected point, and which (if any) of the single-point goodies it is
+type SelectedPoint<'a> = (
+    &'a IntMoveable,
+    Has<EReplenish>,
+    Option<&'a ESwitch>,
+    Option<&'a EStardust>,
+);
+
+#[derive(Resource, Debug, Default)]
+pub(super) struct EClipboard {
+    pieces: Option<LevelData>,
+}
+
+/// Corners of the bounding box of some points
+fn bounds(poses: impl Iterator<Item = IVec2>) -> Option<(IVec2, IVec2)> {
+    poses
+        .map(|pos| (pos, pos))
+        .reduce(|(min, max), (pos, _)| (min.min(pos), max.max(pos)))
+}
+
+/// Center of the bounding box of some points
+fn bounds_center(poses: &[IVec2]) -> Option<IVec2> {
+    let (min, max) = bounds(poses.iter().copied())?;
+    Some((min + max) / 2)
+}
+
+/// Everything needed to turn the selection into pieces
+#[derive(SystemParam)]
+pub(super) struct ESelectionPieces<'w, 's> {
+    selected_q: Query<'w, 's, SelectedPoint<'static>, With<ESelected>>,
+    groups_q: Query<
+        'w,
+        's,
+        (
+            &'static EPointGroup,
+            Option<&'static ERock>,
+            Option<&'static EField>,
+        ),
+    >,
+    segments_q: Query<'w, 's, &'static ESegment>,
+    portals_q: Query<'w, 's, &'static EPortal>,
+}
+impl ESelectionPieces<'_, '_> {
+    /// Every rock/field whose points are all selected, plus selected replenishes/switches/stardust
+    /// and any segment/portal with both ends selected. Positions are relative to the center of the
+    /// selection, which is returned alongside
+    fn collect(&self) -> Option<(LevelData, IVec2)> {
+        let poses: Vec<IVec2> = self
+            .selected_q
+            .iter()
+            .map(|(mv, _, _, _)| mv.get_ipos().truncate())
+            .collect();
+        let center = bounds_center(&poses)?;
+        let pos_of = |eid: &Entity| {
+            self.selected_q
+                .get(*eid)
+                .ok()
+                .map(|(mv, _, _, _)| mv.get_ipos().truncate() - center)
+        };
+        let all_selected = |pg: &EPointGroup| {
+            let points: Option<Vec<IVec2>> = pg.eids.iter().map(pos_of).collect();
+            points.filter(|points| !points.is_empty())
+        };
+        let mut pieces = LevelData::default();
+        for (pg, erock, efield) in self.groups_q.iter() {
+            let Some(points) = all_selected(pg) else {
+                continue;
+            };
+            if let Some(erock) = erock {
+                pieces.rocks.push(ExportedRock {
+                    kind: erock.kind,
+                    points,
+                    z: erock.z,
+                    bounciness: erock.bounciness,
+                    path: erock.path.clone(),
+                    rotation: erock.rotation.clone(),
+                    target: erock.target.clone(),
+                });
+            } else if let Some(efield) = efield {
+                pieces.fields.push(ExportedField {
+                    points,
+                    dir: efield.dir,
+                    strength: efield.strength,
+                    drag: efield.drag,
+                    well: efield.well.clone(),
+                    target: efield.target.clone(),
+                });
+            }
+        }
+        for segment in self.segments_q.iter() {
+            if let (Some(left_parent), Some(right_parent)) =
+                (pos_of(&segment.left), pos_of(&segment.right))
+            {
+                pieces.segments.push(ExportedSegment {
+                    kind: segment.kind,
+                    left_parent,
+                    right_parent,
+                });
+            }
+        }
+        for portal in self.portals_q.iter() {
+            if let (Some(a), Some(b)) = (pos_of(&portal.a), pos_of(&portal.b)) {
+                pieces.portals.push(ExportedPortal {
+                    a,
+                    b,
+                    a_angle: portal.a_angle,
+                    b_angle: portal.b_angle,
+                    rotate: portal.rotate,
+                });
+            }
+        }
+        for (mv, is_replenish, eswitch, estardust) in self.selected_q.iter() {
+            let pos = mv.get_ipos().truncate() - center;
+            if is_replenish {
+                pieces.replenishes.push(ExportedReplenish { pos });
+            }
+            if let Some(eswitch) = eswitch {
+                pieces.switches.push(ExportedSwitch {
+                    pos,
+                    targets: eswitch.targets.clone(),
+                    reset: eswitch.reset,
+                });
+            }
+            if let Some(estardust) = estardust {
+                pieces.stardust.push(ExportedStardust {
+                    uid: estardust.uid,
+                    pos,
+                });
+            }
+        }
+        Some((pieces, center))
+    }
+}
+
+/// i.e. "2 rock(s), 1 portal(s)"
+fn describe_pieces(pieces: &LevelData) -> String {
+    let counts = [
+        (pieces.rocks.len(), "rock(s)"),
+        (pieces.fields.len(), "field(s)"),
+        (pieces.segments.len(), "segment(s)"),
+        (pieces.portals.len(), "portal(s)"),
+        (pieces.replenishes.len(), "replenish(es)"),
+        (pieces.switches.len(), "switch(es)"),
+        (pieces.stardust.len(), "stardust"),
+    ];
+    let parts: Vec<String> = counts
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{count} {what}"))
+        .collect();
+    if parts.is_empty() {
+        "nothing".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Copies the selection to the clipboard, and says what was copied
+pub(super) fn copy_selection(
+    In(()): In<()>,
+    mut clipboard: ResMut<EClipboard>,
+    selection: ESelectionPieces,
+    mut help_bar_q: Query<&mut HelpBarData>,
+) {
+    let Some((pieces, _)) = selection.collect() else {
+        return;
+    };
+    say(
+        &mut help_bar_q,
+        format!("Copied {}", describe_pieces(&pieces)),
+    );
+    clipboard.pieces = Some(pieces);
+}
+
+/// Spawns `pieces` centered on `pos` and makes them the selection. Pasted stardust is new
+/// stardust, so it gets new uids
+fn paste_pieces(
+    mut pieces: LevelData,
+    pos: IVec2,
+    commands: &mut Commands,
+    eroot: Entity,
+    selected: impl Iterator<Item = Entity>,
+    meta_writer: &mut EventWriter<SetMetaState>,
+) {
+    for stardust in pieces.stardust.iter_mut() {
+        stardust.uid = fresh_uid();
+    }
+    for eid in selected {
+        commands.entity(eid).remove::<ESelected>();
+    }
+    let mut spawned = None;
+    commands.entity(eroot).with_children(|parent| {
+        spawned = Some(spawn_pieces(parent, &pieces, pos));
+    });
+    let Some(spawned) = spawned else {
+        return;
+    };
+    for (order, point) in spawned.points.iter().enumerate() {
+        commands.entity(point.eid).insert(ESelected {
+            order: order as u32,
+            offset: Vec2::ZERO,
+        });
+    }
+    record_edit(commands, EEdit::spawn_pieces(spawned, EditingMode::Free));
+    meta_writer.send(SetMetaState(EditingMode::Free.to_meta_state()));
+}
+
+/// Pastes the clipboard centered on `pos`
+pub(super) fn paste_clipboard(
+    In(pos): In<IVec2>,
+    clipboard: Res<EClipboard>,
+    mut commands: Commands,
+    eroot: Res<ERootEid>,
+    selected_q: Query<Entity, With<ESelected>>,
+    mut meta_writer: EventWriter<SetMetaState>,
+) {
+    let Some(pieces) = clipboard.pieces.clone() else {
+        return;
+    };
+    paste_pieces(
+        pieces,
+        pos,
+        &mut commands,
+        eroot.0,
+        selected_q.iter(),
+        &mut meta_writer,
+    );
+}
+
+/// Pastes a copy of the selection a little ways away, leaving the clipboard alone
+pub(super) fn duplicate_selection(
+    In(()): In<()>,
+    mut commands: Commands,
+    eroot: Res<ERootEid>,
+    selection: ESelectionPieces,
+    selected_q: Query<Entity, With<ESelected>>,
+    mut meta_writer: EventWriter<SetMetaState>,
+) {
+    let Some((pieces, center)) = selection.collect() else {
+        return;
+    };
+    paste_pieces(
+        pieces,
+        center + DUPLICATE_OFFSET,
+        &mut commands,
+        eroot.0,
+        selected_q.iter(),
+        &mut meta_writer,
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ETransform {
+    MirrorHorizontal,
+    MirrorVertical,
+    /// 90 degrees clockwise
+    Rotate,
+}
+impl ETransform {
+    /// Where `pos` ends up, for a selection centered on `center`. Rotating a selection whose
+    /// width and height don't have the same parity lands points halfway between grid cells
+    fn apply(&self, pos: Vec2, center: Vec2) -> Vec2 {
+        match *self {
+            Self::MirrorHorizontal => Vec2::new(2.0 * center.x - pos.x, pos.y),
+            Self::MirrorVertical => Vec2::new(pos.x, 2.0 * center.y - pos.y),
+            Self::Rotate => center + self.apply_dir(pos - center),
+        }
+    }
+
+    /// Where an offset (i.e. a path waypoint) ends up, once whatever it's relative to has moved
+    fn apply_offset(&self, offset: IVec2) -> IVec2 {
+        match *self {
+            Self::MirrorHorizontal => IVec2::new(-offset.x, offset.y),
+            Self::MirrorVertical => IVec2::new(offset.x, -offset.y),
+            Self::Rotate => IVec2::new(offset.y, -offset.x),
+        }
+    }
+
+    /// For angles in radians, i.e. which way a portal faces
+    fn apply_angle(&self, angle: f32) -> f32 {
+        match *self {
+            Self::MirrorHorizontal => PI - angle,
+            Self::MirrorVertical => -angle,
+            Self::Rotate => angle - FRAC_PI_2,
+        }
+    }
+
+    fn apply_dir(&self, dir: Vec2) -> Vec2 {
+        match *self {
+            Self::MirrorHorizontal => Vec2::new(-dir.x, dir.y),
+            Self::MirrorVertical => Vec2::new(dir.x, -dir.y),
+            Self::Rotate => Vec2::new(dir.y, -dir.x),
+        }
+    }
+
+    /// Mirroring turns clockwise polygons counter-clockwise
+    fn flips(&self) -> bool {
+        match *self {
+            Self::MirrorHorizontal | Self::MirrorVertical => true,
+            Self::Rotate => false,
+        }
+    }
+}
+
+/// Remembered between rotations of the same selection, so it keeps turning about the same center
+/// instead of drifting as it gets rounded back onto the grid
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LastRotation {
+    min: IVec2,
+    max: IVec2,
+    center: Vec2,
+    /// How far the last rotation rounded the points from where they really belonged
+    rounding: Vec2,
+}
+
+/// Mirrors/rotates the selected points about the center of their bounds. Rocks and fields that
+/// are entirely selected keep their winding, and turn everything that's relative to them (field
+/// directions, rock paths...) along with them
+pub(super) fn transform_selection(
+    In(transform): In<ETransform>,
+    mut last_rotation: Local<Option<LastRotation>>,
+    mut commands: Commands,
+    mut selected_q: Query<(Entity, &mut IntMoveable), With<ESelected>>,
+    mut groups_q: Query<(
+        Entity,
+        &mut EPointGroup,
+        Option<&mut ERock>,
+        Option<&mut EField>,
+    )>,
+    mut portals_q: Query<(Entity, &mut EPortal)>,
+) {
+    let Some((min, max)) = bounds(
+        selected_q
+            .iter()
+            .map(|(_, mv)| mv.fpos.truncate().to_ivec2()),
+    ) else {
+        return;
+    };
+    let (center, rounding) = match *last_rotation {
+        Some(last) if transform == ETransform::Rotate && (last.min, last.max) == (min, max) => {
+            (last.center, last.rounding)
+        }
+        _ => ((min + max).as_vec2() / 2.0, Vec2::ZERO),
+    };
+    let mut moves = vec![];
+    let mut new_rounding = Vec2::ZERO;
+    for (eid, mut mv) in selected_q.iter_mut() {
+        let from = mv.fpos.truncate().to_ivec2();
+        let exact = transform.apply(from.as_vec2() - rounding, center);
+        // Always round halves the same way so every point moves together
+        let to = (exact + Vec2::splat(0.5)).floor();
+        new_rounding = to - exact;
+        let to = to.as_ivec2();
+        mv.fpos = to.as_vec2().extend(mv.fpos.z);
+        moves.push((eid, from, to));
+    }
+    *last_rotation = match (transform, bounds(moves.iter().map(|(_, _, to)| *to))) {
+        (ETransform::Rotate, Some((min, max))) => Some(LastRotation {
+            min,
+            max,
+            center,
+            rounding: new_rounding,
+        }),
+        _ => None,
+    };
+    let mut group_changes = vec![];
+    let mut dirs = vec![];
+    for (eid, mut pg, mut erock, mut efield) in groups_q.iter_mut() {
+        if pg.eids.is_empty() || !pg.eids.iter().all(|eid| selected_q.contains(*eid)) {
+            continue;
+        }
+        let before = GroupSnapshot::new(eid, &pg, erock.as_deref(), efield.as_deref());
+        if transform.flips() {
+            pg.eids.reverse();
+            pg.poses.reverse();
+        }
+        if let Some(path) = erock.as_mut().and_then(|erock| erock.path.as_mut()) {
+            for waypoint in path.waypoints.iter_mut() {
+                *waypoint = transform.apply_offset(*waypoint);
+            }
+        }
+        if let Some(rotation) = erock.as_mut().and_then(|erock| erock.rotation.as_mut()) {
+            rotation.pivot = transform.apply_offset(rotation.pivot);
+            // A mirrored spinner spins the other way
+            if transform.flips() {
+                rotation.speed = -rotation.speed;
+            }
+        }
+        if let Some(efield) = efield.as_mut() {
+            let from = efield.dir;
+            efield.dir = transform.apply_dir(from);
+            dirs.push((eid, from, efield.dir));
+            if let Some(well) = efield.well.as_mut() {
+                well.center = transform.apply_offset(well.center);
+            }
+        }
+        let after = GroupSnapshot::new(eid, &pg, erock.as_deref(), efield.as_deref());
+        group_changes.extend(before.zip(after));
+    }
+    let mut portal_changes = vec![];
+    for (eid, mut portal) in portals_q.iter_mut() {
+        if !selected_q.contains(portal.a) || !selected_q.contains(portal.b) {
+            continue;
+        }
+        let before = portal.clone();
+        portal.a_angle = transform.apply_angle(portal.a_angle);
+        portal.b_angle = transform.apply_angle(portal.b_angle);
+        portal_changes.push((eid, before, portal.clone()));
+    }
+    record_edit(
+        &mut commands,
+        EEdit::transform_points(moves, group_changes, dirs, portal_changes),
+    );
+}
+
+#[cfg(test)]
+mod eclipboard_tests {
+    use super::*;
+    use crate::{
+        editor::{
+            ehistory::{step_history, EHistory, HistoryDirection},
+            esave::{crystallize_level_data, load_level_data},
+        },
+        meta::old_level_data::{ExportedRockPath, ExportedRockRotation, ExportedWell},
+    };
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// Loads `level_data` into an editor with everything selected, then transforms it.
+    /// Returns the level as it was transformed, after making sure undo puts it back
+    fn transform_everything(level_data: LevelData, transform: ETransform) -> LevelData {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        world.run_system_once_with(level_data, load_level_data);
+        let before = world.run_system_once(crystallize_level_data).unwrap();
+        let points: Vec<Entity> = world
+            .query_filtered::<Entity, With<IntMoveable>>()
+            .iter(&world)
+            .collect();
+        for (order, eid) in points.into_iter().enumerate() {
+            world.entity_mut(eid).insert(ESelected {
+                order: order as u32,
+                offset: Vec2::ZERO,
+            });
+        }
+        world.run_system_once_with(transform, transform_selection);
+        let after = world.run_system_once(crystallize_level_data).unwrap();
+        world.run_system_once_with(HistoryDirection::Undo, step_history);
+        let undone = world.run_system_once(crystallize_level_data).unwrap();
+        assert_eq!(undone, before, "{transform:?} didn't undo");
+        after
+    }
+
+    fn triangle_rock() -> ExportedRock {
+        ExportedRock {
+            points: vec![IVec2::new(0, 0), IVec2::new(0, 10), IVec2::new(10, 0)],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn rock_paths_turn_with_their_rock() {
+        let level_data = LevelData {
+            start: IVec2::new(-20, 0),
+            goal: IVec2::new(20, 0),
+            rocks: vec![ExportedRock {
+                path: Some(ExportedRockPath {
+                    waypoints: vec![IVec2::new(30, 5)],
+                    speed: 1.0,
+                    ..default()
+                }),
+                ..triangle_rock()
+            }],
+            ..default()
+        };
+        let mirrored = transform_everything(level_data.clone(), ETransform::MirrorHorizontal);
+        let path = mirrored.rocks[0].path.as_ref().unwrap();
+        assert_eq!(path.waypoints, vec![IVec2::new(-30, 5)]);
+        let rotated = transform_everything(level_data, ETransform::Rotate);
+        let path = rotated.rocks[0].path.as_ref().unwrap();
+        assert_eq!(path.waypoints, vec![IVec2::new(5, -30)]);
+    }
+
+    #[test]
+    fn rock_rotations_turn_with_their_rock() {
+        let level_data = LevelData {
+            start: IVec2::new(-20, 0),
+            goal: IVec2::new(20, 0),
+            rocks: vec![ExportedRock {
+                rotation: Some(ExportedRockRotation {
+                    pivot: IVec2::new(4, 1),
+                    speed: 0.1,
+                }),
+                ..triangle_rock()
+            }],
+            ..default()
+        };
+        let mirrored = transform_everything(level_data.clone(), ETransform::MirrorVertical);
+        let rotation = mirrored.rocks[0].rotation.as_ref().unwrap();
+        assert_eq!(rotation.pivot, IVec2::new(4, -1));
+        assert_eq!(rotation.speed, -0.1);
+        let rotated = transform_everything(level_data, ETransform::Rotate);
+        let rotation = rotated.rocks[0].rotation.as_ref().unwrap();
+        assert_eq!(rotation.pivot, IVec2::new(1, -4));
+        assert_eq!(rotation.speed, 0.1);
+    }
+
+    #[test]
+    fn wells_turn_with_their_field() {
+        let level_data = LevelData {
+            start: IVec2::new(-20, 0),
+            goal: IVec2::new(20, 0),
+            fields: vec![ExportedField {
+                points: vec![IVec2::new(0, 0), IVec2::new(0, 10), IVec2::new(10, 0)],
+                dir: Vec2::X,
+                well: Some(ExportedWell {
+                    center: IVec2::new(6, 2),
+                    radius: 20.0,
+                    ..default()
+                }),
+                ..default()
+            }],
+            ..default()
+        };
+        let mirrored = transform_everything(level_data, ETransform::MirrorHorizontal);
+        assert_eq!(mirrored.fields[0].dir, Vec2::NEG_X);
+        let well = mirrored.fields[0].well.as_ref().unwrap();
+        assert_eq!(well.center, IVec2::new(-6, 2));
+    }
+
+    #[test]
+    fn portals_turn_with_the_selection() {
+        let level_data = LevelData {
+            start: IVec2::new(-20, 0),
+            goal: IVec2::new(20, 0),
+            portals: vec![ExportedPortal {
+                a: IVec2::new(-10, 30),
+                b: IVec2::new(10, 30),
+                a_angle: 0.0,
+                b_angle: FRAC_PI_2,
+                rotate: true,
+            }],
+            ..default()
+        };
+        let mirrored = transform_everything(level_data.clone(), ETransform::MirrorHorizontal);
+        assert_eq!(mirrored.portals[0].a_angle, PI);
+        assert_eq!(mirrored.portals[0].b_angle, FRAC_PI_2);
+        let rotated = transform_everything(level_data, ETransform::Rotate);
+        assert_eq!(rotated.portals[0].a_angle, -FRAC_PI_2);
+        assert_eq!(rotated.portals[0].b_angle, 0.0);
+    }
+
+    #[test]
+    fn duplicating_leaves_the_clipboard_alone() {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        let copied = LevelData {
+            replenishes: vec![ExportedReplenish { pos: IVec2::ZERO }],
+            ..default()
+        };
+        world.insert_resource(EClipboard {
+            pieces: Some(copied.clone()),
+        });
+        let level_data = LevelData {
+            start: IVec2::new(-20, 0),
+            goal: IVec2::new(20, 0),
+            rocks: vec![triangle_rock()],
+            ..default()
+        };
+        world.run_system_once_with(level_data, load_level_data);
+        let points: Vec<Entity> = world.query::<&EPointGroup>().single(&world).eids.clone();
+        for (order, eid) in points.into_iter().enumerate() {
+            world.entity_mut(eid).insert(ESelected {
+                order: order as u32,
+                offset: Vec2::ZERO,
+            });
+        }
+        world.run_system_once_with((), duplicate_selection);
+        let duplicated = world.run_system_once(crystallize_level_data).unwrap();
+        assert_eq!(duplicated.rocks.len(), 2);
+        assert_eq!(world.resource::<EClipboard>().pieces, Some(copied));
+    }
+
+    #[test]
+    fn transforms_move_points_about_the_center() {
+        let center = Vec2::new(5.0, 5.0);
+        assert_eq!(
+            ETransform::MirrorHorizontal.apply(Vec2::new(-10.0, 3.0), center),
+            Vec2::new(20.0, 3.0)
+        );
+        assert_eq!(
+            ETransform::MirrorVertical.apply(Vec2::new(-10.0, 3.0), center),
+            Vec2::new(-10.0, 7.0)
+        );
+        assert_eq!(
+            ETransform::Rotate.apply(Vec2::new(5.0, 10.0), center),
+            Vec2::new(10.0, 5.0)
+        );
+        assert_eq!(ETransform::Rotate.apply_dir(Vec2::Y), Vec2::X);
+    }
+
+    /// Selects the corners of a box from `min` to `max`, rotates it `times` times (like pressing
+    /// rotate over and over) and returns where the corners ended up
+    fn rotate_box(min: IVec2, max: IVec2, times: usize) -> Vec<IVec2> {
+        let mut world = World::new();
+        world.init_resource::<EHistory>();
+        let corners = [min, IVec2::new(min.x, max.y), max, IVec2::new(max.x, min.y)];
+        let eids: Vec<Entity> = corners
+            .iter()
+            .enumerate()
+            .map(|(order, corner)| {
+                let selected = ESelected {
+                    order: order as u32,
+                    offset: Vec2::ZERO,
+                };
+                world
+                    .spawn((IntMoveable::new(corner.extend(0)), selected))
+                    .id()
+            })
+            .collect();
+        let rotate = world.register_system(transform_selection);
+        for _ in 0..times {
+            world
+                .run_system_with_input(rotate, ETransform::Rotate)
+                .unwrap();
+        }
+        eids.into_iter()
+            .map(|eid| world.get::<IntMoveable>(eid).unwrap().fpos.truncate())
+            .map(|pos| pos.to_ivec2())
+            .collect()
+    }
+
+    #[test]
+    fn rotating_keeps_the_selection_in_place() {
+        for (min, max) in [
+            (IVec2::new(0, 0), IVec2::new(3, 1)),
+            (IVec2::new(0, 0), IVec2::new(2, 1)),
+            (IVec2::new(-7, 4), IVec2::new(6, 10)),
+        ] {
+            let original = rotate_box(min, max, 0);
+            let mut turned_around = rotate_box(min, max, 2);
+            turned_around.rotate_left(2);
+            assert_eq!(turned_around, original, "{min} {max} twice");
+            assert_eq!(rotate_box(min, max, 4), original, "{min} {max} four times");
+        }
+    }
+}
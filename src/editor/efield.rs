@@ -2,74 +2,157 @@ use bevy::{input::keyboard, prelude::*};
 
 use crate::{
     drawing::animation::{AnimationManager, SpriteInfo},
-    math::ifield_norm,
-    meta::game_state::{EditingMode, GameState, SetMetaState},
+    environment::field::{FieldDrag, FieldStrength, WellFalloff},
+    input::MouseState,
+    math::{icenter, ifield_norm},
+    meta::{
+        game_state::{EditingMode, GameState, SetMetaState},
+        old_level_data::{ExportedSwitchTarget, ExportedWell},
+    },
     physics::dyno::IntMoveable,
 };
 
 use super::{
+    ehistory::{record_edit, EEdit, GroupKind, GroupSnapshot},
     epoint::{EPoint, EPointGroup, ESelected},
+    esnap::ESnap,
+    help::{say, HelpBarData},
+    oneshots::EOneshots,
     transitions::ERootEid,
 };
 
+/// How far new wells reach
+const DEFAULT_WELL_RADIUS: f32 = 60.0;
+
 #[derive(Component, Debug, Clone, Reflect)]
 pub struct EField {
     /// Needed to solve the issue where on the tick where we send event to change game state
     /// we spawn the rock, and then the minimum is set to 2 and it's despawned
     needs_init: bool,
     pub dir: Vec2,
+    pub strength: FieldStrength,
+    pub drag: FieldDrag,
+    pub well: Option<ExportedWell>,
+    pub target: Option<ExportedSwitchTarget>,
 }
 impl EField {
     pub fn new(dir: Vec2) -> Self {
         Self {
             needs_init: true,
             dir,
+            strength: default(),
+            drag: default(),
+            well: None,
+            target: None,
+        }
+    }
+
+    /// For fields that already have all their points, i.e. ones loaded from a level
+    pub fn finished(dir: Vec2) -> Self {
+        Self {
+            needs_init: false,
+            dir,
+            strength: default(),
+            drag: default(),
+            well: None,
+            target: None,
         }
     }
 }
 
 #[derive(Bundle)]
-struct EFieldBundle {
+pub(super) struct EFieldBundle {
     name: Name,
     efield: EField,
     point_group: EPointGroup,
     spatial: SpatialBundle,
     anim: AnimationManager,
 }
+impl EFieldBundle {
+    pub(super) fn new(efield: EField, point_group: EPointGroup) -> Self {
+        let anim = AnimationManager::single_repeating(
+            SpriteInfo {
+                path: "sprites/field/field_dyno.png".to_string(),
+                size: UVec2::new(8, 8),
+                ..default()
+            },
+            8,
+        )
+        .force_points(vec![]);
+        Self {
+            name: Name::new("field"),
+            efield,
+            point_group,
+            spatial: SpatialBundle {
+                transform: Transform::from_translation(-Vec3::Z * 3.0),
+                ..default()
+            },
+            anim,
+        }
+    }
+}
 
+/// Starts a new field. With no `dir`, it gets one from the first two points
 pub(super) fn spawn_field(
-    In(()): In<()>,
+    In((dir, strength)): In<(Vec2, FieldStrength)>,
     mut commands: Commands,
     mut meta_writer: EventWriter<SetMetaState>,
     eroot: Res<ERootEid>,
 ) {
-    let anim = AnimationManager::single_repeating(
-        SpriteInfo {
-            path: "sprites/field/field_dyno.png".to_string(),
-            size: UVec2::new(8, 8),
-            ..default()
-        },
-        8,
-    )
-    .force_points(vec![]);
+    let mut efield = EField::new(dir);
+    efield.strength = strength;
+    let pg = EPointGroup::default();
     let mut eid = Entity::PLACEHOLDER;
     commands.entity(eroot.0).with_children(|parent| {
         eid = parent
-            .spawn(EFieldBundle {
-                name: Name::new("field"),
-                efield: EField::new(Vec2::ZERO),
-                point_group: EPointGroup::default(),
-                spatial: SpatialBundle {
-                    transform: Transform::from_translation(-Vec3::Z * 3.0),
-                    ..default()
-                },
-                anim,
-            })
+            .spawn(EFieldBundle::new(efield.clone(), pg.clone()))
             .id();
     });
-    meta_writer.send(SetMetaState(
-        EditingMode::CreatingField(eid).to_meta_state(),
-    ));
+    let emode = EditingMode::CreatingField(eid);
+    let group = GroupSnapshot {
+        eid,
+        kind: GroupKind::Field(efield),
+        pg,
+    };
+    record_edit(&mut commands, EEdit::create_group(group, emode));
+    meta_writer.send(SetMetaState(emode.to_meta_state()));
+}
+
+/// Points the field being worked on in `dir`, and sets its strength if given. If there isn't
+/// one, starts a new field
+pub(super) fn change_field(
+    In((dir, strength)): In<(Vec2, Option<FieldStrength>)>,
+    gs: Res<GameState>,
+    mut fields_q: Query<(&mut EField, &EPointGroup)>,
+    oneshots: Res<EOneshots>,
+    mut commands: Commands,
+) {
+    let Some(EditingMode::CreatingField(eid) | EditingMode::EditingField(eid)) =
+        gs.get_editing_mode()
+    else {
+        let strength = strength.unwrap_or_default();
+        commands.run_system_with_input(oneshots.spawn_field, (dir, strength));
+        return;
+    };
+    let Ok((mut efield, pg)) = fields_q.get_mut(eid) else {
+        return;
+    };
+    let before = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    let from = efield.dir;
+    efield.dir = dir;
+    if let Some(strength) = strength {
+        efield.strength = strength;
+    }
+    let after = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    record_edit(
+        &mut commands,
+        EEdit::transform_points(
+            vec![],
+            before.zip(after).into_iter().collect(),
+            vec![(eid, from, dir)],
+            vec![],
+        ),
+    );
 }
 
 pub(super) fn update_fields(
@@ -85,13 +168,20 @@ pub(super) fn update_fields(
         EditingMode::Free
         | EditingMode::EditingRock(_)
         | EditingMode::CreatingRock(_)
-        | EditingMode::EditingField(_) => Entity::PLACEHOLDER,
+        | EditingMode::EditingField(_)
+        | EditingMode::PlacingSegment(_)
+        | EditingMode::PlacingReplenish
+        | EditingMode::PlacingSwitch
+        | EditingMode::PlacingStardust
+        | EditingMode::PlacingPortal
+        | EditingMode::PlacingStart
+        | EditingMode::PlacingGoal => Entity::PLACEHOLDER,
         EditingMode::CreatingField(eid) => eid,
     });
     for mut field_data in &mut fields_q {
         if Some(field_data.0) == creating_eid {
             field_data.1.needs_init = false;
-            if field_data.2.poses.len() == 2 {
+            if field_data.2.poses.len() == 2 && field_data.1.dir == Vec2::ZERO {
                 // When creating the field, make it's dir according to the first two points you add
                 field_data.1.dir = ifield_norm(field_data.2.poses[0], field_data.2.poses[1]);
             }
@@ -120,5 +210,127 @@ pub(super) fn animate_fields(mut fields_q: Query<(&mut AnimationManager, &EPoint
     for (mut anim, pg, field) in fields_q.iter_mut() {
         anim.set_points(pg.poses.clone());
         anim.set_mat_rot(field.dir.to_angle());
+        let well = field.well.as_ref().map(|well| {
+            let center = icenter(&pg.poses) + well.center;
+            (center.as_vec2(), well.repel)
+        });
+        anim.set_mat_well(well);
+    }
+}
+
+/// How a well pulls. Where it pulls toward is set with the mouse
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct EWellPull {
+    pub falloff: WellFalloff,
+    pub radius: f32,
+    pub repel: bool,
+}
+
+/// Something about a field that can be changed from the console, besides its direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum EFieldChange {
+    Strength(FieldStrength),
+    Drag(FieldDrag),
+    /// Turns the field into a well, or back into a normal field with `None`. Keeps the center of
+    /// an existing well
+    Well(Option<EWellPull>),
+}
+
+/// Changes the field being worked on
+pub(super) fn tweak_field(
+    In(change): In<EFieldChange>,
+    gs: Res<GameState>,
+    mut fields_q: Query<(&mut EField, &EPointGroup)>,
+    mut commands: Commands,
+    mut help_bar_q: Query<&mut HelpBarData>,
+) {
+    let Some(EditingMode::CreatingField(eid) | EditingMode::EditingField(eid)) =
+        gs.get_editing_mode()
+    else {
+        say(&mut help_bar_q, "Not editing a field".into());
+        return;
+    };
+    let Ok((mut efield, pg)) = fields_q.get_mut(eid) else {
+        return;
+    };
+    let before = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    match change {
+        EFieldChange::Strength(strength) => efield.strength = strength,
+        EFieldChange::Drag(drag) => efield.drag = drag,
+        EFieldChange::Well(pull) => {
+            let center = efield
+                .well
+                .take()
+                .map(|well| well.center)
+                .unwrap_or_default();
+            efield.well = pull.map(|pull| ExportedWell {
+                center,
+                falloff: pull.falloff,
+                radius: pull.radius,
+                repel: pull.repel,
+            });
+        }
+    }
+    let after = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    record_edit(
+        &mut commands,
+        EEdit::set_groups(before.zip(after).into_iter().collect()),
+    );
+}
+
+/// While editing a field, I moves the center of its well to the mouse (making it a well if it
+/// wasn't one), and shift+I turns it back into a normal field
+pub(super) fn watch_well_input(
+    gs: Res<GameState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<MouseState>,
+    snap: Res<ESnap>,
+    mut fields_q: Query<(&mut EField, &EPointGroup)>,
+    mut commands: Commands,
+) {
+    let Some(EditingMode::EditingField(eid)) = gs.get_editing_mode() else {
+        return;
+    };
+    if !keyboard.just_pressed(KeyCode::KeyI) {
+        return;
+    }
+    let Ok((mut efield, pg)) = fields_q.get_mut(eid) else {
+        return;
+    };
+    let before = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        if efield.well.take().is_none() {
+            return;
+        }
+    } else {
+        efield
+            .well
+            .get_or_insert_with(|| ExportedWell {
+                radius: DEFAULT_WELL_RADIUS,
+                ..default()
+            })
+            .center = snap.snap(mouse.world_pos.as_vec2()) - icenter(&pg.poses);
+    }
+    let after = GroupSnapshot::new(eid, pg, None, Some(&*efield));
+    record_edit(
+        &mut commands,
+        EEdit::set_groups(before.zip(after).into_iter().collect()),
+    );
+}
+
+/// Shows how far wells reach
+pub(super) fn draw_wells(fields_q: Query<(&EField, &EPointGroup)>, mut gizmos: Gizmos) {
+    for (efield, pg) in fields_q.iter() {
+        let Some(well) = efield.well.as_ref() else {
+            continue;
+        };
+        let color = if well.repel {
+            Color::rgba(1.0, 0.6, 0.4, 0.5)
+        } else {
+            Color::rgba(0.4, 0.6, 1.0, 0.5)
+        };
+        let center = (icenter(&pg.poses) + well.center).as_vec2();
+        gizmos.circle_2d(center, 2.0, color);
+        gizmos.circle_2d(center, well.radius, color);
     }
 }
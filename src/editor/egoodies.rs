@@ -0,0 +1,634 @@
+//! The non-rock, non-field parts of a level: start, goal, replenishes, switches, stardust,
+//! segments and portals. Start/goal/replenishes/switches/stardust are just points with an extra
+//! marker (and a sprite or gizmo to tell them apart), which means hovering, selecting and moving
+//! them all comes for free.
+//! Segments and portals hang off of two points the same way.
+
+use bevy::prelude::*;
+
+use crate::{
+    drawing::animation::{AnimationManager, SpriteInfo},
+    environment::{goal::GoalSize, portal::PORTAL_RADIUS, segment::SegmentKind, start::StartSize},
+    math::{MathLine, ToIVec2},
+    meta::{
+        game_state::{EditingMode, GameState, SetMetaState},
+        old_level_data::ExportedSwitchTarget,
+    },
+    physics::dyno::IntMoveable,
+    uid::{fresh_uid, UId},
+};
+
+use super::{
+    efield::EField,
+    ehistory::{record_edit, EEdit, Goodie, GroupSnapshot, PointSnapshot},
+    epoint::{EPoint, EPointBundle, EPointGroup, ESelected},
+    erock::ERock,
+    esave::SpawnedPieces,
+    help::{say, HelpBarData},
+    transitions::ERootEid,
+};
+
+/// How far from a rock edge you can click and still attach a segment to it
+const SEGMENT_REACH: f32 = 8.0;
+
+/// Where the second end of a new portal goes, relative to the first
+const PORTAL_SPREAD: IVec2 = IVec2::new(48, 0);
+
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct EStart;
+
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct EGoal;
+
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct EReplenish;
+
+/// Flips the rocks/fields whose target has one of `targets` as its uid
+#[derive(Component, Debug, Clone, Default, PartialEq, Reflect)]
+pub struct ESwitch {
+    pub targets: Vec<UId>,
+    pub reset: Option<f32>,
+}
+
+/// A collectible. `uid` is what the save file remembers it by, so it sticks with the stardust
+/// through moves, saves and loads (but not copy/paste)
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+pub struct EStardust {
+    pub uid: UId,
+}
+
+/// What the `switch` command does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ESwitchChange {
+    /// Points the selected switches at the selected rocks/fields, with the given reset
+    Link(Option<f32>),
+    Unlink,
+    /// Whether the selected rocks/fields start switched off
+    StartsOff(bool),
+}
+
+/// A spike/spring that lives on the edge between two points (usually on a rock)
+#[derive(Component, Debug, Clone, Reflect)]
+pub struct ESegment {
+    pub kind: SegmentKind,
+    pub left: Entity,
+    pub right: Entity,
+}
+
+/// Two points that the ship warps between
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+pub struct EPortal {
+    pub a: Entity,
+    pub b: Entity,
+    /// Which way each end faces, in radians. Only matters when `rotate`
+    pub a_angle: f32,
+    pub b_angle: f32,
+    pub rotate: bool,
+}
+impl EPortal {
+    pub fn straight(a: Entity, b: Entity) -> Self {
+        Self {
+            a,
+            b,
+            a_angle: 0.0,
+            b_angle: 0.0,
+            rotate: false,
+        }
+    }
+}
+
+/// Spawns a point with the given marker, and a child sprite showing what it is
+pub(super) fn spawn_goodie(
+    parent: &mut ChildBuilder,
+    marker: impl Component,
+    name: &str,
+    anim: AnimationManager,
+    pos: IVec2,
+) -> Entity {
+    parent
+        .spawn(EPointBundle::new(pos))
+        .insert((marker, Name::new(name.to_string())))
+        .with_children(|point| {
+            point.spawn((
+                Name::new(format!("{name}_sprite")),
+                SpatialBundle::from_transform(Transform::from_translation(-Vec3::Z)),
+                anim,
+            ));
+        })
+        .id()
+}
+
+pub(super) fn spawn_start(parent: &mut ChildBuilder, pos: IVec2) -> Entity {
+    let size = StartSize::Medium;
+    let anim = AnimationManager::single_repeating(size.to_sprite_info(), size.to_anim_length());
+    spawn_goodie(parent, EStart, "start", anim, pos)
+}
+
+pub(super) fn spawn_goal(parent: &mut ChildBuilder, pos: IVec2) -> Entity {
+    let size = GoalSize::Medium;
+    let anim = AnimationManager::single_repeating(size.to_sprite_info(), size.to_anim_length());
+    spawn_goodie(parent, EGoal, "goal", anim, pos)
+}
+
+pub(super) fn spawn_replenish(parent: &mut ChildBuilder, pos: IVec2) -> Entity {
+    let anim = AnimationManager::single_static(SpriteInfo {
+        path: "sprites/replenish.png".to_string(),
+        size: UVec2::new(12, 12),
+        ..default()
+    });
+    spawn_goodie(parent, EReplenish, "replenish", anim, pos)
+}
+
+/// Switches don't have a sprite, `draw_switches` shows them instead
+pub(super) fn spawn_switch(parent: &mut ChildBuilder, pos: IVec2, eswitch: ESwitch) -> Entity {
+    parent
+        .spawn(EPointBundle::new(pos))
+        .insert((eswitch, Name::new("switch")))
+        .id()
+}
+
+/// Stardust doesn't have a sprite either, `draw_stardust` shows it
+pub(super) fn spawn_stardust(
+    parent: &mut ChildBuilder,
+    pos: IVec2,
+    estardust: EStardust,
+) -> Entity {
+    parent
+        .spawn(EPointBundle::new(pos))
+        .insert((estardust, Name::new("stardust")))
+        .id()
+}
+
+/// Segments don't survive losing either of their points
+pub(super) fn cleanup_segments(
+    mut commands: Commands,
+    segments_q: Query<(Entity, &ESegment)>,
+    points_q: Query<(), With<EPoint>>,
+) {
+    for (eid, segment) in segments_q.iter() {
+        if !points_q.contains(segment.left) || !points_q.contains(segment.right) {
+            commands.entity(eid).despawn_recursive();
+        }
+    }
+}
+
+/// Portals don't survive losing either end
+pub(super) fn cleanup_portals(
+    mut commands: Commands,
+    portals_q: Query<(Entity, &EPortal)>,
+    points_q: Query<(), With<EPoint>>,
+) {
+    for (eid, portal) in portals_q.iter() {
+        if !points_q.contains(portal.a) || !points_q.contains(portal.b) {
+            commands.entity(eid).despawn_recursive();
+        }
+    }
+}
+
+pub(super) fn draw_portals(
+    mut gizmos: Gizmos,
+    portals_q: Query<&EPortal>,
+    points_q: Query<&IntMoveable, With<EPoint>>,
+) {
+    for portal in portals_q.iter() {
+        let (Ok(a), Ok(b)) = (points_q.get(portal.a), points_q.get(portal.b)) else {
+            continue;
+        };
+        let (a, b) = (a.fpos.truncate(), b.fpos.truncate());
+        gizmos.line_2d(a, b, Color::PURPLE.with_a(0.3));
+        for (pos, angle) in [(a, portal.a_angle), (b, portal.b_angle)] {
+            gizmos.circle_2d(pos, PORTAL_RADIUS as f32, Color::PURPLE);
+            if portal.rotate {
+                let facing = Vec2::from_angle(angle) * PORTAL_RADIUS as f32 * 2.0;
+                gizmos.line_2d(pos, pos + facing, Color::FUCHSIA);
+            }
+        }
+    }
+}
+
+/// Switches, with a line to the middle of everything they flip
+pub(super) fn draw_switches(
+    mut gizmos: Gizmos,
+    switches_q: Query<(&ESwitch, &IntMoveable)>,
+    groups_q: Query<(&EPointGroup, Option<&ERock>, Option<&EField>)>,
+    points_q: Query<&IntMoveable, With<EPoint>>,
+) {
+    for (eswitch, mv) in switches_q.iter() {
+        let pos = mv.fpos.truncate();
+        let color = if eswitch.reset.is_some() {
+            Color::YELLOW
+        } else {
+            Color::ORANGE
+        };
+        gizmos.rect_2d(pos, 0.0, Vec2::splat(8.0), color);
+        for (pg, erock, efield) in groups_q.iter() {
+            let target = erock
+                .and_then(|erock| erock.target.as_ref())
+                .or(efield.and_then(|efield| efield.target.as_ref()));
+            if !target.is_some_and(|target| eswitch.targets.contains(&target.uid)) {
+                continue;
+            }
+            let poses: Vec<Vec2> = pg
+                .eids
+                .iter()
+                .filter_map(|eid| points_q.get(*eid).ok())
+                .map(|mv| mv.fpos.truncate())
+                .collect();
+            if poses.is_empty() {
+                continue;
+            }
+            let middle = poses.iter().sum::<Vec2>() / poses.len() as f32;
+            gizmos.line_2d(pos, middle, color.with_a(0.3));
+        }
+    }
+}
+
+pub(super) fn draw_stardust(mut gizmos: Gizmos, stardust_q: Query<&IntMoveable, With<EStardust>>) {
+    for mv in stardust_q.iter() {
+        gizmos.circle_2d(mv.fpos.truncate(), 4.0, Color::GOLD);
+    }
+}
+
+pub(super) fn draw_segments(
+    mut gizmos: Gizmos,
+    segments_q: Query<&ESegment>,
+    points_q: Query<&IntMoveable, With<EPoint>>,
+) {
+    for segment in segments_q.iter() {
+        let (Ok(left), Ok(right)) = (points_q.get(segment.left), points_q.get(segment.right))
+        else {
+            continue;
+        };
+        let color = match segment.kind {
+            SegmentKind::Spike => Color::RED,
+            SegmentKind::Spring => Color::GREEN,
+        };
+        gizmos.line_2d(left.fpos.truncate(), right.fpos.truncate(), color);
+    }
+}
+
+/// K/J (spike/spring), O (replenish), B (switch), C (stardust), U (portal), comma (start) and
+/// period (goal) switch into the mode for placing that thing. Pressing the same key again, or
+/// escape, goes back to free editing
+pub(super) fn watch_goodie_input(
+    gs: Res<GameState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut meta_writer: EventWriter<SetMetaState>,
+) {
+    let Some(emode) = gs.get_editing_mode() else {
+        return;
+    };
+    let placing = matches!(
+        emode,
+        EditingMode::PlacingSegment(_)
+            | EditingMode::PlacingReplenish
+            | EditingMode::PlacingSwitch
+            | EditingMode::PlacingStardust
+            | EditingMode::PlacingPortal
+            | EditingMode::PlacingStart
+            | EditingMode::PlacingGoal
+    );
+    if emode != EditingMode::Free && !placing {
+        // Don't abandon a rock/field halfway through
+        return;
+    }
+    if keyboard.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]) {
+        return;
+    }
+    let next_emode = [
+        (
+            KeyCode::KeyK,
+            EditingMode::PlacingSegment(SegmentKind::Spike),
+        ),
+        (
+            KeyCode::KeyJ,
+            EditingMode::PlacingSegment(SegmentKind::Spring),
+        ),
+        (KeyCode::KeyO, EditingMode::PlacingReplenish),
+        (KeyCode::KeyB, EditingMode::PlacingSwitch),
+        (KeyCode::KeyC, EditingMode::PlacingStardust),
+        (KeyCode::KeyU, EditingMode::PlacingPortal),
+        (KeyCode::Comma, EditingMode::PlacingStart),
+        (KeyCode::Period, EditingMode::PlacingGoal),
+    ]
+    .into_iter()
+    .find(|(key, _)| keyboard.just_pressed(*key))
+    .map(|(_, next_emode)| next_emode);
+    let next_emode = match next_emode {
+        Some(next_emode) if next_emode != emode => next_emode,
+        Some(_) => EditingMode::Free,
+        None if placing && keyboard.just_pressed(KeyCode::Escape) => EditingMode::Free,
+        None => return,
+    };
+    meta_writer.send(SetMetaState(next_emode.to_meta_state()));
+}
+
+/// Makes every portal with a selected end turn the ship to match its ends, which face the given
+/// angles (in degrees). `None` makes them keep the ship going the same way instead
+pub(super) fn change_portals(
+    In(angles): In<Option<(f32, f32)>>,
+    mut commands: Commands,
+    mut portals_q: Query<(Entity, &mut EPortal)>,
+    selected_q: Query<(), With<ESelected>>,
+    mut help_bar_q: Query<&mut HelpBarData>,
+) {
+    let mut changes = vec![];
+    for (eid, mut portal) in portals_q.iter_mut() {
+        if !selected_q.contains(portal.a) && !selected_q.contains(portal.b) {
+            continue;
+        }
+        let before = portal.clone();
+        match angles {
+            Some((a_angle, b_angle)) => {
+                portal.a_angle = a_angle.to_radians();
+                portal.b_angle = b_angle.to_radians();
+                portal.rotate = true;
+            }
+            None => portal.rotate = false,
+        }
+        changes.push((eid, before, portal.clone()));
+    }
+    if changes.is_empty() {
+        say(&mut help_bar_q, "No portal selected".into());
+        return;
+    }
+    record_edit(&mut commands, EEdit::set_portals(changes));
+}
+
+/// Links/unlinks the selected switches, or changes how the selected rocks/fields start. Rocks and
+/// fields get a uid the first time anything points at them
+pub(super) fn change_switches(
+    In(change): In<ESwitchChange>,
+    mut commands: Commands,
+    mut switches_q: Query<(Entity, &mut ESwitch), With<ESelected>>,
+    mut groups_q: Query<(
+        Entity,
+        &EPointGroup,
+        Option<&mut ERock>,
+        Option<&mut EField>,
+    )>,
+    selected_q: Query<(), With<ESelected>>,
+    mut help_bar_q: Query<&mut HelpBarData>,
+) {
+    if matches!(change, ESwitchChange::Link(_) | ESwitchChange::Unlink) && switches_q.is_empty() {
+        say(&mut help_bar_q, "No switch selected".into());
+        return;
+    }
+    let mut uids = vec![];
+    let mut group_changes = vec![];
+    if change != ESwitchChange::Unlink {
+        for (eid, pg, mut erock, mut efield) in groups_q.iter_mut() {
+            if !pg.eids.iter().any(|point| selected_q.contains(*point)) {
+                continue;
+            }
+            let before = GroupSnapshot::new(eid, pg, erock.as_deref(), efield.as_deref());
+            let target = match (erock.as_deref_mut(), efield.as_deref_mut()) {
+                (Some(erock), _) => &mut erock.target,
+                (None, Some(efield)) => &mut efield.target,
+                (None, None) => continue,
+            };
+            let target = target.get_or_insert_with(|| ExportedSwitchTarget {
+                uid: fresh_uid(),
+                starts_off: false,
+            });
+            if let ESwitchChange::StartsOff(starts_off) = change {
+                target.starts_off = starts_off;
+            }
+            uids.push(target.uid);
+            let after = GroupSnapshot::new(eid, pg, erock.as_deref(), efield.as_deref());
+            group_changes.extend(before.zip(after));
+        }
+        if uids.is_empty() {
+            say(&mut help_bar_q, "No rock or field selected".into());
+            return;
+        }
+    }
+    let mut switch_changes = vec![];
+    if !matches!(change, ESwitchChange::StartsOff(_)) {
+        for (eid, mut eswitch) in switches_q.iter_mut() {
+            let before = eswitch.clone();
+            eswitch.targets.clone_from(&uids);
+            if let ESwitchChange::Link(reset) = change {
+                eswitch.reset = reset;
+            }
+            switch_changes.push((eid, before, eswitch.clone()));
+        }
+    }
+    record_edit(
+        &mut commands,
+        EEdit::change_switches(switch_changes, group_changes),
+    );
+}
+
+/// The closest rock edge to `pos` (as its two points), if there's one within reach
+fn closest_rock_edge(
+    pos: Vec2,
+    rocks_q: &Query<&EPointGroup, With<ERock>>,
+    points_q: &Query<&mut IntMoveable, With<EPoint>>,
+) -> Option<(Entity, Entity)> {
+    let mut closest = None;
+    let mut closest_dist = SEGMENT_REACH;
+    for pg in rocks_q.iter() {
+        for ix in 0..pg.eids.len() {
+            let (left, right) = (pg.eids[ix], pg.eids[(ix + 1) % pg.eids.len()]);
+            let (Ok(p1), Ok(p2)) = (points_q.get(left), points_q.get(right)) else {
+                continue;
+            };
+            let line = MathLine {
+                p1: p1.fpos.truncate(),
+                p2: p2.fpos.truncate(),
+            };
+            let dist = line.closest_point_on_segment(&pos).distance(pos);
+            if dist <= closest_dist {
+                closest = Some((left, right));
+                closest_dist = dist;
+            }
+        }
+    }
+    closest
+}
+
+/// What a right click does in the segment/replenish/switch/stardust/portal/start/goal modes
+pub(super) fn place_goodie(
+    In((emode, world_pos)): In<(EditingMode, IVec2)>,
+    mut commands: Commands,
+    eroot: Res<ERootEid>,
+    rocks_q: Query<&EPointGroup, With<ERock>>,
+    mut points_q: Query<&mut IntMoveable, With<EPoint>>,
+    start_q: Query<Entity, With<EStart>>,
+    goal_q: Query<Entity, With<EGoal>>,
+) {
+    match emode {
+        EditingMode::PlacingSegment(kind) => {
+            let Some((left, right)) = closest_rock_edge(world_pos.as_vec2(), &rocks_q, &points_q)
+            else {
+                return;
+            };
+            let esegment = ESegment { kind, left, right };
+            let mut eid = Entity::PLACEHOLDER;
+            commands.entity(eroot.0).with_children(|parent| {
+                eid = parent.spawn((Name::new("segment"), esegment.clone())).id();
+            });
+            let pieces = SpawnedPieces {
+                points: vec![],
+                groups: vec![],
+                segments: vec![(eid, esegment)],
+                portals: vec![],
+            };
+            record_edit(&mut commands, EEdit::spawn_pieces(pieces, emode));
+        }
+        EditingMode::PlacingReplenish => {
+            let mut eid = Entity::PLACEHOLDER;
+            commands.entity(eroot.0).with_children(|parent| {
+                eid = spawn_replenish(parent, world_pos);
+            });
+            let pieces = SpawnedPieces {
+                points: vec![PointSnapshot {
+                    eid,
+                    pos: world_pos,
+                    goodie: Some(Goodie::Replenish),
+                }],
+                groups: vec![],
+                segments: vec![],
+                portals: vec![],
+            };
+            record_edit(&mut commands, EEdit::spawn_pieces(pieces, emode));
+        }
+        EditingMode::PlacingSwitch => {
+            let mut eid = Entity::PLACEHOLDER;
+            commands.entity(eroot.0).with_children(|parent| {
+                eid = spawn_switch(parent, world_pos, default());
+            });
+            let pieces = SpawnedPieces {
+                points: vec![PointSnapshot {
+                    eid,
+                    pos: world_pos,
+                    goodie: Some(Goodie::Switch(default())),
+                }],
+                groups: vec![],
+                segments: vec![],
+                portals: vec![],
+            };
+            record_edit(&mut commands, EEdit::spawn_pieces(pieces, emode));
+        }
+        EditingMode::PlacingStardust => {
+            let estardust = EStardust { uid: fresh_uid() };
+            let mut eid = Entity::PLACEHOLDER;
+            commands.entity(eroot.0).with_children(|parent| {
+                eid = spawn_stardust(parent, world_pos, estardust.clone());
+            });
+            let pieces = SpawnedPieces {
+                points: vec![PointSnapshot {
+                    eid,
+                    pos: world_pos,
+                    goodie: Some(Goodie::Stardust(estardust)),
+                }],
+                groups: vec![],
+                segments: vec![],
+                portals: vec![],
+            };
+            record_edit(&mut commands, EEdit::spawn_pieces(pieces, emode));
+        }
+        EditingMode::PlacingPortal => {
+            // Drops both ends at once, the second one can be dragged wherever it should go
+            let mut points = vec![];
+            let mut portals = vec![];
+            commands.entity(eroot.0).with_children(|parent| {
+                for pos in [world_pos, world_pos + PORTAL_SPREAD] {
+                    let eid = parent.spawn(EPointBundle::new(pos)).id();
+                    points.push(PointSnapshot {
+                        eid,
+                        pos,
+                        goodie: None,
+                    });
+                }
+                let eportal = EPortal::straight(points[0].eid, points[1].eid);
+                let eid = parent.spawn((Name::new("portal"), eportal.clone())).id();
+                portals.push((eid, eportal));
+            });
+            let pieces = SpawnedPieces {
+                points,
+                groups: vec![],
+                segments: vec![],
+                portals,
+            };
+            record_edit(&mut commands, EEdit::spawn_pieces(pieces, emode));
+        }
+        EditingMode::PlacingStart | EditingMode::PlacingGoal => {
+            let goodie = if emode == EditingMode::PlacingStart {
+                start_q.get_single()
+            } else {
+                goal_q.get_single()
+            };
+            let Ok(eid) = goodie else {
+                return;
+            };
+            let Ok(mut mv) = points_q.get_mut(eid) else {
+                return;
+            };
+            let from = mv.fpos.truncate().to_ivec2();
+            mv.fpos = world_pos.as_vec2().extend(mv.fpos.z);
+            record_edit(
+                &mut commands,
+                EEdit::move_points(vec![(eid, from, world_pos)]),
+            );
+        }
+        _ => {
+            warn!("Tried to place a goodie while in {emode:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod egoodies_tests {
+    use super::*;
+    use crate::{
+        editor::ehistory::{step_history, EHistory, HistoryDirection},
+        meta::game_state::SetMetaState,
+    };
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn num_segments(world: &mut World) -> usize {
+        world.query::<&ESegment>().iter(world).count()
+    }
+
+    #[test]
+    fn segments_attach_to_the_closest_rock_edge() {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        let points: Vec<Entity> = [(0, 0), (0, 20), (40, 20), (40, 0)]
+            .into_iter()
+            .map(|(x, y)| world.spawn(EPointBundle::new(IVec2::new(x, y))).id())
+            .collect();
+        world.spawn((
+            ERock::finished(default()),
+            EPointGroup {
+                eids: points.clone(),
+                ..default()
+            },
+        ));
+        let emode = EditingMode::PlacingSegment(SegmentKind::Spring);
+
+        // Too far from any edge
+        world.run_system_once_with((emode, IVec2::new(20, 40)), place_goodie);
+        assert_eq!(num_segments(&mut world), 0);
+
+        world.run_system_once_with((emode, IVec2::new(20, 23)), place_goodie);
+        let segment = world.query::<&ESegment>().single(&world);
+        assert_eq!((segment.left, segment.right), (points[1], points[2]));
+        assert_eq!(segment.kind, SegmentKind::Spring);
+
+        world.run_system_once_with(HistoryDirection::Undo, step_history);
+        assert_eq!(num_segments(&mut world), 0);
+        world.run_system_once_with(HistoryDirection::Redo, step_history);
+        assert_eq!(num_segments(&mut world), 1);
+    }
+}
@@ -0,0 +1,560 @@
+//! Undo/redo for the editor.
+//! Every edit is recorded as two lists of steps: one that undoes it and one that redoes it.
+//! Undoing a deletion has to respawn things, which gives them new entity ids. Rather than
+//! rewriting every edit in the history, we remember which id replaced which and resolve lazily.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    meta::game_state::{EditingMode, SetMetaState},
+    physics::dyno::IntMoveable,
+};
+
+use super::{
+    efield::{EField, EFieldBundle},
+    egoodies::{
+        spawn_goal, spawn_replenish, spawn_stardust, spawn_start, spawn_switch, EPortal, ESegment,
+        EStardust, ESwitch,
+    },
+    epoint::{EPoint, EPointBundle, EPointGroup},
+    erock::{ERock, ERockBundle},
+    esave::SpawnedPieces,
+    transitions::ERootEid,
+};
+
+/// Points that are also a start/goal/replenish/switch/stardust need to come back as one
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Goodie {
+    Start,
+    Goal,
+    Replenish,
+    Switch(ESwitch),
+    Stardust(EStardust),
+}
+
+/// A point as it was right before it was despawned
+#[derive(Debug, Clone)]
+pub(super) struct PointSnapshot {
+    pub eid: Entity,
+    pub pos: IVec2,
+    pub goodie: Option<Goodie>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum GroupKind {
+    Rock(ERock),
+    Field(EField),
+}
+
+/// A rock/field as it was at some point in time. Enough to respawn it if it's gone
+#[derive(Debug, Clone)]
+pub(super) struct GroupSnapshot {
+    pub eid: Entity,
+    pub kind: GroupKind,
+    pub pg: EPointGroup,
+}
+impl GroupSnapshot {
+    pub(super) fn new(
+        eid: Entity,
+        pg: &EPointGroup,
+        erock: Option<&ERock>,
+        efield: Option<&EField>,
+    ) -> Option<Self> {
+        let kind = match (erock, efield) {
+            (Some(erock), _) => GroupKind::Rock(erock.clone()),
+            (None, Some(efield)) => GroupKind::Field(efield.clone()),
+            (None, None) => return None,
+        };
+        Some(Self {
+            eid,
+            kind,
+            pg: pg.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum EStep {
+    SetPoses(Vec<(Entity, IVec2)>),
+    SpawnPoints(Vec<PointSnapshot>),
+    DespawnPoints(Vec<Entity>),
+    /// Puts groups back the way they were, respawning them if needed
+    SetGroups(Vec<GroupSnapshot>),
+    DespawnGroups(Vec<Entity>),
+    SpawnSegments(Vec<(Entity, ESegment)>),
+    DespawnSegments(Vec<Entity>),
+    /// Puts portals back the way they were, respawning them if needed
+    SetPortals(Vec<(Entity, EPortal)>),
+    DespawnPortals(Vec<Entity>),
+    /// Switches live on points, so they're only ever changed in place
+    SetSwitches(Vec<(Entity, ESwitch)>),
+    SetDirs(Vec<(Entity, Vec2)>),
+    SetMode(EditingMode),
+}
+
+/// A single thing the user did
+#[derive(Debug, Clone)]
+pub(super) struct EEdit {
+    undo: Vec<EStep>,
+    redo: Vec<EStep>,
+}
+impl EEdit {
+    pub(super) fn move_points(moves: Vec<(Entity, IVec2, IVec2)>) -> Self {
+        Self {
+            undo: vec![EStep::SetPoses(
+                moves.iter().map(|(eid, from, _)| (*eid, *from)).collect(),
+            )],
+            redo: vec![EStep::SetPoses(
+                moves.iter().map(|(eid, _, to)| (*eid, *to)).collect(),
+            )],
+        }
+    }
+
+    /// A point was spawned (or an existing one was reused) and possibly added to a group
+    pub(super) fn spawn_point(
+        emode: EditingMode,
+        spawned: Option<PointSnapshot>,
+        group_change: Option<(GroupSnapshot, GroupSnapshot)>,
+    ) -> Self {
+        let mut undo = vec![];
+        let mut redo = vec![];
+        if let Some((before, after)) = group_change {
+            undo.push(EStep::SetGroups(vec![before]));
+            redo.push(EStep::SetGroups(vec![after]));
+        }
+        if let Some(point) = spawned {
+            undo.push(EStep::DespawnPoints(vec![point.eid]));
+            redo.insert(0, EStep::SpawnPoints(vec![point]));
+        }
+        undo.push(EStep::SetMode(emode));
+        redo.push(EStep::SetMode(emode));
+        Self { undo, redo }
+    }
+
+    /// Points were deleted, along with everything that was attached to them
+    pub(super) fn delete_points(
+        emode: EditingMode,
+        points: Vec<PointSnapshot>,
+        groups: Vec<GroupSnapshot>,
+        segments: Vec<(Entity, ESegment)>,
+        portals: Vec<(Entity, EPortal)>,
+    ) -> Self {
+        let eids = points.iter().map(|point| point.eid).collect();
+        Self {
+            undo: vec![
+                EStep::SpawnPoints(points),
+                EStep::SetGroups(groups),
+                EStep::SpawnSegments(segments),
+                EStep::SetPortals(portals),
+                EStep::SetMode(emode),
+            ],
+            redo: vec![EStep::DespawnPoints(eids), EStep::SetMode(emode)],
+        }
+    }
+
+    /// A bunch of things were spawned at once, i.e. by pasting
+    pub(super) fn spawn_pieces(pieces: SpawnedPieces, emode: EditingMode) -> Self {
+        let group_eids = pieces.groups.iter().map(|group| group.eid).collect();
+        let point_eids = pieces.points.iter().map(|point| point.eid).collect();
+        let segment_eids = pieces.segments.iter().map(|(eid, _)| *eid).collect();
+        let portal_eids = pieces.portals.iter().map(|(eid, _)| *eid).collect();
+        Self {
+            undo: vec![
+                EStep::DespawnPortals(portal_eids),
+                EStep::DespawnSegments(segment_eids),
+                EStep::DespawnGroups(group_eids),
+                EStep::DespawnPoints(point_eids),
+                EStep::SetMode(emode),
+            ],
+            redo: vec![
+                EStep::SpawnPoints(pieces.points),
+                EStep::SetGroups(pieces.groups),
+                EStep::SpawnSegments(pieces.segments),
+                EStep::SetPortals(pieces.portals),
+                EStep::SetMode(emode),
+            ],
+        }
+    }
+
+    /// Points were moved all at once (mirrored, rotated), possibly reordering groups and
+    /// turning fields and portals along the way
+    pub(super) fn transform_points(
+        moves: Vec<(Entity, IVec2, IVec2)>,
+        group_changes: Vec<(GroupSnapshot, GroupSnapshot)>,
+        dirs: Vec<(Entity, Vec2, Vec2)>,
+        portal_changes: Vec<(Entity, EPortal, EPortal)>,
+    ) -> Self {
+        let mut edit = Self::move_points(moves);
+        let portal_edit = Self::set_portals(portal_changes);
+        edit.undo.extend(portal_edit.undo);
+        edit.redo.extend(portal_edit.redo);
+        let (befores, afters) = group_changes.into_iter().unzip();
+        edit.undo.push(EStep::SetGroups(befores));
+        edit.redo.push(EStep::SetGroups(afters));
+        edit.undo.push(EStep::SetDirs(
+            dirs.iter().map(|(eid, from, _)| (*eid, *from)).collect(),
+        ));
+        edit.redo.push(EStep::SetDirs(
+            dirs.iter().map(|(eid, _, to)| (*eid, *to)).collect(),
+        ));
+        edit
+    }
+
+    /// Rocks/fields were changed in place, i.e. their kind or strength
+    pub(super) fn set_groups(changes: Vec<(GroupSnapshot, GroupSnapshot)>) -> Self {
+        let (befores, afters) = changes.into_iter().unzip();
+        Self {
+            undo: vec![EStep::SetGroups(befores)],
+            redo: vec![EStep::SetGroups(afters)],
+        }
+    }
+
+    /// Portals were changed in place, i.e. which way they face
+    pub(super) fn set_portals(changes: Vec<(Entity, EPortal, EPortal)>) -> Self {
+        Self {
+            undo: vec![EStep::SetPortals(
+                changes
+                    .iter()
+                    .map(|(eid, before, _)| (*eid, before.clone()))
+                    .collect(),
+            )],
+            redo: vec![EStep::SetPortals(
+                changes
+                    .into_iter()
+                    .map(|(eid, _, after)| (eid, after))
+                    .collect(),
+            )],
+        }
+    }
+
+    /// Switches were pointed at (or away from) rocks/fields, which might have needed a target
+    pub(super) fn change_switches(
+        switches: Vec<(Entity, ESwitch, ESwitch)>,
+        group_changes: Vec<(GroupSnapshot, GroupSnapshot)>,
+    ) -> Self {
+        let mut edit = Self::set_groups(group_changes);
+        edit.undo.push(EStep::SetSwitches(
+            switches
+                .iter()
+                .map(|(eid, before, _)| (*eid, before.clone()))
+                .collect(),
+        ));
+        edit.redo.push(EStep::SetSwitches(
+            switches
+                .into_iter()
+                .map(|(eid, _, after)| (eid, after))
+                .collect(),
+        ));
+        edit
+    }
+
+    /// A new (empty) rock or field was created
+    pub(super) fn create_group(group: GroupSnapshot, emode: EditingMode) -> Self {
+        Self {
+            undo: vec![
+                EStep::DespawnGroups(vec![group.eid]),
+                EStep::SetMode(EditingMode::Free),
+            ],
+            redo: vec![EStep::SetGroups(vec![group]), EStep::SetMode(emode)],
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub(super) struct EHistory {
+    undo: Vec<EEdit>,
+    redo: Vec<EEdit>,
+    /// Entities that were despawned by one step and respawned by another
+    remap: HashMap<Entity, Entity>,
+}
+impl EHistory {
+    fn resolve(&self, mut eid: Entity) -> Entity {
+        while let Some(next) = self.remap.get(&eid) {
+            eid = *next;
+        }
+        eid
+    }
+
+    fn replace(&mut self, old: Entity, new: Entity) {
+        let old = self.resolve(old);
+        if old != new {
+            self.remap.insert(old, new);
+        }
+    }
+
+    fn resolve_mode(&self, emode: EditingMode) -> EditingMode {
+        match emode {
+            EditingMode::Free => EditingMode::Free,
+            EditingMode::CreatingRock(eid) => EditingMode::CreatingRock(self.resolve(eid)),
+            EditingMode::EditingRock(eid) => EditingMode::EditingRock(self.resolve(eid)),
+            EditingMode::CreatingField(eid) => EditingMode::CreatingField(self.resolve(eid)),
+            EditingMode::EditingField(eid) => EditingMode::EditingField(self.resolve(eid)),
+            EditingMode::PlacingSegment(_)
+            | EditingMode::PlacingReplenish
+            | EditingMode::PlacingSwitch
+            | EditingMode::PlacingStardust
+            | EditingMode::PlacingPortal
+            | EditingMode::PlacingStart
+            | EditingMode::PlacingGoal => emode,
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        *self = default();
+    }
+}
+
+/// Records an edit once the commands that make it have been applied
+pub(super) fn record_edit(commands: &mut Commands, edit: EEdit) {
+    commands.add(move |world: &mut World| {
+        let mut history = world.resource_mut::<EHistory>();
+        history.undo.push(edit);
+        history.redo.clear();
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum HistoryDirection {
+    Undo,
+    Redo,
+}
+
+pub(super) fn step_history(
+    In(direction): In<HistoryDirection>,
+    mut history: ResMut<EHistory>,
+    mut commands: Commands,
+    eroot: Res<ERootEid>,
+    mut points_q: Query<&mut IntMoveable, With<EPoint>>,
+    mut groups_q: Query<(&mut EPointGroup, Option<&mut ERock>, Option<&mut EField>)>,
+    mut meta_writer: EventWriter<SetMetaState>,
+) {
+    let edit = match direction {
+        HistoryDirection::Undo => history.undo.pop(),
+        HistoryDirection::Redo => history.redo.pop(),
+    };
+    let Some(edit) = edit else {
+        return;
+    };
+    let steps = match direction {
+        HistoryDirection::Undo => &edit.undo,
+        HistoryDirection::Redo => &edit.redo,
+    };
+    for step in steps {
+        match step {
+            EStep::SetPoses(poses) => {
+                for (eid, pos) in poses {
+                    if let Ok(mut mv) = points_q.get_mut(history.resolve(*eid)) {
+                        mv.fpos = pos.as_vec2().extend(mv.fpos.z);
+                    }
+                }
+            }
+            EStep::SpawnPoints(points) => {
+                commands.entity(eroot.0).with_children(|parent| {
+                    for point in points {
+                        let new_eid = match &point.goodie {
+                            None => parent.spawn(EPointBundle::new(point.pos)).id(),
+                            Some(Goodie::Start) => spawn_start(parent, point.pos),
+                            Some(Goodie::Goal) => spawn_goal(parent, point.pos),
+                            Some(Goodie::Replenish) => spawn_replenish(parent, point.pos),
+                            Some(Goodie::Switch(eswitch)) => {
+                                spawn_switch(parent, point.pos, eswitch.clone())
+                            }
+                            Some(Goodie::Stardust(estardust)) => {
+                                spawn_stardust(parent, point.pos, estardust.clone())
+                            }
+                        };
+                        history.replace(point.eid, new_eid);
+                    }
+                });
+            }
+            EStep::DespawnPoints(eids)
+            | EStep::DespawnGroups(eids)
+            | EStep::DespawnSegments(eids)
+            | EStep::DespawnPortals(eids) => {
+                for eid in eids {
+                    if let Some(commands) = commands.get_entity(history.resolve(*eid)) {
+                        commands.despawn_recursive();
+                    }
+                }
+            }
+            EStep::SetGroups(groups) => {
+                for group in groups {
+                    let pg = EPointGroup {
+                        eids: group
+                            .pg
+                            .eids
+                            .iter()
+                            .map(|eid| history.resolve(*eid))
+                            .collect(),
+                        ..group.pg.clone()
+                    };
+                    if let Ok((mut existing, erock, efield)) =
+                        groups_q.get_mut(history.resolve(group.eid))
+                    {
+                        *existing = pg;
+                        match (&group.kind, erock, efield) {
+                            (GroupKind::Rock(snapshot), Some(mut erock), _) => {
+                                erock.kind = snapshot.kind;
+                                erock.bounciness = snapshot.bounciness;
+                                erock.z = snapshot.z;
+                                erock.path = snapshot.path.clone();
+                                erock.rotation = snapshot.rotation.clone();
+                                erock.target = snapshot.target.clone();
+                            }
+                            (GroupKind::Field(snapshot), _, Some(mut efield)) => {
+                                efield.strength = snapshot.strength;
+                                efield.drag = snapshot.drag;
+                                efield.well = snapshot.well.clone();
+                                efield.target = snapshot.target.clone();
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    let mut new_eid = Entity::PLACEHOLDER;
+                    commands.entity(eroot.0).with_children(|parent| {
+                        new_eid = match &group.kind {
+                            GroupKind::Rock(erock) => {
+                                parent.spawn(ERockBundle::new(erock.clone(), pg)).id()
+                            }
+                            GroupKind::Field(efield) => {
+                                parent.spawn(EFieldBundle::new(efield.clone(), pg)).id()
+                            }
+                        };
+                    });
+                    history.replace(group.eid, new_eid);
+                }
+            }
+            EStep::SpawnSegments(segments) => {
+                for (eid, segment) in segments {
+                    if commands.get_entity(history.resolve(*eid)).is_some() {
+                        continue;
+                    }
+                    let segment = ESegment {
+                        left: history.resolve(segment.left),
+                        right: history.resolve(segment.right),
+                        ..segment.clone()
+                    };
+                    let mut new_eid = Entity::PLACEHOLDER;
+                    commands.entity(eroot.0).with_children(|parent| {
+                        new_eid = parent.spawn((Name::new("segment"), segment)).id();
+                    });
+                    history.replace(*eid, new_eid);
+                }
+            }
+            EStep::SetPortals(portals) => {
+                for (eid, portal) in portals {
+                    let portal = EPortal {
+                        a: history.resolve(portal.a),
+                        b: history.resolve(portal.b),
+                        ..portal.clone()
+                    };
+                    if let Some(mut existing) = commands.get_entity(history.resolve(*eid)) {
+                        existing.insert(portal);
+                        continue;
+                    }
+                    let mut new_eid = Entity::PLACEHOLDER;
+                    commands.entity(eroot.0).with_children(|parent| {
+                        new_eid = parent.spawn((Name::new("portal"), portal)).id();
+                    });
+                    history.replace(*eid, new_eid);
+                }
+            }
+            EStep::SetSwitches(switches) => {
+                for (eid, eswitch) in switches {
+                    if let Some(mut existing) = commands.get_entity(history.resolve(*eid)) {
+                        existing.insert(eswitch.clone());
+                    }
+                }
+            }
+            EStep::SetDirs(dirs) => {
+                for (eid, dir) in dirs {
+                    if let Ok((_, _, Some(mut efield))) = groups_q.get_mut(history.resolve(*eid)) {
+                        efield.dir = *dir;
+                    }
+                }
+            }
+            EStep::SetMode(emode) => {
+                // Never leave the editor pointing at a group that's gone
+                let emode = match history.resolve_mode(*emode) {
+                    EditingMode::CreatingRock(eid)
+                    | EditingMode::EditingRock(eid)
+                    | EditingMode::CreatingField(eid)
+                    | EditingMode::EditingField(eid)
+                        if commands.get_entity(eid).is_none() =>
+                    {
+                        EditingMode::Free
+                    }
+                    emode => emode,
+                };
+                meta_writer.send(SetMetaState(emode.to_meta_state()));
+            }
+        }
+    }
+    match direction {
+        HistoryDirection::Undo => history.redo.push(edit),
+        HistoryDirection::Redo => history.undo.push(edit),
+    }
+}
+
+#[cfg(test)]
+mod ehistory_tests {
+    use super::*;
+    use crate::{
+        editor::{
+            epoint::{cleanup_points, delete_points},
+            esave::{crystallize_level_data, load_level_data},
+        },
+        meta::{game_state::GameState, old_level_data::LevelData},
+    };
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// Respawned rocks can come back in a different order
+    fn crystallize_sorted(world: &mut World) -> LevelData {
+        let mut level_data = world.run_system_once(crystallize_level_data).unwrap();
+        level_data
+            .rocks
+            .sort_by_key(|rock| rock.points.first().map(|p| (p.x, p.y)));
+        level_data
+    }
+
+    #[test]
+    fn deleting_a_rock_can_be_undone() {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        world.insert_resource(GameState {
+            meta: EditingMode::Free.to_meta_state(),
+            pause: None,
+        });
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        let level_data = LevelData::read_from_disk("basic_1").unwrap();
+        world.run_system_once_with(level_data, load_level_data);
+        let before = crystallize_sorted(&mut world);
+
+        let rock_points = world
+            .query_filtered::<&EPointGroup, With<ERock>>()
+            .iter(&world)
+            .next()
+            .unwrap()
+            .eids
+            .clone();
+        world.run_system_once_with(rock_points, delete_points);
+        world.run_system_once(cleanup_points);
+        let after = crystallize_sorted(&mut world);
+        assert_eq!(after.rocks.len(), before.rocks.len() - 1);
+
+        world.run_system_once_with(HistoryDirection::Undo, step_history);
+        world.run_system_once(cleanup_points);
+        assert_eq!(crystallize_sorted(&mut world), before);
+
+        // Redo, then undo again so the respawned points get respawned
+        world.run_system_once_with(HistoryDirection::Redo, step_history);
+        world.run_system_once(cleanup_points);
+        assert_eq!(crystallize_sorted(&mut world), after);
+        world.run_system_once_with(HistoryDirection::Undo, step_history);
+        world.run_system_once(cleanup_points);
+        assert_eq!(crystallize_sorted(&mut world), before);
+    }
+}
@@ -6,7 +6,10 @@ use crate::{
 };
 
 use super::{
+    eclipboard::ETransform,
+    ehistory::HistoryDirection,
     epoint::{EPoint, ESelected},
+    esnap::ESnap,
     oneshots::EOneshots,
 };
 
@@ -19,6 +22,7 @@ pub(super) fn watch_dramatic_editing_input(
     mouse: Res<MouseState>,
     keyboard: Res<ButtonInput<KeyCode>>,
     selected_points: Query<Entity, (With<EPoint>, With<ESelected>)>,
+    snap: Res<ESnap>,
 ) {
     // NOTE: We return after doing any "real work" to maintain the nice-to-have
     // invariant that only one dramatic thing happens per frame
@@ -26,8 +30,72 @@ pub(super) fn watch_dramatic_editing_input(
         warn!("In order to watch editor input, we must have an editing mode. Gs: {gs:?}");
         return;
     };
+    let snapped_pos = snap.snap(mouse.world_pos.as_vec2());
+    let ctrl = keyboard.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if ctrl && keyboard.just_pressed(KeyCode::KeyZ) {
+        let direction = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            HistoryDirection::Redo
+        } else {
+            HistoryDirection::Undo
+        };
+        commands.run_system_with_input(oneshots.step_history, direction);
+        return;
+    }
+    if ctrl && keyboard.just_pressed(KeyCode::KeyA) {
+        commands.run_system(oneshots.select_group);
+        return;
+    }
+    if ctrl && keyboard.just_pressed(KeyCode::KeyC) {
+        commands.run_system(oneshots.copy_selection);
+        return;
+    }
+    if ctrl && keyboard.just_pressed(KeyCode::KeyV) {
+        commands.run_system_with_input(oneshots.paste_clipboard, snapped_pos);
+        return;
+    }
+    if ctrl && keyboard.just_pressed(KeyCode::KeyD) {
+        commands.run_system(oneshots.duplicate_selection);
+        return;
+    }
+    if ctrl {
+        // Everything below is a plain key
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        // Test from here
+        commands.run_system_with_input(oneshots.start_testing, Some(Vec2::ZERO));
+        return;
+    }
+    let transform = if keyboard.just_pressed(KeyCode::KeyH) {
+        Some(ETransform::MirrorHorizontal)
+    } else if keyboard.just_pressed(KeyCode::KeyV) {
+        Some(ETransform::MirrorVertical)
+    } else if keyboard.just_pressed(KeyCode::KeyR) {
+        Some(ETransform::Rotate)
+    } else {
+        None
+    };
+    if let Some(transform) = transform {
+        commands.run_system_with_input(oneshots.transform_selection, transform);
+        return;
+    }
     if mouse.button_input.just_pressed(MouseButton::Right) {
-        commands.run_system_with_input(oneshots.spawn_point, (emode, mouse.world_pos));
+        let oneshot = match emode {
+            EditingMode::PlacingSegment(_)
+            | EditingMode::PlacingReplenish
+            | EditingMode::PlacingSwitch
+            | EditingMode::PlacingStardust
+            | EditingMode::PlacingPortal
+            | EditingMode::PlacingStart
+            | EditingMode::PlacingGoal => oneshots.place_goodie,
+            _ => oneshots.spawn_point,
+        };
+        commands.run_system_with_input(oneshot, (emode, snapped_pos));
         return;
     }
     if keyboard.just_pressed(KeyCode::Backspace) {
@@ -37,13 +105,13 @@ pub(super) fn watch_dramatic_editing_input(
     }
     if keyboard.just_pressed(KeyCode::KeyP) {
         if let EditingMode::Free = emode {
-            commands.run_system(oneshots.spawn_rock);
+            commands.run_system_with_input(oneshots.spawn_rock, default());
             return;
         }
     }
     if keyboard.just_pressed(KeyCode::KeyF) {
         if let EditingMode::Free = emode {
-            commands.run_system(oneshots.spawn_field);
+            commands.run_system_with_input(oneshots.spawn_field, default());
             return;
         }
     }
@@ -0,0 +1,306 @@
+//! A panel in the top right that shows (and edits) the properties of the rock/field being edited.
+//! Up/down picks a row, left/right changes it. On vertex rows left/right moves the point along
+//! x, and holding shift moves it along y instead.
+
+use bevy::prelude::*;
+
+use crate::{
+    environment::{
+        field::{FieldDrag, FieldStrength},
+        rock::RockKind,
+    },
+    math::ToIVec2,
+    meta::{
+        consts::MENU_GROWTH_F32,
+        game_state::{EditingMode, GameState},
+    },
+    physics::dyno::IntMoveable,
+};
+
+use super::{
+    efield::EField,
+    ehistory::{record_edit, EEdit, GroupSnapshot},
+    epoint::{EPoint, EPointGroup},
+    erock::ERock,
+    help::{HelpTextBg, HelpTextFg},
+};
+
+/// How far left/right turns a field
+const ANGLE_STEP_DEGREES: f32 = 15.0;
+/// Height of a single row of text, in screen space
+const ROW_HEIGHT: u32 = 6;
+const PANEL_WIDTH: u32 = 70;
+
+#[derive(Resource, Debug, Default)]
+pub(super) struct EInspector {
+    /// The rock/field being inspected. The cursor goes back to the top when this changes
+    target: Option<Entity>,
+    row: usize,
+}
+
+#[derive(Component, Debug)]
+pub(super) struct EInspectorRoot;
+#[derive(Component, Debug)]
+pub(super) struct EInspectorBg;
+#[derive(Component, Debug)]
+pub(super) struct EInspectorFg;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EInspectorRow {
+    RockKind,
+    Z,
+    FieldAngle,
+    FieldStrength,
+    FieldDrag,
+    Vertex(usize),
+}
+
+fn rows(is_rock: bool, num_points: usize) -> Vec<EInspectorRow> {
+    let mut rows = if is_rock {
+        vec![EInspectorRow::RockKind, EInspectorRow::Z]
+    } else {
+        vec![
+            EInspectorRow::FieldAngle,
+            EInspectorRow::FieldStrength,
+            EInspectorRow::FieldDrag,
+        ]
+    };
+    rows.extend((0..num_points).map(EInspectorRow::Vertex));
+    rows
+}
+
+/// The rock/field that's being edited, if there is one
+fn inspected(gs: &GameState) -> Option<Entity> {
+    match gs.get_editing_mode() {
+        Some(EditingMode::EditingRock(eid) | EditingMode::EditingField(eid)) => Some(eid),
+        _ => None,
+    }
+}
+
+/// The option `offset` steps away from `current`, wrapping around
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, offset: i32) -> T {
+    let ix = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0) as i32;
+    options[(ix + offset).rem_euclid(options.len() as i32) as usize]
+}
+
+/// Field directions are shown (and changed) as an angle in degrees, snapped to the step
+fn field_angle(dir: Vec2) -> f32 {
+    let degrees = dir.to_angle().to_degrees();
+    (degrees / ANGLE_STEP_DEGREES).round() * ANGLE_STEP_DEGREES
+}
+
+pub(super) fn spawn_inspector(parent: &mut ChildBuilder) {
+    parent
+        .spawn((
+            Name::new("inspector"),
+            EInspectorRoot,
+            SpatialBundle {
+                transform: Transform::from_translation(
+                    Vec3::new(120.0, 85.0, 0.0) * MENU_GROWTH_F32,
+                ),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Name::new("inspector_bg"),
+                EInspectorBg,
+                HelpTextBg::new(
+                    Color::ANTIQUE_WHITE,
+                    IVec3::ZERO,
+                    UVec2::new(PANEL_WIDTH, ROW_HEIGHT),
+                )
+                .into_bundle(),
+            ))
+            .with_children(|bg| {
+                bg.spawn((
+                    Name::new("inspector_fg"),
+                    EInspectorFg,
+                    HelpTextFg::new(Color::BLACK, 36.0, "".into()).into_bundle(),
+                ));
+            });
+        });
+}
+
+pub(super) fn watch_inspector_input(
+    gs: Res<GameState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut inspector: ResMut<EInspector>,
+    mut rocks_q: Query<(&mut ERock, &EPointGroup), Without<EField>>,
+    mut fields_q: Query<(&mut EField, &EPointGroup), Without<ERock>>,
+    mut points_q: Query<&mut IntMoveable, With<EPoint>>,
+    mut commands: Commands,
+) {
+    let Some(eid) = inspected(&gs) else {
+        inspector.target = None;
+        return;
+    };
+    if inspector.target != Some(eid) {
+        inspector.target = Some(eid);
+        inspector.row = 0;
+    }
+    let (is_rock, pg) = match (rocks_q.get(eid), fields_q.get(eid)) {
+        (Ok((_, pg)), _) => (true, pg.clone()),
+        (_, Ok((_, pg))) => (false, pg.clone()),
+        _ => return,
+    };
+    let rows = rows(is_rock, pg.eids.len());
+    if keyboard.just_pressed(KeyCode::ArrowUp) {
+        inspector.row = inspector.row.saturating_sub(1);
+    }
+    if keyboard.just_pressed(KeyCode::ArrowDown) {
+        inspector.row += 1;
+    }
+    inspector.row = inspector.row.min(rows.len().saturating_sub(1));
+    let offset = match (
+        keyboard.just_pressed(KeyCode::ArrowLeft),
+        keyboard.just_pressed(KeyCode::ArrowRight),
+    ) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    let Some(row) = rows.get(inspector.row) else {
+        return;
+    };
+    let edit = match *row {
+        EInspectorRow::RockKind | EInspectorRow::Z => {
+            let Ok((mut erock, _)) = rocks_q.get_mut(eid) else {
+                return;
+            };
+            let before = GroupSnapshot::new(eid, &pg, Some(&*erock), None);
+            if *row == EInspectorRow::RockKind {
+                erock.kind = cycle(&RockKind::ALL, erock.kind, offset);
+            } else {
+                erock.z += offset;
+            }
+            let after = GroupSnapshot::new(eid, &pg, Some(&*erock), None);
+            EEdit::set_groups(before.zip(after).into_iter().collect())
+        }
+        EInspectorRow::FieldAngle => {
+            let Ok((mut efield, _)) = fields_q.get_mut(eid) else {
+                return;
+            };
+            let from = efield.dir;
+            let degrees = field_angle(from) + offset as f32 * ANGLE_STEP_DEGREES;
+            efield.dir = Vec2::from_angle(degrees.to_radians());
+            EEdit::transform_points(vec![], vec![], vec![(eid, from, efield.dir)], vec![])
+        }
+        EInspectorRow::FieldStrength | EInspectorRow::FieldDrag => {
+            let Ok((mut efield, _)) = fields_q.get_mut(eid) else {
+                return;
+            };
+            let before = GroupSnapshot::new(eid, &pg, None, Some(&*efield));
+            if *row == EInspectorRow::FieldStrength {
+                efield.strength = cycle(&FieldStrength::ALL, efield.strength, offset);
+            } else {
+                efield.drag = cycle(&FieldDrag::ALL, efield.drag, offset);
+            }
+            let after = GroupSnapshot::new(eid, &pg, None, Some(&*efield));
+            EEdit::set_groups(before.zip(after).into_iter().collect())
+        }
+        EInspectorRow::Vertex(ix) => {
+            let Ok(mut mv) = points_q.get_mut(pg.eids[ix]) else {
+                return;
+            };
+            let from = mv.fpos.truncate().to_ivec2();
+            let to = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                from + IVec2::Y * offset
+            } else {
+                from + IVec2::X * offset
+            };
+            mv.fpos = to.as_vec2().extend(mv.fpos.z);
+            EEdit::move_points(vec![(pg.eids[ix], from, to)])
+        }
+    };
+    record_edit(&mut commands, edit);
+}
+
+pub(super) fn update_inspector(
+    gs: Res<GameState>,
+    inspector: Res<EInspector>,
+    rocks_q: Query<(&ERock, &EPointGroup)>,
+    fields_q: Query<(&EField, &EPointGroup)>,
+    mut root_q: Query<&mut Visibility, With<EInspectorRoot>>,
+    mut bg_q: Query<&mut HelpTextBg, With<EInspectorBg>>,
+    mut fg_q: Query<&mut HelpTextFg, With<EInspectorFg>>,
+) {
+    let (Ok(mut visibility), Ok(mut bg), Ok(mut fg)) = (
+        root_q.get_single_mut(),
+        bg_q.get_single_mut(),
+        fg_q.get_single_mut(),
+    ) else {
+        return;
+    };
+    let erock = inspected(&gs).and_then(|eid| rocks_q.get(eid).ok());
+    let efield = inspected(&gs).and_then(|eid| fields_q.get(eid).ok());
+    // Same order as `rows`
+    let (title, properties, pg) = match (erock, efield) {
+        (Some((erock, pg)), _) => (
+            "rock",
+            vec![format!("kind: {}", erock.kind), format!("z: {}", erock.z)],
+            pg,
+        ),
+        (_, Some((efield, pg))) => (
+            "field",
+            vec![
+                format!("angle: {:.0}", efield.dir.to_angle().to_degrees()),
+                format!("strength: {}", efield.strength),
+                format!("drag: {}", efield.drag),
+            ],
+            pg,
+        ),
+        _ => {
+            *visibility = Visibility::Hidden;
+            return;
+        }
+    };
+    let vertices = pg
+        .poses
+        .iter()
+        .enumerate()
+        .map(|(ix, pos)| format!("v{ix}: ({}, {})", pos.x, pos.y));
+    let mut lines = vec![title.to_string()];
+    for (ix, value) in properties.into_iter().chain(vertices).enumerate() {
+        let cursor = if ix == inspector.row { ">" } else { " " };
+        lines.push(format!("{cursor} {value}"));
+    }
+    let height = ROW_HEIGHT * lines.len() as u32 + 4;
+    bg.dims = UVec2::new(PANEL_WIDTH, height);
+    bg.pos = IVec3::new(0, -(height as i32) / 2, 0);
+    fg.content = lines.join("\n");
+    *visibility = Visibility::Inherited;
+}
+
+#[cfg(test)]
+mod einspector_tests {
+    use super::*;
+
+    #[test]
+    fn cycling_wraps_around() {
+        assert_eq!(
+            cycle(&RockKind::ALL, RockKind::Normal, -1),
+            RockKind::MagLev
+        );
+        assert_eq!(cycle(&RockKind::ALL, RockKind::MagLev, 1), RockKind::Normal);
+        assert_eq!(
+            cycle(&RockKind::ALL, RockKind::Normal, 1),
+            RockKind::SimpleKill
+        );
+        assert_eq!(field_angle(Vec2::new(1.0, 1.02)), 45.0);
+        assert_eq!(
+            rows(false, 2),
+            vec![
+                EInspectorRow::FieldAngle,
+                EInspectorRow::FieldStrength,
+                EInspectorRow::FieldDrag,
+                EInspectorRow::Vertex(0),
+                EInspectorRow::Vertex(1),
+            ]
+        );
+    }
+}
@@ -1,13 +1,21 @@
-use bevy::{prelude::*, utils::hashbrown::HashMap};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::hashbrown::HashMap};
 
 use crate::{
     drawing::animation::{AnimationManager, MultiAnimationManager, SpriteInfo},
     input::MouseState,
+    math::ToIVec2,
     meta::game_state::{EditingMode, GameState, SetMetaState},
     physics::dyno::{IntMoveable, IntMoveableBundle},
 };
 
-use super::{efield::EField, erock::ERock, transitions::ERootEid};
+use super::{
+    efield::EField,
+    egoodies::{EGoal, EPortal, EReplenish, ESegment, EStardust, EStart, ESwitch},
+    ehistory::{record_edit, EEdit, Goodie, GroupSnapshot, PointSnapshot},
+    erock::ERock,
+    esnap::ESnap,
+    transitions::ERootEid,
+};
 
 #[derive(Component, Debug, Clone, Reflect)]
 pub struct EPoint {
@@ -50,10 +58,47 @@ pub struct ESelected {
 }
 
 #[derive(Bundle)]
-struct EPointBundle {
+pub(super) struct EPointBundle {
     name: Name,
     point: EPoint,
     mv: IntMoveableBundle,
+    multi: MultiAnimationManager,
+}
+impl EPointBundle {
+    pub(super) fn new(world_pos: IVec2) -> Self {
+        let anim = AnimationManager::from_static_pairs(vec![
+            (
+                "none",
+                SpriteInfo {
+                    path: "sprites/editor/point.png".into(),
+                    size: UVec2::new(8, 8),
+                    ..default()
+                },
+            ),
+            (
+                "hovered",
+                SpriteInfo {
+                    path: "sprites/editor/point_hovered.png".into(),
+                    size: UVec2::new(8, 8),
+                    ..default()
+                },
+            ),
+            (
+                "selected",
+                SpriteInfo {
+                    path: "sprites/editor/point_selected.png".into(),
+                    size: UVec2::new(8, 8),
+                    ..default()
+                },
+            ),
+        ]);
+        Self {
+            name: Name::new("point"),
+            point: EPoint { size: 6.0 },
+            mv: IntMoveableBundle::new(world_pos.extend(51)),
+            multi: MultiAnimationManager::well_lit(anim),
+        }
+    }
 }
 
 /// Given a point group, determine at what index a new point should be inserted
@@ -95,8 +140,8 @@ pub(super) fn spawn_point(
     In((emode, world_pos)): In<(EditingMode, IVec2)>,
     mut commands: Commands,
     hover_q: Query<Entity, With<EHovered>>,
-    mut rocks_q: Query<&mut EPointGroup, (With<ERock>, Without<EField>)>,
-    mut fields_q: Query<&mut EPointGroup, (Without<ERock>, With<EField>)>,
+    mut rocks_q: Query<(&ERock, &mut EPointGroup), Without<EField>>,
+    mut fields_q: Query<(&EField, &mut EPointGroup), Without<ERock>>,
     points_q: Query<&IntMoveable, With<EPoint>>,
     eroot: Res<ERootEid>,
     mut meta_writer: EventWriter<SetMetaState>,
@@ -104,7 +149,7 @@ pub(super) fn spawn_point(
 ) {
     // Handle the edge case where we're closing a rock
     if let EditingMode::CreatingRock(eid) = emode {
-        let rock = rocks_q.get(eid).unwrap();
+        let (_, rock) = rocks_q.get(eid).unwrap();
         if !rock.eids.is_empty() && hover_q.contains(rock.eids[0]) {
             meta_writer.send(SetMetaState(EditingMode::EditingRock(eid).to_meta_state()));
             // Don't actually spawn a point
@@ -113,7 +158,7 @@ pub(super) fn spawn_point(
     }
     // Handle the edge case where we're closing a field
     if let EditingMode::CreatingField(eid) = emode {
-        let field = fields_q.get(eid).unwrap();
+        let (_, field) = fields_q.get(eid).unwrap();
         if !field.eids.is_empty() && hover_q.contains(field.eids[0]) {
             meta_writer.send(SetMetaState(EditingMode::EditingField(eid).to_meta_state()));
             // Don't actually spawn a point
@@ -122,38 +167,10 @@ pub(super) fn spawn_point(
     }
 
     // First just spawn a point and record the id
-    let anim = AnimationManager::from_static_pairs(vec![
-        (
-            "none",
-            SpriteInfo {
-                path: "sprites/editor/point.png".into(),
-                size: UVec2::new(8, 8),
-                ..default()
-            },
-        ),
-        (
-            "hovered",
-            SpriteInfo {
-                path: "sprites/editor/point_hovered.png".into(),
-                size: UVec2::new(8, 8),
-                ..default()
-            },
-        ),
-        (
-            "selected",
-            SpriteInfo {
-                path: "sprites/editor/point_selected.png".into(),
-                size: UVec2::new(8, 8),
-                ..default()
-            },
-        ),
-    ]);
-    let multi = MultiAnimationManager::well_lit(anim);
-    let point_bund = EPointBundle {
-        name: Name::new("point"),
-        point: EPoint { size: 6.0 },
-        mv: IntMoveableBundle::new(world_pos.extend(51)),
-    };
+    let point_bund = EPointBundle::new(world_pos);
+    // What changed, for the history
+    let mut spawned = None;
+    let mut group_change = None;
     commands.entity(eroot.0).with_children(|eroot| {
         // Then perform interesting work depending on the editor state
         let hovered_eid = hover_q.iter().next();
@@ -161,42 +178,150 @@ pub(super) fn spawn_point(
             Some(hovered_eid) => hovered_eid,
             None => {
                 // NOTE: Spawn happens here
-                eroot.spawn((point_bund, multi)).id()
+                let new_eid = eroot.spawn(point_bund).id();
+                spawned = Some(PointSnapshot {
+                    eid: new_eid,
+                    pos: world_pos,
+                    goodie: None,
+                });
+                new_eid
             }
         };
         match emode {
-            EditingMode::Free => {
+            EditingMode::Free
+            | EditingMode::PlacingSegment(_)
+            | EditingMode::PlacingReplenish
+            | EditingMode::PlacingSwitch
+            | EditingMode::PlacingStardust
+            | EditingMode::PlacingPortal
+            | EditingMode::PlacingStart
+            | EditingMode::PlacingGoal => {
                 // Just a bare spawn, nothing else to do
             }
             EditingMode::CreatingRock(eid) => {
-                let mut pg = rocks_q.get_mut(eid).unwrap();
+                let (erock, mut pg) = rocks_q.get_mut(eid).unwrap();
+                let before = GroupSnapshot::new(eid, &pg, Some(erock), None);
                 pg.eids.push(new_eid);
+                group_change = before.zip(GroupSnapshot::new(eid, &pg, Some(erock), None));
             }
             EditingMode::EditingRock(eid) => {
                 if keyboard.pressed(KeyCode::KeyF) {
                     // We're actually just spawning a regular point
                     // Nothing left to do.
                 } else {
-                    let mut pg = rocks_q.get_mut(eid).unwrap();
+                    let (erock, mut pg) = rocks_q.get_mut(eid).unwrap();
+                    let before = GroupSnapshot::new(eid, &pg, Some(erock), None);
                     let pos = get_insertion_ix(&pg, world_pos, &points_q);
-                    pg.insert_point(pos, new_eid, world_pos)
+                    pg.insert_point(pos, new_eid, world_pos);
+                    group_change = before.zip(GroupSnapshot::new(eid, &pg, Some(erock), None));
                 }
             }
             EditingMode::CreatingField(eid) => {
-                let mut pg = fields_q.get_mut(eid).unwrap();
+                let (efield, mut pg) = fields_q.get_mut(eid).unwrap();
+                let before = GroupSnapshot::new(eid, &pg, None, Some(efield));
                 pg.eids.push(new_eid);
+                group_change = before.zip(GroupSnapshot::new(eid, &pg, None, Some(efield)));
             }
             EditingMode::EditingField(eid) => {
-                let mut pg = fields_q.get_mut(eid).unwrap();
+                let (efield, mut pg) = fields_q.get_mut(eid).unwrap();
+                let before = GroupSnapshot::new(eid, &pg, None, Some(efield));
                 let pos = get_insertion_ix(&pg, world_pos, &points_q);
-                pg.insert_point(pos, new_eid, world_pos)
+                pg.insert_point(pos, new_eid, world_pos);
+                group_change = before.zip(GroupSnapshot::new(eid, &pg, None, Some(efield)));
             }
         }
     });
+    if spawned.is_some() || group_change.is_some() {
+        record_edit(
+            &mut commands,
+            EEdit::spawn_point(emode, spawned, group_change),
+        );
+    }
+}
+
+/// Which (if any) of the single-point goodies a point is
+type GoodieFlags<'a> = (
+    Has<EStart>,
+    Has<EGoal>,
+    Has<EReplenish>,
+    Option<&'a ESwitch>,
+    Option<&'a EStardust>,
+);
+
+/// Everything built on top of points, which goes down with them when they're deleted
+#[derive(SystemParam)]
+pub(super) struct EPointDependents<'w, 's> {
+    groups_q: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static EPointGroup,
+            Option<&'static ERock>,
+            Option<&'static EField>,
+        ),
+    >,
+    segments_q: Query<'w, 's, (Entity, &'static ESegment)>,
+    portals_q: Query<'w, 's, (Entity, &'static EPortal)>,
 }
 
 /// Deletes a vector of points by Entity
-pub(super) fn delete_points(In(eids): In<Vec<Entity>>, mut commands: Commands) {
+pub(super) fn delete_points(
+    In(eids): In<Vec<Entity>>,
+    mut commands: Commands,
+    gs: Res<GameState>,
+    points_q: Query<&IntMoveable, With<EPoint>>,
+    goodies_q: Query<GoodieFlags>,
+    dependents: EPointDependents,
+) {
+    let EPointDependents {
+        groups_q,
+        segments_q,
+        portals_q,
+    } = dependents;
+    // Remember everything this is about to take down with it, so it can be undone
+    let mut points = vec![];
+    for eid in eids.iter() {
+        let (Ok(mv), Ok(goodie_flags)) = (points_q.get(*eid), goodies_q.get(*eid)) else {
+            continue;
+        };
+        let goodie = match goodie_flags {
+            (true, _, _, _, _) => Some(Goodie::Start),
+            (_, true, _, _, _) => Some(Goodie::Goal),
+            (_, _, true, _, _) => Some(Goodie::Replenish),
+            (_, _, _, Some(eswitch), _) => Some(Goodie::Switch(eswitch.clone())),
+            (_, _, _, _, Some(estardust)) => Some(Goodie::Stardust(estardust.clone())),
+            _ => None,
+        };
+        points.push(PointSnapshot {
+            eid: *eid,
+            pos: mv.get_ipos().truncate(),
+            goodie,
+        });
+    }
+    let groups = groups_q
+        .iter()
+        .filter(|(_, pg, _, _)| pg.eids.iter().any(|eid| eids.contains(eid)))
+        .filter_map(|(eid, pg, erock, efield)| GroupSnapshot::new(eid, pg, erock, efield))
+        .collect();
+    let segments = segments_q
+        .iter()
+        .filter(|(_, segment)| eids.contains(&segment.left) || eids.contains(&segment.right))
+        .map(|(eid, segment)| (eid, segment.clone()))
+        .collect();
+    let portals = portals_q
+        .iter()
+        .filter(|(_, portal)| eids.contains(&portal.a) || eids.contains(&portal.b))
+        .map(|(eid, portal)| (eid, portal.clone()))
+        .collect();
+    if !points.is_empty() {
+        let emode = gs.get_editing_mode().unwrap_or(EditingMode::Free);
+        record_edit(
+            &mut commands,
+            EEdit::delete_points(emode, points, groups, segments, portals),
+        );
+    }
+
     for eid in eids {
         if let Some(commands) = commands.get_entity(eid) {
             commands.despawn_recursive();
@@ -264,6 +389,13 @@ pub(super) fn select_points(
             }
         }
     } else {
+        // Grabbing a point that's already selected grabs the whole selection
+        if points_q
+            .iter()
+            .any(|data| data.1.is_some() && data.2.is_some())
+        {
+            return;
+        }
         // All hovered points become selected. The rest become deselected
         next_order = 0;
         for data in points_q.iter() {
@@ -312,6 +444,79 @@ pub(super) fn select_points(
     }
 }
 
+/// Dragging from empty space selects every point inside the box (on top of any existing
+/// selection, since a clean press in empty space already cleared it)
+pub(super) fn box_select_points(
+    mouse: Res<MouseState>,
+    mut box_start: Local<Option<IVec2>>,
+    points_q: Query<(Entity, &IntMoveable, Option<&ESelected>), With<EPoint>>,
+    hovered_q: Query<(), With<EHovered>>,
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+) {
+    if mouse.button_input.just_pressed(MouseButton::Left) && hovered_q.is_empty() {
+        *box_start = Some(mouse.world_pos);
+    }
+    let Some(start) = *box_start else {
+        return;
+    };
+    let min = start.min(mouse.world_pos);
+    let max = start.max(mouse.world_pos);
+    if mouse.button_input.pressed(MouseButton::Left) {
+        gizmos.rect_2d(
+            (min + max).as_vec2() / 2.0,
+            0.0,
+            (max - min).as_vec2(),
+            Color::YELLOW,
+        );
+        return;
+    }
+    *box_start = None;
+    let mut next_order = points_q
+        .iter()
+        .filter_map(|data| data.2.map(|sel| sel.order + 1))
+        .max()
+        .unwrap_or(0);
+    for (eid, mv, sel) in points_q.iter() {
+        let pos = mv.get_ipos().truncate();
+        if sel.is_none() && pos.cmpge(min).all() && pos.cmple(max).all() {
+            commands.entity(eid).insert(ESelected {
+                order: next_order,
+                offset: (pos - mouse.world_pos).as_vec2(),
+            });
+            next_order += 1;
+        }
+    }
+}
+
+/// Selects every point in the rock/field being worked on, or every point if there isn't one
+pub(super) fn select_group(
+    In(()): In<()>,
+    gs: Res<GameState>,
+    points_q: Query<Entity, With<EPoint>>,
+    groups_q: Query<&EPointGroup>,
+    mut commands: Commands,
+) {
+    let eids: Vec<Entity> = match gs.get_editing_mode() {
+        Some(
+            EditingMode::CreatingRock(eid)
+            | EditingMode::EditingRock(eid)
+            | EditingMode::CreatingField(eid)
+            | EditingMode::EditingField(eid),
+        ) => groups_q
+            .get(eid)
+            .map(|pg| pg.eids.clone())
+            .unwrap_or_default(),
+        _ => points_q.iter().collect(),
+    };
+    for (order, eid) in eids.into_iter().enumerate() {
+        commands.entity(eid).insert(ESelected {
+            order: order as u32,
+            offset: Vec2::ZERO,
+        });
+    }
+}
+
 pub(super) fn animate_points(
     mut points_q: Query<
         (
@@ -342,15 +547,42 @@ pub(super) fn animate_points(
 
 pub(super) fn move_points(
     mouse: Res<MouseState>,
-    mut points_q: Query<(&mut IntMoveable, &mut ESelected), With<EPoint>>,
+    mut points_q: Query<(Entity, &mut IntMoveable, &mut ESelected), With<EPoint>>,
+    mut drag_starts: Local<HashMap<Entity, IVec2>>,
+    mut commands: Commands,
+    snap: Res<ESnap>,
 ) {
     if mouse.button_input.pressed(MouseButton::Left) {
-        for (mut mv, sel) in points_q.iter_mut() {
-            let two_d = mouse.world_pos.as_vec2() + sel.offset;
+        // Snap whichever point is closest to the mouse (the one being grabbed), and move the
+        // rest of the selection by the same amount so it keeps its shape
+        let grabbed = points_q
+            .iter()
+            .map(|(_, _, sel)| mouse.world_pos.as_vec2() + sel.offset)
+            .min_by(|a, b| {
+                let mouse_pos = mouse.world_pos.as_vec2();
+                a.distance(mouse_pos).total_cmp(&b.distance(mouse_pos))
+            });
+        let nudge = grabbed.map_or(Vec2::ZERO, |pos| snap.snap(pos).as_vec2() - pos);
+        for (eid, mut mv, sel) in points_q.iter_mut() {
+            drag_starts
+                .entry(eid)
+                .or_insert(mv.fpos.truncate().to_ivec2());
+            let two_d = mouse.world_pos.as_vec2() + sel.offset + nudge;
             mv.fpos = two_d.extend(mv.fpos.z);
         }
     } else {
-        for (mv, mut sel) in points_q.iter_mut() {
+        // The drag is over, remember it as one big move
+        let moves: Vec<_> = drag_starts
+            .drain()
+            .filter_map(|(eid, from)| {
+                let to = points_q.get(eid).ok()?.1.fpos.truncate().to_ivec2();
+                (from != to).then_some((eid, from, to))
+            })
+            .collect();
+        if !moves.is_empty() {
+            record_edit(&mut commands, EEdit::move_points(moves));
+        }
+        for (_, mv, mut sel) in points_q.iter_mut() {
             sel.offset = mv.fpos.truncate() - mouse.world_pos.as_vec2();
         }
     }
@@ -431,7 +663,14 @@ pub(super) fn tag_shiny(
         return;
     };
     match emode {
-        EditingMode::Free => {}
+        EditingMode::Free
+        | EditingMode::PlacingSegment(_)
+        | EditingMode::PlacingReplenish
+        | EditingMode::PlacingSwitch
+        | EditingMode::PlacingStardust
+        | EditingMode::PlacingPortal
+        | EditingMode::PlacingStart
+        | EditingMode::PlacingGoal => {}
         EditingMode::CreatingRock(eid)
         | EditingMode::EditingRock(eid)
         | EditingMode::CreatingField(eid)
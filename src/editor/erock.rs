@@ -3,26 +3,66 @@ use bevy::prelude::*;
 use crate::{
     drawing::{animation::MultiAnimationManager, mesh::ioutline_points},
     environment::rock::RockKind,
-    meta::game_state::{EditingMode, GameState, SetMetaState},
+    input::MouseState,
+    math::{icenter, Spleen},
+    meta::{
+        game_state::{EditingMode, GameState, SetMetaState},
+        old_level_data::{
+            ExportedRockPath, ExportedRockRotation, ExportedSwitchTarget, RockPathMode,
+        },
+    },
 };
 
 use super::{
+    ehistory::{record_edit, EEdit, GroupKind, GroupSnapshot},
     epoint::{EPointGroup, EShiny},
+    esnap::ESnap,
+    help::{say, HelpBarData},
+    oneshots::EOneshots,
     transitions::ERootEid,
 };
 
+/// How fast new paths move, in units per tick
+const DEFAULT_PATH_SPEED: f32 = 0.5;
+/// How fast new rotations spin, in radians per tick
+const DEFAULT_SPIN_SPEED: f32 = 0.02;
+
 #[derive(Component, Debug, Clone, Reflect)]
 pub struct ERock {
     /// Needed to solve the issue where on the tick where we send event to change game state
     /// we spawn the rock, and then the minimum is set to 2 and it's despawned
     needs_init: bool,
     pub kind: RockKind,
+    /// Overrides the bounciness that comes with `kind`
+    pub bounciness: Option<f32>,
+    pub z: i32,
+    pub path: Option<ExportedRockPath>,
+    pub rotation: Option<ExportedRockRotation>,
+    pub target: Option<ExportedSwitchTarget>,
 }
 impl ERock {
     pub fn new(kind: RockKind) -> Self {
         Self {
             needs_init: true,
             kind,
+            bounciness: None,
+            z: 0,
+            path: None,
+            rotation: None,
+            target: None,
+        }
+    }
+
+    /// For rocks that already have all their points, i.e. ones loaded from a level
+    pub fn finished(kind: RockKind) -> Self {
+        Self {
+            needs_init: false,
+            kind,
+            bounciness: None,
+            z: 0,
+            path: None,
+            rotation: None,
+            target: None,
         }
     }
 }
@@ -35,29 +75,124 @@ pub struct ERockBundle {
     spatial: SpatialBundle,
     multi: MultiAnimationManager,
 }
+impl ERockBundle {
+    pub(super) fn new(erock: ERock, point_group: EPointGroup) -> Self {
+        let (inner, outer) = erock.kind.to_sprite_infos();
+        Self {
+            name: Name::new("rock"),
+            multi: MultiAnimationManager::bordered_mesh(vec![], inner, outer, 6.0),
+            erock,
+            point_group,
+            spatial: default(),
+        }
+    }
+}
 
 pub(super) fn spawn_rock(
-    In(()): In<()>,
+    In(kind): In<RockKind>,
     mut commands: Commands,
     mut meta_writer: EventWriter<SetMetaState>,
     eroot: Res<ERootEid>,
 ) {
-    let kind = RockKind::default();
-    let (inner, outer) = kind.to_sprite_infos();
-    let multi = MultiAnimationManager::bordered_mesh(vec![], inner, outer, 6.0);
+    let erock = ERock::new(kind);
+    let pg = EPointGroup::default();
     let mut eid = Entity::PLACEHOLDER;
     commands.entity(eroot.0).with_children(|eroot| {
         eid = eroot
-            .spawn(ERockBundle {
-                name: Name::new("rock"),
-                erock: ERock::new(kind),
-                point_group: EPointGroup::default(),
-                spatial: default(),
-                multi,
-            })
+            .spawn(ERockBundle::new(erock.clone(), pg.clone()))
             .id();
     });
-    meta_writer.send(SetMetaState(EditingMode::CreatingRock(eid).to_meta_state()));
+    let emode = EditingMode::CreatingRock(eid);
+    let group = GroupSnapshot {
+        eid,
+        kind: GroupKind::Rock(erock),
+        pg,
+    };
+    record_edit(&mut commands, EEdit::create_group(group, emode));
+    meta_writer.send(SetMetaState(emode.to_meta_state()));
+}
+
+/// Something about a rock that can be changed from the console
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ERockChange {
+    Kind(RockKind),
+    Bounciness(f32),
+    /// How the rock moves along its path. Keeps the waypoints, if there are any
+    PathMotion {
+        mode: RockPathMode,
+        speed: f32,
+        spleen: Option<Spleen>,
+    },
+    ClearPath,
+    /// How fast the rock spins around its pivot. Zero stops it spinning
+    Spin(f32),
+}
+
+/// Changes the rock being worked on. Changing the kind when there isn't one starts a new rock
+pub(super) fn change_rock(
+    In(change): In<ERockChange>,
+    gs: Res<GameState>,
+    mut rocks_q: Query<(&mut ERock, &EPointGroup)>,
+    oneshots: Res<EOneshots>,
+    mut commands: Commands,
+    mut help_bar_q: Query<&mut HelpBarData>,
+) {
+    let eid = match gs.get_editing_mode() {
+        Some(EditingMode::CreatingRock(eid) | EditingMode::EditingRock(eid)) => eid,
+        _ => {
+            match change {
+                ERockChange::Kind(kind) => {
+                    commands.run_system_with_input(oneshots.spawn_rock, kind)
+                }
+                ERockChange::Bounciness(_)
+                | ERockChange::PathMotion { .. }
+                | ERockChange::ClearPath
+                | ERockChange::Spin(_) => {
+                    say(&mut help_bar_q, "Not editing a rock".into());
+                }
+            }
+            return;
+        }
+    };
+    let Ok((mut erock, pg)) = rocks_q.get_mut(eid) else {
+        return;
+    };
+    let before = GroupSnapshot::new(eid, pg, Some(&*erock), None);
+    match change {
+        ERockChange::Kind(kind) => erock.kind = kind,
+        ERockChange::Bounciness(bounciness) => erock.bounciness = Some(bounciness),
+        ERockChange::PathMotion {
+            mode,
+            speed,
+            spleen,
+        } => {
+            let waypoints = erock
+                .path
+                .take()
+                .map(|path| path.waypoints)
+                .unwrap_or_default();
+            erock.path = Some(ExportedRockPath {
+                waypoints,
+                speed,
+                spleen,
+                mode,
+            });
+        }
+        ERockChange::ClearPath => erock.path = None,
+        ERockChange::Spin(speed) => {
+            let pivot = erock
+                .rotation
+                .take()
+                .map(|rotation| rotation.pivot)
+                .unwrap_or_default();
+            erock.rotation = (speed != 0.0).then_some(ExportedRockRotation { pivot, speed });
+        }
+    }
+    let after = GroupSnapshot::new(eid, pg, Some(&*erock), None);
+    record_edit(
+        &mut commands,
+        EEdit::set_groups(before.zip(after).into_iter().collect()),
+    );
 }
 
 pub(super) fn update_rocks(
@@ -71,12 +206,22 @@ pub(super) fn update_rocks(
         EditingMode::Free
         | EditingMode::EditingRock(_)
         | EditingMode::CreatingField(_)
-        | EditingMode::EditingField(_) => Entity::PLACEHOLDER,
+        | EditingMode::EditingField(_)
+        | EditingMode::PlacingSegment(_)
+        | EditingMode::PlacingReplenish
+        | EditingMode::PlacingSwitch
+        | EditingMode::PlacingStardust
+        | EditingMode::PlacingPortal
+        | EditingMode::PlacingStart
+        | EditingMode::PlacingGoal => Entity::PLACEHOLDER,
         EditingMode::CreatingRock(eid) => eid,
     });
     for mut rock_data in &mut rocks_q {
         if Some(rock_data.0) == creating_eid {
-            rock_data.1.needs_init = false;
+            // Only write when needed, `animate_rocks` watches for changes
+            if rock_data.1.needs_init {
+                rock_data.1.needs_init = false;
+            }
         } else {
             if !rock_data.1.needs_init {
                 rock_data.2.minimum = 3;
@@ -86,12 +231,108 @@ pub(super) fn update_rocks(
 }
 
 pub(super) fn animate_rocks(
-    mut rocks_q: Query<(&mut MultiAnimationManager, &EPointGroup), With<ERock>>,
+    mut rocks_q: Query<(&mut MultiAnimationManager, &EPointGroup, Ref<ERock>)>,
 ) {
-    for (mut multi, pg) in rocks_q.iter_mut() {
+    for (mut multi, pg, erock) in rocks_q.iter_mut() {
+        if erock.is_changed() && !erock.is_added() {
+            // The kind may have changed, which means different sprites
+            let (inner, outer) = erock.kind.to_sprite_infos();
+            *multi = MultiAnimationManager::bordered_mesh(vec![], inner, outer, 6.0);
+            multi.is_coup = true;
+        }
         let inner = multi.map.get_mut("inner").unwrap();
         inner.set_points(ioutline_points(&pg.poses, -6.0));
         let outer = multi.map.get_mut("outer").unwrap();
         outer.set_points(pg.poses.clone());
     }
 }
+
+/// While editing a rock, L adds a waypoint to its path at the mouse, and shift+L takes the last
+/// one away. I moves the pivot it spins around to the mouse, and shift+I stops it spinning
+pub(super) fn watch_rock_motion_input(
+    gs: Res<GameState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<MouseState>,
+    snap: Res<ESnap>,
+    mut rocks_q: Query<(&mut ERock, &EPointGroup)>,
+    mut commands: Commands,
+) {
+    let Some(EditingMode::EditingRock(eid)) = gs.get_editing_mode() else {
+        return;
+    };
+    if !keyboard.any_just_pressed([KeyCode::KeyL, KeyCode::KeyI]) {
+        return;
+    }
+    let Ok((mut erock, pg)) = rocks_q.get_mut(eid) else {
+        return;
+    };
+    let before = GroupSnapshot::new(eid, pg, Some(&*erock), None);
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    // Both paths and pivots are stored relative to the middle of the rock
+    let mouse_offset = snap.snap(mouse.world_pos.as_vec2()) - icenter(&pg.poses);
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        if shift {
+            let Some(path) = erock.path.as_mut() else {
+                return;
+            };
+            path.waypoints.pop();
+        } else {
+            erock
+                .path
+                .get_or_insert_with(|| ExportedRockPath {
+                    speed: DEFAULT_PATH_SPEED,
+                    ..default()
+                })
+                .waypoints
+                .push(mouse_offset);
+        }
+    } else if shift {
+        if erock.rotation.take().is_none() {
+            return;
+        }
+    } else {
+        erock
+            .rotation
+            .get_or_insert_with(|| ExportedRockRotation {
+                speed: DEFAULT_SPIN_SPEED,
+                ..default()
+            })
+            .pivot = mouse_offset;
+    }
+    let after = GroupSnapshot::new(eid, pg, Some(&*erock), None);
+    record_edit(
+        &mut commands,
+        EEdit::set_groups(before.zip(after).into_iter().collect()),
+    );
+}
+
+/// Shows where moving rocks will go, with a ghost of the rock at every waypoint, and what
+/// spinning rocks spin around
+pub(super) fn draw_rock_motion(rocks_q: Query<(&ERock, &EPointGroup)>, mut gizmos: Gizmos) {
+    let color = Color::rgba(0.6, 0.9, 0.6, 0.5);
+    for (erock, pg) in rocks_q.iter() {
+        let origin = icenter(&pg.poses).as_vec2();
+        if let Some(rotation) = erock.rotation.as_ref() {
+            let pivot = origin + rotation.pivot.as_vec2();
+            gizmos.circle_2d(pivot, 2.0, color);
+            gizmos.line_2d(pivot, origin, color);
+        }
+        let Some(path) = erock.path.as_ref() else {
+            continue;
+        };
+        let mut stops = vec![origin];
+        stops.extend(
+            path.waypoints
+                .iter()
+                .map(|waypoint| origin + waypoint.as_vec2()),
+        );
+        if path.mode == RockPathMode::Loop {
+            stops.push(origin);
+        }
+        gizmos.linestrip_2d(stops.iter().copied(), color);
+        for waypoint in path.waypoints.iter() {
+            let ghost = pg.poses.iter().map(|pos| (*pos + *waypoint).as_vec2());
+            gizmos.linestrip_2d(ghost.clone().chain(ghost.take(1)), color);
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Turning what's in the editor into `LevelData` (for testing/saving), and back again (for loading)
+
+use bevy::{
+    ecs::system::{SystemParam, SystemState},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    meta::{
+        game_state::{EditingMode, SetMetaState},
+        level_lint::signed_area2,
+        old_level_data::{
+            ExportedField, ExportedPortal, ExportedReplenish, ExportedRock, ExportedSegment,
+            ExportedStardust, ExportedSwitch, LevelData,
+        },
+    },
+    physics::dyno::IntMoveable,
+};
+
+use super::{
+    efield::{EField, EFieldBundle},
+    egoodies::{
+        spawn_goal, spawn_replenish, spawn_stardust, spawn_start, spawn_switch, EGoal, EPortal,
+        EReplenish, ESegment, EStardust, EStart, ESwitch,
+    },
+    ehistory::{EHistory, Goodie, GroupKind, GroupSnapshot, PointSnapshot},
+    epoint::{EPoint, EPointBundle, EPointGroup, ShinyThing},
+    erock::{ERock, ERockBundle},
+    help::push_help_output,
+    oneshots::EOneshots,
+    transitions::ERootEid,
+};
+
+/// The id of the level being edited, if it came from (or has been saved to) disk
+#[derive(Resource, Debug, Default)]
+pub(super) struct ELevelId(pub Option<String>);
+
+/// Level ids name files straight in `assets/levels`, so they can't point anywhere else
+fn check_level_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || ["/", "\\", ".."].iter().any(|bad| id.contains(bad)) {
+        return Err(format!("{id:?} isn't a valid level id"));
+    }
+    Ok(())
+}
+
+fn level_path(id: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("assets/levels/{id}.level.ron"))
+}
+
+/// What you get when you open the editor fresh
+pub(super) fn blank_level_data() -> LevelData {
+    LevelData {
+        start: IVec2::new(-40, 0),
+        goal: IVec2::new(40, 0),
+        ..default()
+    }
+}
+
+/// Nothing stops you from drawing a polygon counter-clockwise in the editor, but everything
+/// that consumes level data expects clockwise points
+fn clockwise(mut points: Vec<IVec2>) -> Vec<IVec2> {
+    if signed_area2(&points) > 0 {
+        points.reverse();
+    }
+    points
+}
+
+/// The single-point pieces of a level
+#[derive(SystemParam)]
+pub(super) struct EGoodiesQuery<'w, 's> {
+    start_q: Query<'w, 's, &'static IntMoveable, With<EStart>>,
+    goal_q: Query<'w, 's, &'static IntMoveable, With<EGoal>>,
+    replenishes_q: Query<'w, 's, &'static IntMoveable, With<EReplenish>>,
+    switches_q: Query<'w, 's, (&'static IntMoveable, &'static ESwitch)>,
+    stardust_q: Query<'w, 's, (&'static IntMoveable, &'static EStardust)>,
+}
+
+/// Fails if the level doesn't have exactly one start and one goal
+pub(super) fn crystallize_level_data(
+    points_q: Query<&IntMoveable, With<EPoint>>,
+    rocks_q: Query<(&ERock, &EPointGroup)>,
+    fields_q: Query<(&EField, &EPointGroup)>,
+    goodies: EGoodiesQuery,
+    segments_q: Query<&ESegment>,
+    portals_q: Query<&EPortal>,
+) -> Result<LevelData, String> {
+    let EGoodiesQuery {
+        start_q,
+        goal_q,
+        replenishes_q,
+        switches_q,
+        stardust_q,
+    } = goodies;
+    let pos_of = |eid: &Entity| points_q.get(*eid).ok().map(|mv| mv.get_ipos().truncate());
+    let single_pos = |poses: Vec<IVec2>, what: &str| match poses.as_slice() {
+        [pos] => Ok(*pos),
+        _ => Err(format!(
+            "level needs exactly one {what} (found {})",
+            poses.len()
+        )),
+    };
+    let start = single_pos(
+        start_q.iter().map(|mv| mv.get_ipos().truncate()).collect(),
+        "start",
+    )?;
+    let goal = single_pos(
+        goal_q.iter().map(|mv| mv.get_ipos().truncate()).collect(),
+        "goal",
+    )?;
+    // Groups that are still being created can have too few points to mean anything
+    let rocks = rocks_q
+        .iter()
+        .map(|(erock, pg)| ExportedRock {
+            kind: erock.kind,
+            points: clockwise(pg.eids.iter().filter_map(pos_of).collect()),
+            z: erock.z,
+            bounciness: erock.bounciness,
+            path: erock.path.clone(),
+            rotation: erock.rotation.clone(),
+            target: erock.target.clone(),
+        })
+        .filter(|rock| rock.points.len() >= 3)
+        .collect();
+    let fields = fields_q
+        .iter()
+        .map(|(efield, pg)| ExportedField {
+            points: clockwise(pg.eids.iter().filter_map(pos_of).collect()),
+            dir: efield.dir,
+            strength: efield.strength,
+            drag: efield.drag,
+            well: efield.well.clone(),
+            target: efield.target.clone(),
+        })
+        .filter(|field| field.points.len() >= 3)
+        .collect();
+    let segments = segments_q
+        .iter()
+        .filter_map(|segment| {
+            Some(ExportedSegment {
+                kind: segment.kind,
+                left_parent: pos_of(&segment.left)?,
+                right_parent: pos_of(&segment.right)?,
+            })
+        })
+        .collect();
+    let replenishes = replenishes_q
+        .iter()
+        .map(|mv| ExportedReplenish {
+            pos: mv.get_ipos().truncate(),
+        })
+        .collect();
+    let switches = switches_q
+        .iter()
+        .map(|(mv, eswitch)| ExportedSwitch {
+            pos: mv.get_ipos().truncate(),
+            targets: eswitch.targets.clone(),
+            reset: eswitch.reset,
+        })
+        .collect();
+    let stardust = stardust_q
+        .iter()
+        .map(|(mv, estardust)| ExportedStardust {
+            uid: estardust.uid,
+            pos: mv.get_ipos().truncate(),
+        })
+        .collect();
+    let portals = portals_q
+        .iter()
+        .filter_map(|portal| {
+            Some(ExportedPortal {
+                a: pos_of(&portal.a)?,
+                b: pos_of(&portal.b)?,
+                a_angle: portal.a_angle,
+                b_angle: portal.b_angle,
+                rotate: portal.rotate,
+            })
+        })
+        .collect();
+    Ok(LevelData {
+        start,
+        goal,
+        rocks,
+        fields,
+        segments,
+        replenishes,
+        portals,
+        switches,
+        stardust,
+        ..default()
+    })
+}
+
+/// Everything `spawn_pieces` spawned, in the shape the history wants it
+pub(super) struct SpawnedPieces {
+    pub points: Vec<PointSnapshot>,
+    pub groups: Vec<GroupSnapshot>,
+    pub segments: Vec<(Entity, ESegment)>,
+    pub portals: Vec<(Entity, EPortal)>,
+}
+
+/// Spawns the rocks, fields, segments, replenishes, switches, stardust and portals of a level (not
+/// the start/goal), shifted by `offset`
+pub(super) fn spawn_pieces(
+    parent: &mut ChildBuilder,
+    level_data: &LevelData,
+    offset: IVec2,
+) -> SpawnedPieces {
+    let mut spawned = SpawnedPieces {
+        points: vec![],
+        groups: vec![],
+        segments: vec![],
+        portals: vec![],
+    };
+    // Rocks, fields, segments and portals that share a position share a point, so the shared point
+    // can be dragged around as one
+    let mut points = HashMap::<IVec2, Entity>::new();
+    let mut new_points = vec![];
+    let mut get_point = |parent: &mut ChildBuilder, pos: IVec2| {
+        *points.entry(pos).or_insert_with(|| {
+            let eid = parent.spawn(EPointBundle::new(pos)).id();
+            new_points.push(PointSnapshot {
+                eid,
+                pos,
+                goodie: None,
+            });
+            eid
+        })
+    };
+    let mut get_group = |parent: &mut ChildBuilder, poses: &[IVec2]| {
+        let poses: Vec<IVec2> = poses.iter().map(|pos| *pos + offset).collect();
+        EPointGroup {
+            eids: poses.iter().map(|pos| get_point(parent, *pos)).collect(),
+            poses,
+            minimum: 3,
+        }
+    };
+    for rock in level_data.rocks.iter() {
+        let pg = get_group(parent, &rock.points);
+        let mut erock = ERock::finished(rock.kind);
+        erock.bounciness = rock.bounciness;
+        erock.z = rock.z;
+        erock.path = rock.path.clone();
+        erock.rotation = rock.rotation.clone();
+        erock.target = rock.target.clone();
+        let eid = parent
+            .spawn(ERockBundle::new(erock.clone(), pg.clone()))
+            .id();
+        spawned.groups.push(GroupSnapshot {
+            eid,
+            kind: GroupKind::Rock(erock),
+            pg,
+        });
+    }
+    for field in level_data.fields.iter() {
+        let pg = get_group(parent, &field.points);
+        let mut efield = EField::finished(field.dir);
+        efield.strength = field.strength;
+        efield.drag = field.drag;
+        efield.well = field.well.clone();
+        efield.target = field.target.clone();
+        let eid = parent
+            .spawn(EFieldBundle::new(efield.clone(), pg.clone()))
+            .id();
+        spawned.groups.push(GroupSnapshot {
+            eid,
+            kind: GroupKind::Field(efield),
+            pg,
+        });
+    }
+    for segment in level_data.segments.iter() {
+        let pg = get_group(parent, &[segment.left_parent, segment.right_parent]);
+        let esegment = ESegment {
+            kind: segment.kind,
+            left: pg.eids[0],
+            right: pg.eids[1],
+        };
+        let eid = parent.spawn((Name::new("segment"), esegment.clone())).id();
+        spawned.segments.push((eid, esegment));
+    }
+    for portal in level_data.portals.iter() {
+        let pg = get_group(parent, &[portal.a, portal.b]);
+        let eportal = EPortal {
+            a: pg.eids[0],
+            b: pg.eids[1],
+            a_angle: portal.a_angle,
+            b_angle: portal.b_angle,
+            rotate: portal.rotate,
+        };
+        let eid = parent.spawn((Name::new("portal"), eportal.clone())).id();
+        spawned.portals.push((eid, eportal));
+    }
+    spawned.points = new_points;
+    for replenish in level_data.replenishes.iter() {
+        let pos = replenish.pos + offset;
+        spawned.points.push(PointSnapshot {
+            eid: spawn_replenish(parent, pos),
+            pos,
+            goodie: Some(Goodie::Replenish),
+        });
+    }
+    for switch in level_data.switches.iter() {
+        let pos = switch.pos + offset;
+        let eswitch = ESwitch {
+            targets: switch.targets.clone(),
+            reset: switch.reset,
+        };
+        spawned.points.push(PointSnapshot {
+            eid: spawn_switch(parent, pos, eswitch.clone()),
+            pos,
+            goodie: Some(Goodie::Switch(eswitch)),
+        });
+    }
+    for stardust in level_data.stardust.iter() {
+        let pos = stardust.pos + offset;
+        let estardust = EStardust { uid: stardust.uid };
+        spawned.points.push(PointSnapshot {
+            eid: spawn_stardust(parent, pos, estardust.clone()),
+            pos,
+            goodie: Some(Goodie::Stardust(estardust)),
+        });
+    }
+    spawned
+}
+
+/// Replaces everything in the editor with the given level
+pub(super) fn load_level_data(
+    In(level_data): In<LevelData>,
+    mut commands: Commands,
+    eroot: Res<ERootEid>,
+    children_q: Query<(Entity, &Parent), Without<ShinyThing>>,
+    mut meta_writer: EventWriter<SetMetaState>,
+    mut history: ResMut<EHistory>,
+) {
+    history.clear();
+    for (eid, parent) in children_q.iter() {
+        if parent.get() == eroot.0 {
+            commands.entity(eid).despawn_recursive();
+        }
+    }
+    commands.entity(eroot.0).with_children(|parent| {
+        spawn_pieces(parent, &level_data, IVec2::ZERO);
+        spawn_start(parent, level_data.start);
+        spawn_goal(parent, level_data.goal);
+    });
+    meta_writer.send(SetMetaState(EditingMode::Free.to_meta_state()));
+}
+
+/// Saves the level to `assets/levels/<id>.level.ron`. With no id, saves over whatever was
+/// last loaded/saved
+pub(super) fn save_level(
+    In(id): In<Option<String>>,
+    world: &mut World,
+    params: &mut SystemState<(Res<EOneshots>, Res<ELevelId>)>,
+) {
+    let (oneshots, level_id) = params.get_mut(world);
+    let crystallize = oneshots.crystallize_level_data;
+    let Some(id) = id.or(level_id.0.clone()) else {
+        push_help_output(world, "No level loaded, use `save <id>`".into());
+        return;
+    };
+    if let Err(e) = check_level_id(&id) {
+        push_help_output(world, format!("Not saving, {e}"));
+        return;
+    }
+    let output = match world.run_system(crystallize) {
+        Ok(Ok(level_data)) => match level_data.write_to_disk(&level_path(&id)) {
+            Ok(()) => {
+                world.resource_mut::<ELevelId>().0 = Some(id.clone());
+                format!("Saved {id}")
+            }
+            Err(e) => format!("Failed to save: {e}"),
+        },
+        Ok(Err(e)) => format!("Not saving, {e}"),
+        Err(e) => format!("Failed to crystallize level data (system): {e:?}"),
+    };
+    push_help_output(world, output);
+}
+
+/// Clears the editor out, forgetting which level was loaded
+pub(super) fn new_level(
+    world: &mut World,
+    params: &mut SystemState<(Res<EOneshots>, ResMut<ELevelId>)>,
+) {
+    let (oneshots, mut level_id) = params.get_mut(world);
+    let load_level_data = oneshots.load_level_data;
+    level_id.0 = None;
+    world
+        .run_system_with_input(load_level_data, blank_level_data())
+        .unwrap();
+    push_help_output(world, "New level".into());
+}
+
+/// Reads `assets/levels/<id>.level.ron` and replaces the editor contents with it
+pub(super) fn load_level(
+    In(id): In<String>,
+    world: &mut World,
+    params: &mut SystemState<Res<EOneshots>>,
+) {
+    if let Err(e) = check_level_id(&id) {
+        push_help_output(world, format!("Not loading, {e}"));
+        return;
+    }
+    let level_data = match LevelData::read_from_disk(&id) {
+        Ok(level_data) => level_data,
+        Err(e) => {
+            push_help_output(world, format!("Failed to load: {e}"));
+            return;
+        }
+    };
+    let load_level_data = params.get_mut(world).load_level_data;
+    let output = match world.run_system_with_input(load_level_data, level_data) {
+        Ok(()) => {
+            world.resource_mut::<ELevelId>().0 = Some(id.clone());
+            format!("Loaded {id}")
+        }
+        Err(e) => format!("Failed to load level data (system): {e:?}"),
+    };
+    push_help_output(world, output);
+}
+
+#[cfg(test)]
+mod esave_tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn level_ids_stay_in_the_levels_folder() {
+        assert_eq!(check_level_id("basic_1"), Ok(()));
+        for id in ["", "../progress", "editing/basic_1", "a\\b"] {
+            assert!(check_level_id(id).is_err(), "{id:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn levels_survive_the_editor() {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        for id in ["basic_1", "springy_1"] {
+            let mut level_data = LevelData::read_from_disk(id).unwrap();
+            world.run_system_once_with(level_data.clone(), load_level_data);
+            let mut crystallized = world.run_system_once(crystallize_level_data).unwrap();
+            for data in [&mut level_data, &mut crystallized] {
+                data.rocks
+                    .sort_by_key(|rock| rock.points.first().map(|p| (p.x, p.y)));
+                data.fields
+                    .sort_by_key(|field| field.points.first().map(|p| (p.x, p.y)));
+            }
+            assert_eq!(crystallized, level_data, "{id} changed in the editor");
+        }
+    }
+
+    #[test]
+    fn levels_without_a_goal_dont_crystallize() {
+        let mut world = World::new();
+        world.init_resource::<Events<SetMetaState>>();
+        world.init_resource::<EHistory>();
+        let eroot = world.spawn(SpatialBundle::default()).id();
+        world.insert_resource(ERootEid(eroot));
+        world.run_system_once_with(blank_level_data(), load_level_data);
+        assert!(world.run_system_once(crystallize_level_data).is_ok());
+        let goals: Vec<Entity> = world
+            .query_filtered::<Entity, With<EGoal>>()
+            .iter(&world)
+            .collect();
+        for eid in goals {
+            world.entity_mut(eid).despawn_recursive();
+        }
+        assert!(world.run_system_once(crystallize_level_data).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Snapping for placing/dragging points (to a grid, or to the vertices/edges of other groups),
+//! plus a ruler that measures between selected points.
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraMarker,
+    drawing::BgSpriteGizmoGroup,
+    math::{MathLine, ToIVec2},
+    meta::consts::fscreen_size,
+    physics::dyno::IntMoveable,
+};
+
+use super::{
+    epoint::{EPoint, EPointGroup, ESelected},
+    help::HelpBoxData,
+};
+
+/// How close (in world units) a point has to be to a vertex/edge to snap to it
+const SNAP_RADIUS: f32 = 4.0;
+const MIN_GRID_SIZE: i32 = 2;
+const MAX_GRID_SIZE: i32 = 64;
+
+#[derive(Resource, Debug)]
+pub(super) struct ESnap {
+    pub grid_on: bool,
+    pub grid_size: i32,
+    pub geometry_on: bool,
+    /// Unselected points, which selected points can snap to
+    vertices: Vec<IVec2>,
+    /// Edges of groups with no selected points
+    edges: Vec<MathLine>,
+}
+impl Default for ESnap {
+    fn default() -> Self {
+        Self {
+            grid_on: false,
+            grid_size: 8,
+            geometry_on: true,
+            vertices: vec![],
+            edges: vec![],
+        }
+    }
+}
+impl ESnap {
+    /// Where a point at `pos` should actually go. Vertices win over edges, which win over the grid
+    pub fn snap(&self, pos: Vec2) -> IVec2 {
+        if self.geometry_on {
+            let closest_vertex = self
+                .vertices
+                .iter()
+                .map(|vertex| vertex.as_vec2())
+                .filter(|vertex| vertex.distance(pos) <= SNAP_RADIUS)
+                .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)));
+            if let Some(vertex) = closest_vertex {
+                return vertex.to_ivec2();
+            }
+            let closest_edge = self
+                .edges
+                .iter()
+                .map(|edge| edge.closest_point_on_segment(&pos))
+                .filter(|point| point.distance(pos) <= SNAP_RADIUS)
+                .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)));
+            if let Some(point) = closest_edge {
+                return point.to_ivec2();
+            }
+        }
+        if self.grid_on {
+            let size = self.grid_size as f32;
+            return ((pos / size).round() * size).to_ivec2();
+        }
+        pos.to_ivec2()
+    }
+
+    fn describe(&self) -> Option<String> {
+        let mut parts = vec![];
+        if self.grid_on {
+            parts.push(format!("grid {}", self.grid_size));
+        }
+        if self.geometry_on {
+            parts.push("geometry".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// N toggles the grid, M toggles snapping to geometry, [ and ] shrink/grow the grid
+pub(super) fn watch_snap_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut snap: ResMut<ESnap>,
+    mut help_box_q: Query<&mut HelpBoxData>,
+) {
+    if keyboard.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]) {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        snap.grid_on = !snap.grid_on;
+    }
+    if keyboard.just_pressed(KeyCode::KeyM) {
+        snap.geometry_on = !snap.geometry_on;
+    }
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        snap.grid_size = (snap.grid_size / 2).max(MIN_GRID_SIZE);
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        snap.grid_size = (snap.grid_size * 2).min(MAX_GRID_SIZE);
+    }
+    if let Ok(mut help_box) = help_box_q.get_single_mut() {
+        help_box.set("snap", snap.describe());
+    }
+}
+
+/// Recomputes what selected points are allowed to snap to. Has to run before `move_points`
+pub(super) fn update_snap_targets(
+    mut snap: ResMut<ESnap>,
+    points_q: Query<(&IntMoveable, Has<ESelected>), With<EPoint>>,
+    groups_q: Query<&EPointGroup>,
+) {
+    let unselected = |eid: &Entity| match points_q.get(*eid) {
+        Ok((mv, false)) => Some(mv.get_ipos().truncate().as_vec2()),
+        _ => None,
+    };
+    snap.vertices = points_q
+        .iter()
+        .filter(|(_, selected)| !selected)
+        .map(|(mv, _)| mv.get_ipos().truncate())
+        .collect();
+    snap.edges = groups_q
+        .iter()
+        .filter_map(|pg| {
+            let points: Option<Vec<Vec2>> = pg.eids.iter().map(unselected).collect();
+            points.filter(|points| points.len() >= 2)
+        })
+        .flat_map(|points| MathLine::from_points(&points))
+        .collect();
+}
+
+/// Draws the grid over whatever the camera can see
+pub(super) fn draw_grid(
+    snap: Res<ESnap>,
+    camera_q: Query<(&Transform, &CameraMarker)>,
+    mut gizmos: Gizmos<BgSpriteGizmoGroup>,
+) {
+    if !snap.grid_on {
+        return;
+    }
+    let Ok((camera_tran, camera_marker)) = camera_q.get_single() else {
+        return;
+    };
+    let center = camera_tran.translation.truncate();
+    let half_size = fscreen_size() * camera_marker.scale.to_f32() / 2.0;
+    let size = snap.grid_size as f32;
+    let min = ((center - half_size) / size).floor() * size;
+    let max = center + half_size;
+    let color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+    let mut x = min.x;
+    while x <= max.x {
+        gizmos.line_2d(Vec2::new(x, min.y), Vec2::new(x, max.y), color);
+        x += size;
+    }
+    let mut y = min.y;
+    while y <= max.y {
+        gizmos.line_2d(Vec2::new(min.x, y), Vec2::new(max.x, y), color);
+        y += size;
+    }
+}
+
+/// Draws lines between selected points (in the order they were selected) and shows the
+/// length/angle of the most recent one
+pub(super) fn draw_ruler(
+    mut gizmos: Gizmos,
+    selected_q: Query<(&IntMoveable, &ESelected), With<EPoint>>,
+    mut help_box_q: Query<&mut HelpBoxData>,
+) {
+    let mut selected: Vec<(u32, Vec2)> = selected_q
+        .iter()
+        .map(|(mv, sel)| (sel.order, mv.fpos.truncate()))
+        .collect();
+    selected.sort_by_key(|(order, _)| *order);
+    let poses: Vec<Vec2> = selected.into_iter().map(|(_, pos)| pos).collect();
+    for pair in poses.windows(2) {
+        gizmos.line_2d(pair[0], pair[1], Color::YELLOW);
+    }
+    let reading = match poses.as_slice() {
+        [.., from, to] => {
+            let diff = *to - *from;
+            let angle = diff.y.atan2(diff.x).to_degrees();
+            Some(format!("{:.1} @ {angle:.0}°", diff.length()))
+        }
+        _ => None,
+    };
+    if let Ok(mut help_box) = help_box_q.get_single_mut() {
+        help_box.set("ruler", reading);
+    }
+}
+
+#[cfg(test)]
+mod esnap_tests {
+    use super::*;
+
+    #[test]
+    fn vertices_beat_edges_beat_the_grid() {
+        let mut snap = ESnap {
+            grid_on: true,
+            vertices: vec![IVec2::new(20, 20)],
+            edges: vec![MathLine {
+                p1: Vec2::new(-100.0, 0.0),
+                p2: Vec2::new(100.0, 0.0),
+            }],
+            ..default()
+        };
+        assert_eq!(snap.snap(Vec2::new(22.0, 19.0)), IVec2::new(20, 20));
+        assert_eq!(snap.snap(Vec2::new(13.0, 3.0)), IVec2::new(13, 0));
+        assert_eq!(snap.snap(Vec2::new(13.0, 30.0)), IVec2::new(16, 32));
+        snap.geometry_on = false;
+        assert_eq!(snap.snap(Vec2::new(22.0, 19.0)), IVec2::new(24, 16));
+        snap.grid_on = false;
+        assert_eq!(snap.snap(Vec2::new(22.4, 19.0)), IVec2::new(22, 19));
+    }
+}
@@ -0,0 +1,128 @@
+//! Quick iteration while testing: starting a test from the mouse instead of the level start,
+//! putting the editor back exactly how it was afterwards, and showing where the ship went.
+
+use bevy::{ecs::system::SystemState, prelude::*};
+
+use crate::{
+    camera::{CameraMarker, CameraScale},
+    input::MouseState,
+    meta::game_state::{EditingState, EditorState, GameState, SetMetaState},
+    physics::dyno::{IntDyno, IntMoveable},
+    ship::{Dead, Ship},
+};
+
+use super::{help::push_help_output, oneshots::EOneshots};
+
+/// Keeps a runaway ship from growing the trajectory forever
+const MAX_TRAJECTORY_LEN: usize = 20_000;
+
+/// Where (and how fast) the ship should start when testing from somewhere other than the start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct ETestStart {
+    pub pos: IVec2,
+    pub vel: Vec2,
+}
+
+#[derive(Resource, Debug)]
+pub(super) struct ETestSession {
+    /// `None` means test from the level start like normal
+    pub start: Option<ETestStart>,
+    /// What the editor looked like right before testing, restored when editing again
+    pub editing: EditingState,
+    pub camera_pos: Vec2,
+    pub camera_scale: CameraScale,
+    /// Where the ship went during its most recent attempt, in editor space
+    pub trajectory: Vec<Vec2>,
+}
+impl Default for ETestSession {
+    fn default() -> Self {
+        Self {
+            start: None,
+            editing: EditingState::blank(),
+            camera_pos: Vec2::ZERO,
+            camera_scale: CameraScale::One,
+            trajectory: vec![],
+        }
+    }
+}
+
+/// Everything `start_testing` needs once it knows the level can be tested
+type StartTestingParams<'w, 's> = (
+    Res<'w, EOneshots>,
+    Res<'w, GameState>,
+    Res<'w, MouseState>,
+    Query<'w, 's, (&'static CameraMarker, &'static IntMoveable)>,
+    ResMut<'w, ETestSession>,
+    EventWriter<'w, SetMetaState>,
+);
+
+/// Starts testing. `Some(vel)` starts the ship at the mouse with that velocity, `None` starts
+/// it at the level start. Levels that couldn't be saved (i.e. no start) can't be tested either
+pub(super) fn start_testing(
+    In(from_here): In<Option<Vec2>>,
+    world: &mut World,
+    params: &mut SystemState<StartTestingParams>,
+) {
+    let crystallize = params.get_mut(world).0.crystallize_level_data;
+    match world.run_system(crystallize) {
+        Ok(Ok(_)) => (),
+        Ok(Err(e)) => {
+            push_help_output(world, format!("Can't test, {e}"));
+            return;
+        }
+        Err(e) => {
+            warn!("Failed to crystallize level data (system): {e:?}");
+            return;
+        }
+    }
+    let (_, gs, mouse, camera_q, mut session, mut meta_writer) = params.get_mut(world);
+    let Some(EditorState::Editing(editing)) = gs.get_editor_state() else {
+        warn!("Can only start testing while editing. Gs: {gs:?}");
+        return;
+    };
+    session.start = from_here.map(|vel| ETestStart {
+        pos: mouse.world_pos,
+        vel,
+    });
+    session.editing = editing;
+    if let Ok((marker, mv)) = camera_q.get_single() {
+        session.camera_pos = mv.fpos.truncate();
+        session.camera_scale = marker.scale;
+    }
+    session.trajectory.clear();
+    meta_writer.send(SetMetaState(EditorState::Testing.to_meta_state()));
+}
+
+/// Goes back to editing, in whatever mode the editor was in before testing
+pub(super) fn stop_testing(
+    In(()): In<()>,
+    session: Res<ETestSession>,
+    mut meta_writer: EventWriter<SetMetaState>,
+) {
+    meta_writer.send(SetMetaState(
+        EditorState::Editing(session.editing).to_meta_state(),
+    ));
+}
+
+/// Remembers where the ship goes. Only the latest attempt is kept, so dying starts a new one
+pub(super) fn record_trajectory(
+    ships_q: Query<(Ref<Ship>, &IntDyno), Without<Dead>>,
+    mut session: ResMut<ETestSession>,
+) {
+    let Ok((ship, dyno)) = ships_q.get_single() else {
+        return;
+    };
+    if ship.is_added() {
+        session.trajectory.clear();
+    }
+    if session.trajectory.len() < MAX_TRAJECTORY_LEN {
+        session.trajectory.push(dyno.fpos.truncate());
+    }
+}
+
+pub(super) fn draw_trajectory(session: Res<ETestSession>, mut gizmos: Gizmos) {
+    gizmos.linestrip_2d(
+        session.trajectory.iter().copied(),
+        Color::rgba(0.4, 0.8, 1.0, 0.6),
+    );
+}
@@ -2,14 +2,30 @@ use bevy::{prelude::*, render::view::RenderLayers, text::Text2dBounds, utils::Ha
 use clap::{Arg, Command};
 
 use crate::{
+    camera::CameraMarker,
     drawing::layering::menu_layer,
+    environment::{
+        field::{FieldDrag, FieldStrength, WellFalloff},
+        rock::RockKind,
+    },
+    math::Spleen,
     meta::{
         consts::MENU_GROWTH_F32,
-        game_state::{EditingMode, EditingState, EditorState, GameState, SetMetaState},
+        game_state::{EditingMode, EditorState, GameState},
+        old_level_data::{LevelData, RockPathMode},
     },
+    physics::dyno::IntMoveable,
 };
 
-use super::{oneshots::EOneshots, transitions::HRootEid};
+use super::{
+    efield::{EFieldChange, EWellPull},
+    egoodies::ESwitchChange,
+    einspector::spawn_inspector,
+    epoint::{EPoint, ESelected},
+    erock::ERockChange,
+    oneshots::EOneshots,
+    transitions::HRootEid,
+};
 
 // THE STUFF TO CARE ABOUT
 
@@ -25,57 +41,501 @@ pub(super) fn spawn_help(In(()): In<()>, hroot: Res<HRootEid>, mut commands: Com
             });
         });
         HelpBoxData::spawn_hierarchy(parent);
+        spawn_inspector(parent);
+    });
+}
+
+/// Shows a line in the help bar output
+pub(super) fn push_help_output(world: &mut World, line: String) {
+    info!("{line}");
+    let mut help_bar_q = world.query::<&mut HelpBarData>();
+    if let Ok(mut help_bar) = help_bar_q.get_single_mut(world) {
+        help_bar.output.push(line);
+    }
+}
+
+/// Same as `push_help_output`, for systems that aren't exclusive
+pub(super) fn say(help_bar_q: &mut Query<&mut HelpBarData>, line: String) {
+    if let Ok(mut help_bar) = help_bar_q.get_single_mut() {
+        help_bar.output.push(line);
+    }
+}
+
+/// Everything the console understands
+#[derive(Debug, Clone, PartialEq)]
+enum EConsoleCommand {
+    /// `Some(vel)` tests from the mouse, starting with that velocity
+    Test(Option<Vec2>),
+    Edit,
+    Save(Option<String>),
+    Load(String),
+    New,
+    Rock(RockKind),
+    Field {
+        dir: Vec2,
+        strength: Option<FieldStrength>,
+    },
+    Goto(IVec2),
+    SelectAll,
+    Delete,
+    SetBounciness(f32),
+    SetSpin(f32),
+    SetStrength(FieldStrength),
+    SetDrag(FieldDrag),
+    SetPath {
+        mode: RockPathMode,
+        speed: f32,
+        spleen: Option<Spleen>,
+    },
+    ClearPath,
+    /// `None` turns the field back into a normal one
+    Well(Option<EWellPull>),
+    /// Which way (in degrees) the two ends of a portal face. `None` doesn't turn the ship
+    Portal(Option<(f32, f32)>),
+    Switch(ESwitchChange),
+}
+
+fn console_command() -> Command {
+    Command::new("help")
+        .no_binary_name(true)
+        .disable_help_subcommand(true)
+        .subcommand(
+            Command::new("test")
+                .arg(Arg::new("where"))
+                .arg(Arg::new("vx").allow_negative_numbers(true))
+                .arg(Arg::new("vy").allow_negative_numbers(true)),
+        )
+        .subcommand(Command::new("edit"))
+        .subcommand(Command::new("save").arg(Arg::new("id")))
+        .subcommand(Command::new("load").arg(Arg::new("id").required(true)))
+        .subcommand(Command::new("new"))
+        .subcommand(Command::new("rock").arg(Arg::new("kind").required(true)))
+        .subcommand(
+            Command::new("field")
+                .arg(Arg::new("dx").required(true).allow_negative_numbers(true))
+                .arg(Arg::new("dy").required(true).allow_negative_numbers(true))
+                .arg(Arg::new("strength")),
+        )
+        .subcommand(
+            Command::new("goto")
+                .arg(Arg::new("x").required(true).allow_negative_numbers(true))
+                .arg(Arg::new("y").required(true).allow_negative_numbers(true)),
+        )
+        .subcommand(Command::new("select").arg(Arg::new("what").required(true)))
+        .subcommand(Command::new("delete"))
+        .subcommand(
+            Command::new("set")
+                .arg(Arg::new("what").required(true))
+                .arg(
+                    Arg::new("value")
+                        .required(true)
+                        .allow_negative_numbers(true),
+                ),
+        )
+        .subcommand(
+            Command::new("path")
+                .arg(Arg::new("mode").required(true))
+                .arg(Arg::new("speed"))
+                .arg(Arg::new("easing")),
+        )
+        .subcommand(
+            Command::new("well")
+                .arg(Arg::new("pull").required(true))
+                .arg(Arg::new("falloff"))
+                .arg(Arg::new("radius")),
+        )
+        .subcommand(
+            Command::new("portal")
+                .arg(Arg::new("a").required(true).allow_negative_numbers(true))
+                .arg(Arg::new("b").allow_negative_numbers(true)),
+        )
+        .subcommand(Command::new("switch").arg(Arg::new("what").required(true)))
+}
+
+/// A named strength, or a number for a custom one
+fn parse_field_strength(name: &str) -> Result<FieldStrength, String> {
+    if let Some(strength) = FieldStrength::ALL
+        .into_iter()
+        .find(|strength| strength.to_string() == name)
+    {
+        return Ok(strength);
+    }
+    name.parse::<f32>()
+        .ok()
+        .filter(|strength| strength.is_finite() && *strength >= 0.0)
+        .map(FieldStrength::Custom)
+        .ok_or(format!("Unknown field strength `{name}`"))
+}
+
+/// A named drag, or a number (the fraction of speed lost every tick) for a custom one
+fn parse_field_drag(name: &str) -> Result<FieldDrag, String> {
+    if let Some(drag) = FieldDrag::ALL
+        .into_iter()
+        .find(|drag| drag.to_string() == name)
+    {
+        return Ok(drag);
+    }
+    name.parse::<f32>()
+        .ok()
+        .filter(|drag| (0.0..1.0).contains(drag))
+        .map(FieldDrag::Custom)
+        .ok_or(format!("Unknown field drag `{name}`"))
+}
+
+fn parse_console_command(command: &str) -> Result<EConsoleCommand, String> {
+    let matches = console_command()
+        .try_get_matches_from(command.split_whitespace())
+        .map_err(|e| {
+            let message = e.to_string();
+            let first_line = message.lines().next().unwrap_or_default();
+            first_line.trim_start_matches("error: ").to_string()
+        })?;
+    let Some((name, args)) = matches.subcommand() else {
+        return Err("No command".into());
+    };
+    let get = |arg: &str| args.get_one::<String>(arg).cloned();
+    let number = |arg: &str| -> Result<f32, String> {
+        let value = get(arg).unwrap_or_default();
+        value
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or(format!("Expected a number for <{arg}>, got `{value}`"))
+    };
+    match name {
+        "test" => match get("where").as_deref() {
+            None => Ok(EConsoleCommand::Test(None)),
+            Some("here") => {
+                let vel = match (get("vx"), get("vy")) {
+                    (None, None) => Vec2::ZERO,
+                    _ => Vec2::new(number("vx")?, number("vy")?),
+                };
+                Ok(EConsoleCommand::Test(Some(vel)))
+            }
+            Some(other) => Err(format!("Can't test from `{other}`")),
+        },
+        "edit" => Ok(EConsoleCommand::Edit),
+        "save" => Ok(EConsoleCommand::Save(get("id"))),
+        "load" => Ok(EConsoleCommand::Load(get("id").unwrap_or_default())),
+        "new" => Ok(EConsoleCommand::New),
+        "rock" => {
+            let kind = get("kind").unwrap_or_default();
+            RockKind::ALL
+                .into_iter()
+                .find(|rock_kind| rock_kind.to_string() == kind)
+                .map(EConsoleCommand::Rock)
+                .ok_or(format!("Unknown rock kind `{kind}`"))
+        }
+        "field" => {
+            let dir = Vec2::new(number("dx")?, number("dy")?);
+            if dir.length() < 0.001 {
+                return Err("Field direction can't be zero".into());
+            }
+            let strength = match get("strength") {
+                Some(name) => Some(parse_field_strength(&name)?),
+                None => None,
+            };
+            Ok(EConsoleCommand::Field {
+                dir: dir.normalize(),
+                strength,
+            })
+        }
+        "goto" => Ok(EConsoleCommand::Goto(IVec2::new(
+            number("x")?.round() as i32,
+            number("y")?.round() as i32,
+        ))),
+        "select" => match get("what").as_deref() {
+            Some("all") => Ok(EConsoleCommand::SelectAll),
+            what => Err(format!("Can't select `{}`", what.unwrap_or_default())),
+        },
+        "delete" => Ok(EConsoleCommand::Delete),
+        "set" => match get("what").as_deref() {
+            Some("bounciness") => {
+                let bounciness = number("value")?;
+                if bounciness < 0.0 {
+                    return Err("Bounciness can't be negative".into());
+                }
+                Ok(EConsoleCommand::SetBounciness(bounciness))
+            }
+            Some("spin") => Ok(EConsoleCommand::SetSpin(number("value")?)),
+            Some("strength") => Ok(EConsoleCommand::SetStrength(parse_field_strength(
+                &get("value").unwrap_or_default(),
+            )?)),
+            Some("drag") => Ok(EConsoleCommand::SetDrag(parse_field_drag(
+                &get("value").unwrap_or_default(),
+            )?)),
+            what => Err(format!("Can't set `{}`", what.unwrap_or_default())),
+        },
+        "path" => {
+            let mode = get("mode").unwrap_or_default();
+            if mode == "clear" {
+                return Ok(EConsoleCommand::ClearPath);
+            }
+            let mode = RockPathMode::ALL
+                .into_iter()
+                .find(|path_mode| path_mode.to_string() == mode)
+                .ok_or(format!("Unknown path mode `{mode}`"))?;
+            let speed = number("speed")?;
+            if speed <= 0.0 {
+                return Err("Path speed must be positive".into());
+            }
+            let spleen = match get("easing") {
+                Some(name) => Some(
+                    Spleen::ALL
+                        .into_iter()
+                        .find(|spleen| spleen.to_string() == name)
+                        .ok_or(format!("Unknown easing `{name}`"))?,
+                ),
+                None => None,
+            };
+            Ok(EConsoleCommand::SetPath {
+                mode,
+                speed,
+                spleen,
+            })
+        }
+        "well" => {
+            let repel = match get("pull").as_deref() {
+                Some("clear") => return Ok(EConsoleCommand::Well(None)),
+                Some("attract") => false,
+                Some("repel") => true,
+                pull => return Err(format!("Can't `{}`", pull.unwrap_or_default())),
+            };
+            let falloff = get("falloff").unwrap_or_default();
+            let falloff = WellFalloff::ALL
+                .into_iter()
+                .find(|well_falloff| well_falloff.to_string() == falloff)
+                .ok_or(format!("Unknown falloff `{falloff}`"))?;
+            let radius = number("radius")?;
+            if radius <= 0.0 {
+                return Err("Well radius must be positive".into());
+            }
+            Ok(EConsoleCommand::Well(Some(EWellPull {
+                falloff,
+                radius,
+                repel,
+            })))
+        }
+        "portal" => match get("a").as_deref() {
+            Some("straight") => Ok(EConsoleCommand::Portal(None)),
+            _ => Ok(EConsoleCommand::Portal(Some((number("a")?, number("b")?)))),
+        },
+        "switch" => match get("what").as_deref() {
+            Some("forever") => Ok(EConsoleCommand::Switch(ESwitchChange::Link(None))),
+            Some("unlink") => Ok(EConsoleCommand::Switch(ESwitchChange::Unlink)),
+            Some("starts_on") => Ok(EConsoleCommand::Switch(ESwitchChange::StartsOff(false))),
+            Some("starts_off") => Ok(EConsoleCommand::Switch(ESwitchChange::StartsOff(true))),
+            _ => {
+                let reset = number("what")?;
+                if reset <= 0.0 {
+                    return Err("Switch reset must be positive".into());
+                }
+                Ok(EConsoleCommand::Switch(ESwitchChange::Link(Some(reset))))
+            }
+        },
+        _ => Err(format!("Unknown command `{name}`")),
+    }
+}
+
+/// The words that could come after `words`, for tab completion
+fn console_words(words: &[&str]) -> Vec<String> {
+    match words {
+        [] => console_command()
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .collect(),
+        ["save" | "load"] => LevelData::ids_on_disk(),
+        ["rock"] => RockKind::ALL.iter().map(|kind| kind.to_string()).collect(),
+        ["field", _, _] => FieldStrength::ALL
+            .into_iter()
+            .map(|strength| strength.to_string())
+            .collect(),
+        ["test"] => vec!["here".into()],
+        ["select"] => vec!["all".into()],
+        ["set"] => vec![
+            "bounciness".into(),
+            "spin".into(),
+            "strength".into(),
+            "drag".into(),
+        ],
+        ["set", "strength"] => FieldStrength::ALL
+            .iter()
+            .map(|strength| strength.to_string())
+            .collect(),
+        ["set", "drag"] => FieldDrag::ALL.iter().map(|drag| drag.to_string()).collect(),
+        ["path"] => RockPathMode::ALL
+            .iter()
+            .map(|mode| mode.to_string())
+            .chain(["clear".to_string()])
+            .collect(),
+        ["well"] => vec!["attract".into(), "repel".into(), "clear".into()],
+        ["portal"] => vec!["straight".into()],
+        ["switch"] => vec![
+            "forever".into(),
+            "unlink".into(),
+            "starts_on".into(),
+            "starts_off".into(),
+        ],
+        ["well", "attract" | "repel"] => WellFalloff::ALL
+            .iter()
+            .map(|falloff| falloff.to_string())
+            .collect(),
+        ["path", _, _] => Spleen::ALL
+            .iter()
+            .map(|spleen| spleen.to_string())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Completes the last word of `input` as far as it can. Also returns the options when there's
+/// more than one
+fn complete_console_input(input: &str) -> (String, Vec<String>) {
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    let partial = if input.is_empty() || input.ends_with(' ') {
+        ""
+    } else {
+        words.pop().unwrap_or_default()
+    };
+    let options: Vec<String> = console_words(&words)
+        .into_iter()
+        .filter(|word| word.starts_with(partial))
+        .collect();
+    let Some(first) = options.first() else {
+        return (input.to_string(), options);
+    };
+    let common_len = options.iter().fold(first.len(), |len, option| {
+        first
+            .chars()
+            .zip(option.chars())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
     });
+    let mut completed = input[..input.len() - partial.len()].to_string();
+    completed.push_str(&first[..common_len]);
+    if options.len() == 1 {
+        completed.push(' ');
+        return (completed, vec![]);
+    }
+    (completed, options)
 }
 
 pub(super) fn submit_help_command(
     In(command): In<String>,
     gs: Res<GameState>,
-    mut meta_writer: EventWriter<SetMetaState>,
+    oneshots: Res<EOneshots>,
+    mut commands: Commands,
+    points_q: Query<(Entity, Has<ESelected>), With<EPoint>>,
+    mut help_bar_q: Query<&mut HelpBarData>,
 ) {
-    let parts = format!("help {command}");
-    let parts = parts.split_whitespace().collect::<Vec<_>>();
-    let Ok(matches) = Command::new("help")
-        .subcommand(Command::new("test"))
-        .subcommand(Command::new("edit"))
-        .subcommand(Command::new("field").arg(Arg::new("x")).arg(Arg::new("y")))
-        .try_get_matches_from(parts)
-    else {
-        warn!("Invalid command1: {command}");
+    let Some(editor_state) = gs.get_editor_state() else {
+        warn!("GameState looks like {gs:?}, which is not an editor state");
         return;
     };
-    let Some(m) = matches.subcommand() else {
-        warn!("Invalid command2: {command}");
-        return;
+    let console_command = match parse_console_command(&command) {
+        Ok(console_command) => console_command,
+        Err(e) => {
+            say(&mut help_bar_q, e);
+            return;
+        }
     };
-    let Some(editing_state) = gs.get_editor_state() else {
-        warn!("GameState looks like {gs:?}, which is not an editing state");
+    if !matches!(
+        (&console_command, editor_state),
+        (_, EditorState::Editing(_)) | (EConsoleCommand::Edit, EditorState::Testing)
+    ) {
+        say(&mut help_bar_q, "Only `edit` works while testing".into());
         return;
-    };
-    match m {
-        ("test", _) => {
-            if let EditorState::Editing(_) = editing_state {
-                // TODO: Verify that there exists a start
-                meta_writer.send(SetMetaState(EditorState::Testing.to_meta_state()));
+    }
+    match console_command {
+        EConsoleCommand::Test(from_here) => {
+            commands.run_system_with_input(oneshots.start_testing, from_here);
+        }
+        EConsoleCommand::Edit => {
+            commands.run_system(oneshots.stop_testing);
+        }
+        EConsoleCommand::Save(id) => {
+            commands.run_system_with_input(oneshots.save_level, id);
+        }
+        EConsoleCommand::Load(id) => {
+            commands.run_system_with_input(oneshots.load_level, id);
+        }
+        EConsoleCommand::New => {
+            commands.run_system(oneshots.new_level);
+        }
+        EConsoleCommand::Rock(kind) => {
+            commands.run_system_with_input(oneshots.change_rock, ERockChange::Kind(kind));
+        }
+        EConsoleCommand::Field { dir, strength } => {
+            commands.run_system_with_input(oneshots.change_field, (dir, strength));
+        }
+        EConsoleCommand::Goto(pos) => {
+            commands.add(move |world: &mut World| {
+                let mut camera_q = world.query_filtered::<&mut IntMoveable, With<CameraMarker>>();
+                for mut mv in camera_q.iter_mut(world) {
+                    mv.fpos = pos.as_vec2().extend(mv.fpos.z);
+                    mv.vel = Vec2::ZERO;
+                }
+            });
+        }
+        EConsoleCommand::SelectAll => {
+            for (order, (eid, _)) in points_q.iter().enumerate() {
+                commands.entity(eid).insert(ESelected {
+                    order: order as u32,
+                    offset: Vec2::ZERO,
+                });
             }
         }
-        ("edit", _) => {
-            if let EditorState::Testing = editing_state {
-                meta_writer.send(SetMetaState(
-                    EditorState::Editing(EditingState::blank()).to_meta_state(),
-                ));
+        EConsoleCommand::Delete => {
+            let eids: Vec<Entity> = points_q
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(eid, _)| eid)
+                .collect();
+            if eids.is_empty() {
+                say(&mut help_bar_q, "Nothing selected".into());
+                return;
             }
+            commands.run_system_with_input(oneshots.delete_points, eids);
         }
-        ("field", args) => {
-            println!(
-                "got field with {:?} {:?}",
-                args.try_get_one::<String>("x"),
-                args.try_get_one::<String>("y")
+        EConsoleCommand::SetBounciness(bounciness) => {
+            commands
+                .run_system_with_input(oneshots.change_rock, ERockChange::Bounciness(bounciness));
+        }
+        EConsoleCommand::SetSpin(speed) => {
+            commands.run_system_with_input(oneshots.change_rock, ERockChange::Spin(speed));
+        }
+        EConsoleCommand::SetPath {
+            mode,
+            speed,
+            spleen,
+        } => {
+            commands.run_system_with_input(
+                oneshots.change_rock,
+                ERockChange::PathMotion {
+                    mode,
+                    speed,
+                    spleen,
+                },
             );
         }
-        _ => {
-            warn!("Invalid command3: {command}");
-            return;
+        EConsoleCommand::ClearPath => {
+            commands.run_system_with_input(oneshots.change_rock, ERockChange::ClearPath);
+        }
+        EConsoleCommand::SetStrength(strength) => {
+            commands.run_system_with_input(oneshots.tweak_field, EFieldChange::Strength(strength));
+        }
+        EConsoleCommand::SetDrag(drag) => {
+            commands.run_system_with_input(oneshots.tweak_field, EFieldChange::Drag(drag));
+        }
+        EConsoleCommand::Well(pull) => {
+            commands.run_system_with_input(oneshots.tweak_field, EFieldChange::Well(pull));
+        }
+        EConsoleCommand::Portal(angles) => {
+            commands.run_system_with_input(oneshots.change_portals, angles);
+        }
+        EConsoleCommand::Switch(change) => {
+            commands.run_system_with_input(oneshots.change_switches, change);
         }
     }
 }
@@ -118,11 +578,50 @@ pub(super) fn update_help_box(
                 .pairs
                 .insert("mode".into(), format!("efield({eid:?})"));
         }
+        EditingMode::PlacingSegment(kind) => {
+            help_box.pairs.insert(
+                "mode".into(),
+                format!("{kind:?} (right click a rock edge)").to_lowercase(),
+            );
+        }
+        EditingMode::PlacingReplenish => {
+            help_box
+                .pairs
+                .insert("mode".into(), "replenish (right click to drop)".into());
+        }
+        EditingMode::PlacingSwitch => {
+            help_box
+                .pairs
+                .insert("mode".into(), "switch (right click to drop)".into());
+        }
+        EditingMode::PlacingStardust => {
+            help_box
+                .pairs
+                .insert("mode".into(), "stardust (right click to drop)".into());
+        }
+        EditingMode::PlacingPortal => {
+            help_box
+                .pairs
+                .insert("mode".into(), "portal (right click to drop)".into());
+        }
+        EditingMode::PlacingStart => {
+            help_box
+                .pairs
+                .insert("mode".into(), "start (right click to move)".into());
+        }
+        EditingMode::PlacingGoal => {
+            help_box
+                .pairs
+                .insert("mode".into(), "goal (right click to move)".into());
+        }
     }
-    help_box_fg.content = String::new();
-    for (key, value) in help_box.pairs.iter() {
-        help_box_fg.content.push_str(&format!("{key}: {value}"));
-    }
+    let mut keys: Vec<&String> = help_box.pairs.keys().collect();
+    keys.sort();
+    help_box_fg.content = keys
+        .into_iter()
+        .map(|key| format!("{key}: {}", help_box.pairs[key]))
+        .collect::<Vec<_>>()
+        .join("\n");
     *visibility = if help_box_fg.content.len() > 0 {
         Visibility::Inherited
     } else {
@@ -138,6 +637,10 @@ pub(super) struct HelpBarData {
     pub input: String,
     pub captured: bool,
     pub output: Vec<String>,
+    /// Everything that's been submitted, oldest first
+    pub history: Vec<String>,
+    /// Where up/down has gotten to in `history`, if anywhere
+    pub history_ix: Option<usize>,
 }
 
 /// Common piece used for displaying the background of a text box
@@ -150,16 +653,16 @@ pub(super) struct HelpTextBg {
     pub dims: UVec2,
 }
 impl HelpTextBg {
-    fn new(color: Color, pos: IVec3, dims: UVec2) -> Self {
+    pub(super) fn new(color: Color, pos: IVec3, dims: UVec2) -> Self {
         Self { color, pos, dims }
     }
 
-    fn to_bundle(self) -> HelpTextBgBundle {
+    pub(super) fn into_bundle(self) -> HelpTextBgBundle {
         HelpTextBgBundle::new(self)
     }
 }
 #[derive(Bundle)]
-struct HelpTextBgBundle {
+pub(super) struct HelpTextBgBundle {
     driver: HelpTextBg,
     sprite: SpriteBundle,
     render_layers: RenderLayers,
@@ -193,7 +696,7 @@ pub(super) struct HelpTextFg {
     pub content: String,
 }
 impl HelpTextFg {
-    fn new(color: Color, font_size: f32, content: String) -> Self {
+    pub(super) fn new(color: Color, font_size: f32, content: String) -> Self {
         Self {
             color,
             font_size,
@@ -201,12 +704,12 @@ impl HelpTextFg {
         }
     }
 
-    fn to_bundle(self) -> HelpTextFgBundle {
+    pub(super) fn into_bundle(self) -> HelpTextFgBundle {
         HelpTextFgBundle::new(self)
     }
 }
 #[derive(Bundle)]
-struct HelpTextFgBundle {
+pub(super) struct HelpTextFgBundle {
     driver: HelpTextFg,
     text: Text2dBundle,
     render_layers: RenderLayers,
@@ -247,7 +750,7 @@ impl HelpBarInputBgBundle {
             name: Name::new("input_bg"),
             marker: HelpBarInputBg,
             bg: HelpTextBg::new(Color::GRAY, IVec3::new(0, -5, -1), UVec2::new(160, 10))
-                .to_bundle(),
+                .into_bundle(),
         }
     }
 }
@@ -264,7 +767,7 @@ impl HelpBarInputTextBundle {
         Self {
             name: Name::new("input_text"),
             marker: HelpBarInputText,
-            fg: HelpTextFg::new(Color::BLACK, 48.0, "".into()).to_bundle(),
+            fg: HelpTextFg::new(Color::BLACK, 48.0, "".into()).into_bundle(),
         }
     }
 }
@@ -283,7 +786,7 @@ impl HelpBarOutputBgBundle {
             name: Name::new("output_bg"),
             marker: HelpBarOutputBg,
             bg: HelpTextBg::new(Color::DARK_GRAY, IVec3::new(0, 5, -1), UVec2::new(160, 10))
-                .to_bundle(),
+                .into_bundle(),
         }
     }
 }
@@ -301,7 +804,7 @@ impl HelpBarOutputTextBundle {
         Self {
             name: Name::new("output_text"),
             marker: HelpBarOutputText,
-            fg: HelpTextFg::new(Color::WHITE, 48.0, "".into()).to_bundle(),
+            fg: HelpTextFg::new(Color::WHITE, 48.0, "".into()).into_bundle(),
         }
     }
 }
@@ -320,6 +823,8 @@ impl HelpBarData {
                 input: String::new(),
                 captured: false,
                 output: vec![],
+                history: vec![],
+                history_ix: None,
             },
             SpatialBundle::from_transform(Transform::from_translation(
                 Vec3::new(80.0, -80.0, 0.0) * MENU_GROWTH_F32,
@@ -346,6 +851,18 @@ pub(super) struct HelpBoxData {
     pairs: HashMap<String, String>,
 }
 impl HelpBoxData {
+    /// Shows `key: value`, or hides the key when there's no value
+    pub fn set(&mut self, key: &str, value: Option<String>) {
+        match value {
+            Some(value) => {
+                self.pairs.insert(key.to_string(), value);
+            }
+            None => {
+                self.pairs.remove(key);
+            }
+        }
+    }
+
     fn spawn_hierarchy(commands: &mut ChildBuilder) {
         commands
             .spawn((
@@ -361,13 +878,13 @@ impl HelpBoxData {
                         Name::new("help_box_bg"),
                         HelpBoxBg,
                         HelpTextBg::new(Color::ANTIQUE_WHITE, IVec3::ZERO, UVec2::new(60, 30))
-                            .to_bundle(),
+                            .into_bundle(),
                     ))
                     .with_children(|bg| {
                         bg.spawn((
                             Name::new("help_box_fg"),
                             HelpBoxFg,
-                            HelpTextFg::new(Color::BLACK, 36.0, "".into()).to_bundle(),
+                            HelpTextFg::new(Color::BLACK, 36.0, "".into()).into_bundle(),
                         ));
                     });
             });
@@ -451,13 +968,39 @@ pub(super) fn editor_help_input(
             help_bar.captured = false;
             help_bar.input = String::new();
         } else if keyboard.pressed(KeyCode::Enter) {
-            commands.run_system_with_input(oneshots.submit_help_command, help_bar.input.clone());
+            let input = help_bar.input.trim().to_string();
+            if !input.is_empty() && help_bar.history.last() != Some(&input) {
+                help_bar.history.push(input.clone());
+            }
+            help_bar.history_ix = None;
+            commands.run_system_with_input(oneshots.submit_help_command, input);
             help_bar.captured = false;
             help_bar.input = String::new();
+        } else if keyboard.just_pressed(KeyCode::Tab) {
+            let (completed, options) = complete_console_input(&help_bar.input);
+            help_bar.input = completed;
+            if !options.is_empty() {
+                help_bar.output.push(options.join(" "));
+            }
+            evr_char.read();
+        } else if keyboard.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowDown]) {
+            let len = help_bar.history.len();
+            help_bar.history_ix = if keyboard.just_pressed(KeyCode::ArrowUp) {
+                match help_bar.history_ix {
+                    None => len.checked_sub(1),
+                    Some(ix) => Some(ix.saturating_sub(1)),
+                }
+            } else {
+                help_bar.history_ix.map(|ix| ix + 1).filter(|ix| *ix < len)
+            };
+            help_bar.input = match help_bar.history_ix {
+                Some(ix) => help_bar.history[ix].clone(),
+                None => String::new(),
+            };
         } else {
             let chars: Vec<&ReceivedCharacter> = evr_char.read().collect();
             for char in chars {
-                if !["/", "\n", "\u{8}"].contains(&char.char.as_str()) {
+                if !["/", "\n", "\t", "\u{8}"].contains(&char.char.as_str()) {
                     help_bar.input += &char.char.to_string();
                 }
                 if char.char.as_str() == "\u{8}" {
@@ -469,3 +1012,152 @@ pub(super) fn editor_help_input(
         keyboard.reset_all();
     }
 }
+
+#[cfg(test)]
+mod help_tests {
+    use super::*;
+
+    #[test]
+    fn console_commands_parse() {
+        assert_eq!(
+            parse_console_command("rock maglev"),
+            Ok(EConsoleCommand::Rock(RockKind::MagLev))
+        );
+        assert_eq!(
+            parse_console_command("field 0 -2 normal"),
+            Ok(EConsoleCommand::Field {
+                dir: Vec2::NEG_Y,
+                strength: Some(FieldStrength::Normal),
+            })
+        );
+        assert_eq!(
+            parse_console_command("goto -10.4 20"),
+            Ok(EConsoleCommand::Goto(IVec2::new(-10, 20)))
+        );
+        assert_eq!(
+            parse_console_command("set bounciness 0.5"),
+            Ok(EConsoleCommand::SetBounciness(0.5))
+        );
+        assert_eq!(
+            parse_console_command("save"),
+            Ok(EConsoleCommand::Save(None))
+        );
+        assert_eq!(
+            parse_console_command("test here 2 -1"),
+            Ok(EConsoleCommand::Test(Some(Vec2::new(2.0, -1.0))))
+        );
+        assert_eq!(
+            parse_console_command("test"),
+            Ok(EConsoleCommand::Test(None))
+        );
+        assert_eq!(
+            parse_console_command("path ping_pong 0.25 ease_in_out_cubic"),
+            Ok(EConsoleCommand::SetPath {
+                mode: RockPathMode::PingPong,
+                speed: 0.25,
+                spleen: Some(Spleen::EaseInOutCubic),
+            })
+        );
+        assert_eq!(
+            parse_console_command("set spin -0.05"),
+            Ok(EConsoleCommand::SetSpin(-0.05))
+        );
+        assert_eq!(
+            parse_console_command("path clear"),
+            Ok(EConsoleCommand::ClearPath)
+        );
+        assert_eq!(
+            parse_console_command("well repel inverse_square 80"),
+            Ok(EConsoleCommand::Well(Some(EWellPull {
+                falloff: WellFalloff::InverseSquare,
+                radius: 80.0,
+                repel: true,
+            })))
+        );
+        assert_eq!(
+            parse_console_command("set drag 0.1"),
+            Ok(EConsoleCommand::SetDrag(FieldDrag::Custom(0.1)))
+        );
+        assert_eq!(
+            parse_console_command("set drag off"),
+            Ok(EConsoleCommand::SetDrag(FieldDrag::Off))
+        );
+        assert_eq!(
+            parse_console_command("portal 90 -90"),
+            Ok(EConsoleCommand::Portal(Some((90.0, -90.0))))
+        );
+        assert_eq!(
+            parse_console_command("portal straight"),
+            Ok(EConsoleCommand::Portal(None))
+        );
+        assert_eq!(
+            parse_console_command("switch 2.5"),
+            Ok(EConsoleCommand::Switch(ESwitchChange::Link(Some(2.5))))
+        );
+        assert_eq!(
+            parse_console_command("switch starts_off"),
+            Ok(EConsoleCommand::Switch(ESwitchChange::StartsOff(true)))
+        );
+        assert_eq!(
+            parse_console_command("field 1 0 strong"),
+            Ok(EConsoleCommand::Field {
+                dir: Vec2::X,
+                strength: Some(FieldStrength::Strong),
+            })
+        );
+        for bad in [
+            "",
+            "set drag 2",
+            "well attract linear",
+            "portal 90",
+            "switch 0",
+            "switch flip",
+            "path loop -1",
+            "path bounce 1",
+            "rock granite",
+            "field 0 0",
+            "goto x 1",
+            "select some",
+            "test there",
+            "fly",
+        ] {
+            assert!(parse_console_command(bad).is_err(), "{bad} parsed");
+        }
+    }
+
+    #[test]
+    fn console_input_completes() {
+        assert_eq!(complete_console_input("ro"), ("rock ".into(), vec![]));
+        assert_eq!(
+            complete_console_input("rock "),
+            (
+                "rock ".into(),
+                vec!["normal".into(), "simple_kill".into(), "maglev".into()]
+            )
+        );
+        assert_eq!(
+            complete_console_input("s"),
+            (
+                "s".into(),
+                vec![
+                    "save".into(),
+                    "select".into(),
+                    "set".into(),
+                    "switch".into()
+                ]
+            )
+        );
+        assert_eq!(
+            complete_console_input("se"),
+            ("se".into(), vec!["select".into(), "set".into()])
+        );
+        assert_eq!(
+            complete_console_input("select a"),
+            ("select all ".into(), vec![])
+        );
+        assert_eq!(
+            complete_console_input("delete "),
+            ("delete ".into(), vec![])
+        );
+    }
+}
@@ -6,10 +6,17 @@ use crate::{
 };
 use transitions::{in_editing, in_testing, ERootEid, HRootEid, TRootEid};
 
+mod eclipboard;
 pub(self) mod efield;
+mod egoodies;
+mod ehistory;
 mod einput;
+mod einspector;
 pub(self) mod epoint;
 pub(self) mod erock;
+mod esave;
+mod esnap;
+mod etest;
 mod help;
 mod oneshots;
 mod transitions;
@@ -18,6 +25,9 @@ pub struct EditorPlugin;
 
 impl Plugin for EditorPlugin {
     fn build(&self, app: &mut App) {
+        // EClipboard
+        app.insert_resource(eclipboard::EClipboard::default());
+
         // EField
         app.register_type::<efield::EField>();
         app.add_systems(
@@ -26,6 +36,46 @@ impl Plugin for EditorPlugin {
                 .chain()
                 .after(epoint::cleanup_points),
         );
+        app.add_systems(
+            Update,
+            (
+                efield::watch_well_input.after(watch_mouse),
+                efield::draw_wells.after(efield::update_fields),
+            )
+                .run_if(in_editing),
+        );
+
+        // EGoodies
+        app.register_type::<egoodies::EStart>();
+        app.register_type::<egoodies::EGoal>();
+        app.register_type::<egoodies::EReplenish>();
+        app.register_type::<egoodies::ESegment>();
+        app.register_type::<egoodies::EPortal>();
+        app.register_type::<egoodies::ESwitch>();
+        app.register_type::<egoodies::EStardust>();
+        app.add_systems(
+            Update,
+            (
+                egoodies::cleanup_segments,
+                egoodies::draw_segments,
+                egoodies::cleanup_portals,
+                egoodies::draw_portals,
+                egoodies::draw_switches,
+                egoodies::draw_stardust,
+            )
+                .chain()
+                .after(epoint::cleanup_points)
+                .run_if(in_editing),
+        );
+        app.add_systems(
+            Update,
+            egoodies::watch_goodie_input
+                .after(watch_mouse)
+                .run_if(in_editing),
+        );
+
+        // EHistory
+        app.insert_resource(ehistory::EHistory::default());
 
         // EInput
         app.add_systems(
@@ -35,6 +85,16 @@ impl Plugin for EditorPlugin {
                 .run_if(in_editing),
         );
 
+        // EInspector
+        app.insert_resource(einspector::EInspector::default());
+        app.add_systems(
+            Update,
+            einspector::watch_inspector_input
+                .after(watch_mouse)
+                .run_if(in_editing),
+        );
+        app.add_systems(Update, einspector::update_inspector);
+
         // EPoint
         app.register_type::<epoint::EPoint>();
         app.register_type::<epoint::EPointGroup>();
@@ -45,6 +105,7 @@ impl Plugin for EditorPlugin {
             (
                 epoint::hover_points,
                 epoint::select_points,
+                epoint::box_select_points,
                 epoint::move_points,
                 epoint::animate_points,
                 epoint::cleanup_points,
@@ -64,6 +125,35 @@ impl Plugin for EditorPlugin {
                 .chain()
                 .after(epoint::cleanup_points),
         );
+        app.add_systems(
+            Update,
+            (
+                erock::watch_rock_motion_input.after(watch_mouse),
+                erock::draw_rock_motion.after(erock::update_rocks),
+            )
+                .run_if(in_editing),
+        );
+
+        // ESave
+        app.insert_resource(esave::ELevelId::default());
+
+        // ESnap
+        app.insert_resource(esnap::ESnap::default());
+        app.add_systems(
+            Update,
+            (
+                esnap::watch_snap_input,
+                esnap::update_snap_targets.before(epoint::move_points),
+                esnap::draw_grid,
+                esnap::draw_ruler.after(epoint::cleanup_points),
+            )
+                .run_if(in_editing),
+        );
+
+        // ETest
+        app.insert_resource(etest::ETestSession::default());
+        app.add_systems(FixedUpdate, etest::record_trajectory.run_if(in_testing));
+        app.add_systems(Update, etest::draw_trajectory.run_if(in_editing));
 
         // Help
         app.register_type::<help::HelpBarData>();
@@ -1,11 +1,21 @@
 use bevy::{ecs::system::SystemId, prelude::*};
 
-use crate::meta::game_state::EditingMode;
+use crate::{
+    environment::{field::FieldStrength, rock::RockKind},
+    meta::{game_state::EditingMode, old_level_data::LevelData},
+};
 
 use super::{
-    efield::spawn_field,
-    epoint::{delete_points, spawn_point},
-    erock::spawn_rock,
+    eclipboard::{
+        copy_selection, duplicate_selection, paste_clipboard, transform_selection, ETransform,
+    },
+    efield::{change_field, spawn_field, tweak_field, EFieldChange},
+    egoodies::{change_portals, change_switches, place_goodie, ESwitchChange},
+    ehistory::{step_history, HistoryDirection},
+    epoint::{delete_points, select_group, spawn_point},
+    erock::{change_rock, spawn_rock, ERockChange},
+    esave::{crystallize_level_data, load_level, load_level_data, new_level, save_level},
+    etest::{start_testing, stop_testing},
     help::{spawn_help, submit_help_command},
     transitions::start_testing_exclusive,
 };
@@ -13,23 +23,61 @@ use super::{
 #[derive(Resource)]
 pub(super) struct EOneshots {
     pub(super) start_testing_exclusive: SystemId<(), ()>,
+    pub(super) start_testing: SystemId<Option<Vec2>, ()>,
+    pub(super) stop_testing: SystemId<(), ()>,
     pub(super) spawn_help: SystemId<(), ()>,
     pub(super) submit_help_command: SystemId<String, ()>,
     pub(super) spawn_point: SystemId<(EditingMode, IVec2), ()>,
     pub(super) delete_points: SystemId<Vec<Entity>, ()>,
-    pub(super) spawn_rock: SystemId<(), ()>,
-    pub(super) spawn_field: SystemId<(), ()>,
+    pub(super) spawn_rock: SystemId<RockKind, ()>,
+    pub(super) spawn_field: SystemId<(Vec2, FieldStrength), ()>,
+    pub(super) crystallize_level_data: SystemId<(), Result<LevelData, String>>,
+    pub(super) load_level_data: SystemId<LevelData, ()>,
+    pub(super) save_level: SystemId<Option<String>, ()>,
+    pub(super) load_level: SystemId<String, ()>,
+    pub(super) step_history: SystemId<HistoryDirection, ()>,
+    pub(super) select_group: SystemId<(), ()>,
+    pub(super) copy_selection: SystemId<(), ()>,
+    pub(super) paste_clipboard: SystemId<IVec2, ()>,
+    pub(super) duplicate_selection: SystemId<(), ()>,
+    pub(super) transform_selection: SystemId<ETransform, ()>,
+    pub(super) place_goodie: SystemId<(EditingMode, IVec2), ()>,
+    pub(super) new_level: SystemId<(), ()>,
+    pub(super) change_rock: SystemId<ERockChange, ()>,
+    pub(super) change_field: SystemId<(Vec2, Option<FieldStrength>), ()>,
+    pub(super) tweak_field: SystemId<EFieldChange, ()>,
+    pub(super) change_portals: SystemId<Option<(f32, f32)>, ()>,
+    pub(super) change_switches: SystemId<ESwitchChange, ()>,
 }
 
 pub(super) fn register_oneshots(app: &mut App) {
     let oneshots = EOneshots {
         start_testing_exclusive: app.world.register_system(start_testing_exclusive),
+        start_testing: app.world.register_system(start_testing),
+        stop_testing: app.world.register_system(stop_testing),
         spawn_help: app.world.register_system(spawn_help),
         submit_help_command: app.world.register_system(submit_help_command),
         spawn_point: app.world.register_system(spawn_point),
         delete_points: app.world.register_system(delete_points),
         spawn_rock: app.world.register_system(spawn_rock),
         spawn_field: app.world.register_system(spawn_field),
+        crystallize_level_data: app.world.register_system(crystallize_level_data),
+        load_level_data: app.world.register_system(load_level_data),
+        save_level: app.world.register_system(save_level),
+        load_level: app.world.register_system(load_level),
+        step_history: app.world.register_system(step_history),
+        select_group: app.world.register_system(select_group),
+        copy_selection: app.world.register_system(copy_selection),
+        paste_clipboard: app.world.register_system(paste_clipboard),
+        duplicate_selection: app.world.register_system(duplicate_selection),
+        transform_selection: app.world.register_system(transform_selection),
+        place_goodie: app.world.register_system(place_goodie),
+        new_level: app.world.register_system(new_level),
+        change_rock: app.world.register_system(change_rock),
+        change_field: app.world.register_system(change_field),
+        tweak_field: app.world.register_system(tweak_field),
+        change_portals: app.world.register_system(change_portals),
+        change_switches: app.world.register_system(change_switches),
     };
     app.insert_resource(oneshots);
 }
@@ -8,13 +8,23 @@ use crate::{
     camera::{CameraMarker, CameraMode},
     environment::background::*,
     input::SetCameraModeEvent,
-    meta::{game_state::*, old_level_data::LevelDataOneshots},
+    meta::{
+        game_state::*,
+        old_level_data::{LevelDataOneshots, LevelRoot},
+    },
     physics::dyno::IntMoveable,
+    ship::{Ship, ShipBundle},
     when_becomes_false, when_becomes_true,
 };
 use bevy::{ecs::system::SystemState, prelude::*};
 
-use super::{epoint::ShinyThingBundle, oneshots::EOneshots};
+use super::{
+    epoint::ShinyThingBundle,
+    esave::{blank_level_data, ELevelId},
+    etest::ETestSession,
+    help::push_help_output,
+    oneshots::EOneshots,
+};
 
 fn is_editing_helper(gs: &GameState) -> bool {
     match gs.meta {
@@ -112,6 +122,7 @@ pub(super) fn setup_editor(
             .id(),
     );
     commands.run_system(e_oneshots.spawn_help);
+    commands.run_system_with_input(e_oneshots.load_level_data, blank_level_data());
     set_event.send(SetCameraModeEvent {
         mode: CameraMode::Free,
     });
@@ -124,6 +135,8 @@ pub(super) fn destroy_editor(
     mut eroot: ResMut<ERootEid>,
     mut troot: ResMut<TRootEid>,
     mut hroot: ResMut<HRootEid>,
+    mut level_id: ResMut<ELevelId>,
+    mut session: ResMut<ETestSession>,
 ) {
     if let Some(commands) = commands.get_entity(eroot.0) {
         commands.despawn_recursive();
@@ -137,14 +150,22 @@ pub(super) fn destroy_editor(
     *eroot = ERootEid(Entity::PLACEHOLDER);
     *troot = TRootEid(Entity::PLACEHOLDER);
     *hroot = HRootEid(Entity::PLACEHOLDER);
+    *level_id = ELevelId(None);
+    *session = ETestSession::default();
 }
 
 /// Called exactly once when the MetaState::Editor becomes the Editing variant
 /// TRICKY: `Editing` = working on a level, `Editor` = `Editing` || `Testing`
-pub(super) fn setup_editing(mut camera_q: Query<(&mut CameraMarker, &mut IntMoveable)>) {
+/// Puts the camera back wherever it was before testing
+pub(super) fn setup_editing(
+    mut camera_q: Query<(&mut CameraMarker, &mut IntMoveable)>,
+    session: Res<ETestSession>,
+) {
     let (mut marker, mut mv) = camera_q.single_mut();
     marker.mode = CameraMode::Free;
-    mv.fpos = EROOT_HOME.as_vec2().extend(mv.fpos.z);
+    marker.scale = session.camera_scale;
+    mv.fpos = (EROOT_HOME.as_vec2() + session.camera_pos).extend(mv.fpos.z);
+    mv.vel = Vec2::ZERO;
 }
 
 /// Called exactly once when the MetaState::Editor leaves the Editing variant
@@ -164,24 +185,53 @@ pub(super) fn setup_testing(
 }
 
 /// Called exactly once when the MetaState::Editor leaves the Testing variant
-pub(super) fn destroy_testing() {}
+pub(super) fn destroy_testing(level_roots: Query<Entity, With<LevelRoot>>, mut commands: Commands) {
+    for eid in level_roots.iter() {
+        commands.entity(eid).despawn_recursive();
+    }
+}
 
 /// This is helpful to have as a oneshot so setup_testing can be cleaner.
 pub(super) fn start_testing_exclusive(
     In(()): In<()>,
     world: &mut World,
-    params: &mut SystemState<Res<LevelDataOneshots>>,
+    params: &mut SystemState<(Res<EOneshots>, Res<LevelDataOneshots>, Res<ETestSession>)>,
 ) {
-    let level_oneshots = params.get_mut(world);
+    let (e_oneshots, level_oneshots, session) = params.get_mut(world);
+    let crystallize_level_data = e_oneshots.crystallize_level_data;
     let level_oneshots = level_oneshots.clone();
-    match world.run_system(level_oneshots.crystallize_level_data_id) {
-        Ok(level_data) => {
+    let start = session.start;
+    let camera_pos = session.camera_pos;
+    let editing = session.editing;
+    match world.run_system(crystallize_level_data) {
+        Ok(Ok(level_data)) => {
             world
                 .run_system_with_input(level_oneshots.old_spawn_level, (1, level_data, TROOT_HOME))
                 .unwrap();
         }
+        Ok(Err(e)) => {
+            push_help_output(world, format!("Can't test, {e}"));
+            world.send_event(SetMetaState(EditorState::Editing(editing).to_meta_state()));
+            return;
+        }
         Err(e) => {
             warn!("Failed to crystallize level data (system): {e:?}");
+            return;
         }
     }
+    let Some(start) = start else {
+        return;
+    };
+    // Testing from here: move the ship to the mouse and leave the camera where it was
+    let mut ship_q = world.query_filtered::<Entity, With<Ship>>();
+    let ship_eids: Vec<Entity> = ship_q.iter(world).collect();
+    for eid in ship_eids {
+        let mut ship = ShipBundle::new(start.pos);
+        ship.dyno.vel = start.vel;
+        world.entity_mut(eid).insert(ship);
+    }
+    let mut camera_q = world.query_filtered::<&mut IntMoveable, With<CameraMarker>>();
+    for mut mv in camera_q.iter_mut(world) {
+        mv.fpos = (TROOT_HOME.as_vec2() + camera_pos).extend(mv.fpos.z);
+    }
 }
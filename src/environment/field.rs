@@ -1,22 +1,35 @@
 use crate::{
     drawing::animation::{AnimationManager, SpriteInfo},
-    meta::old_level_data::{ExportedField, Rehydrate},
+    math::icenter,
+    meta::old_level_data::{ExportedField, ExportedWell, Rehydrate},
     physics::collider::{ColliderTriggerStub, ColliderTriggerStubs},
     uid::fresh_uid,
 };
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use super::switch::SwitchTarget;
+
 #[derive(Component, PartialEq, Debug, Clone, Copy, Default, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
 pub enum FieldStrength {
+    Weak,
     #[default]
     Normal,
+    Strong,
+    /// For when none of the others feel right
+    Custom(f32),
 }
 impl FieldStrength {
+    /// Everything but `Custom`
+    pub const ALL: [Self; 3] = [Self::Weak, Self::Normal, Self::Strong];
+
     pub fn to_f32(&self) -> f32 {
         match *self {
+            Self::Weak => 0.15,
             Self::Normal => 0.3,
+            Self::Strong => 0.6,
+            Self::Custom(strength) => strength,
         }
     }
 }
@@ -26,12 +39,113 @@ impl FieldStrength {
 pub enum FieldDrag {
     #[default]
     Normal,
+    /// Doesn't slow the ship down at all. Every level from before drag worked is this
+    Off,
+    Light,
+    /// Slows the ship down like water
+    Thick,
+    /// For when none of the others feel right. The fraction of speed lost every tick
+    Custom(f32),
 }
 impl FieldDrag {
+    /// Everything but `Custom`
+    pub const ALL: [Self; 4] = [Self::Normal, Self::Off, Self::Light, Self::Thick];
+
     pub fn to_f32(&self) -> f32 {
         match *self {
             Self::Normal => 0.0003,
+            Self::Off => 0.0,
+            Self::Light => 0.005,
+            Self::Thick => 0.03,
+            Self::Custom(drag) => drag,
+        }
+    }
+}
+
+/// How a well's pull weakens further from its center
+#[derive(PartialEq, Debug, Clone, Copy, Default, Reflect, Serialize, Deserialize)]
+pub enum WellFalloff {
+    /// Fades out evenly, reaching nothing at the edge of the well
+    #[default]
+    Linear,
+    /// Like real gravity, for orbits
+    InverseSquare,
+}
+impl WellFalloff {
+    pub const ALL: [Self; 2] = [Self::Linear, Self::InverseSquare];
+}
+
+impl std::fmt::Display for FieldStrength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match *self {
+            Self::Weak => "weak",
+            Self::Normal => "normal",
+            Self::Strong => "strong",
+            Self::Custom(strength) => return write!(f, "{}", strength),
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for FieldDrag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match *self {
+            Self::Normal => "normal",
+            Self::Off => "off",
+            Self::Light => "light",
+            Self::Thick => "thick",
+            Self::Custom(drag) => return write!(f, "{}", drag),
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for WellFalloff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match *self {
+            Self::Linear => "linear",
+            Self::InverseSquare => "inverse_square",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Inside this distance from the center, inverse square wells pull at full strength (instead
+/// of blowing up)
+const WELL_CORE_RADIUS: f32 = 16.0;
+
+/// Pulls toward (or pushes away from) a point instead of in one direction
+#[derive(Clone, Debug)]
+pub struct FieldWell {
+    /// In the same space as the field's points
+    pub center: Vec2,
+    pub falloff: WellFalloff,
+    pub radius: f32,
+    pub repel: bool,
+}
+impl FieldWell {
+    pub fn new(exported: &ExportedWell, points: &Vec<IVec2>) -> Self {
+        Self {
+            center: (icenter(points) + exported.center).as_vec2(),
+            falloff: exported.falloff,
+            radius: exported.radius,
+            repel: exported.repel,
+        }
+    }
+
+    /// Which way (and how hard, from 0 to 1) the well pulls something at `pos`
+    pub fn pull(&self, pos: Vec2) -> Vec2 {
+        let diff = self.center - pos;
+        let dist = diff.length();
+        if dist > self.radius || dist < 0.001 {
+            return Vec2::ZERO;
         }
+        let mult = match self.falloff {
+            WellFalloff::Linear => 1.0 - dist / self.radius,
+            WellFalloff::InverseSquare => (WELL_CORE_RADIUS / dist.max(WELL_CORE_RADIUS)).powi(2),
+        };
+        let dir = if self.repel { -diff } else { diff } / dist;
+        dir * mult
     }
 }
 
@@ -41,6 +155,16 @@ pub struct Field {
     pub dir: Vec2,
     pub strength: FieldStrength,
     pub drag: FieldDrag,
+    pub well: Option<FieldWell>,
+}
+impl Field {
+    /// Which way (and how hard, from 0 to 1) the field pushes something at `pos`
+    pub fn pull(&self, pos: Vec2) -> Vec2 {
+        match self.well.as_ref() {
+            Some(well) => well.pull(pos),
+            None => self.dir,
+        }
+    }
 }
 
 #[derive(Bundle)]
@@ -54,12 +178,10 @@ pub struct FieldBundle {
 
 impl Rehydrate<FieldBundle> for ExportedField {
     fn rehydrate(self) -> FieldBundle {
-        let field = Field {
-            dir: self.dir,
-            strength: self.strength,
-            drag: self.drag,
-        };
-        // let center = icenter(&self.points);
+        let well = self
+            .well
+            .as_ref()
+            .map(|well| FieldWell::new(well, &self.points));
         let mut spatial = SpatialBundle::default();
         spatial.transform.translation.z = -10.0;
         let mut anim = AnimationManager::single_repeating(
@@ -72,11 +194,24 @@ impl Rehydrate<FieldBundle> for ExportedField {
         )
         .force_mat_rot(self.dir.to_angle());
         anim.set_points(self.points.clone());
+        if let Some(well) = well.as_ref() {
+            anim.set_mat_well(Some((well.center, well.repel)));
+        }
+        let starts_off = self.target.as_ref().is_some_and(|target| target.starts_off);
+        if starts_off {
+            spatial.visibility = Visibility::Hidden;
+        }
         let trigger = ColliderTriggerStub {
             uid: fresh_uid(),
             refresh_period: 0,
             points: self.points.clone(),
-            active: true,
+            active: !starts_off,
+        };
+        let field = Field {
+            dir: self.dir,
+            strength: self.strength,
+            drag: self.drag,
+            well,
         };
         FieldBundle {
             field,
@@ -87,3 +222,42 @@ impl Rehydrate<FieldBundle> for ExportedField {
         }
     }
 }
+
+/// Spawns a field, which can be switched on and off if it has a target
+pub fn spawn_field(parent: &mut ChildBuilder, field: ExportedField) {
+    let target = field.target.as_ref().map(SwitchTarget::new);
+    let mut field_commands = parent.spawn(field.rehydrate());
+    if let Some(target) = target {
+        field_commands.insert(target);
+    }
+}
+
+#[cfg(test)]
+mod field_tests {
+    use super::*;
+
+    #[test]
+    fn wells_pull_toward_their_center() {
+        let points = vec![
+            IVec2::new(-50, -50),
+            IVec2::new(-50, 50),
+            IVec2::new(50, 50),
+            IVec2::new(50, -50),
+        ];
+        let mut well = FieldWell::new(
+            &ExportedWell {
+                center: IVec2::new(10, 0),
+                falloff: WellFalloff::Linear,
+                radius: 40.0,
+                repel: false,
+            },
+            &points,
+        );
+        assert_eq!(well.pull(Vec2::new(-10.0, 0.0)), Vec2::new(0.5, 0.0));
+        assert_eq!(well.pull(Vec2::new(10.0, 60.0)), Vec2::ZERO);
+        well.repel = true;
+        well.falloff = WellFalloff::InverseSquare;
+        assert_eq!(well.pull(Vec2::new(10.0, 2.0)), Vec2::Y);
+        assert_eq!(well.pull(Vec2::new(10.0, 32.0)), Vec2::new(0.0, 0.25));
+    }
+}
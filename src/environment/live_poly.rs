@@ -47,7 +47,7 @@ impl LivePolyBundle {
     }
 }
 
-pub(super) fn mark_live_polys_ready(
+pub(crate) fn mark_live_polys_ready(
     mut live_polys: Query<(&mut LivePolyMarker, Option<&Children>)>,
     active_colliders: Query<&ColliderActive>,
 ) {
@@ -3,13 +3,26 @@ pub mod field;
 pub mod goal;
 pub mod live_poly;
 pub mod particle;
+pub mod portal;
 pub mod replenish;
 pub mod rock;
 pub mod segment;
+pub mod stardust;
 pub mod start;
+pub mod switch;
 pub mod convo;
 
-use self::{particle::register_particles, replenish::update_replenishes};
+use self::{
+    particle::register_particles,
+    portal::{spawn_portal_bursts, teleport_ships, PortalTravel},
+    replenish::update_replenishes,
+    stardust::{collect_stardust, fade_collected_stardust, record_stardust, CollectStardust},
+    switch::{apply_switches, press_switches},
+};
+use crate::{
+    physics::{dyno::apply_fields, should_apply_physics},
+    ship::replenish_shot,
+};
 use bevy::prelude::*;
 use live_poly::mark_live_polys_ready;
 
@@ -20,5 +33,31 @@ impl Plugin for EnvironmentPlugin {
         register_particles(app);
         app.add_systems(Update, update_replenishes);
         app.add_systems(FixedUpdate, mark_live_polys_ready);
+        app.add_event::<PortalTravel>();
+        app.add_systems(
+            FixedUpdate,
+            teleport_ships
+                .after(apply_fields)
+                .before(replenish_shot)
+                .run_if(should_apply_physics),
+        );
+        app.add_systems(Update, spawn_portal_bursts);
+        app.add_systems(
+            FixedUpdate,
+            (press_switches, apply_switches)
+                .chain()
+                .after(teleport_ships)
+                .before(replenish_shot)
+                .run_if(should_apply_physics),
+        );
+        app.add_event::<CollectStardust>();
+        app.add_systems(
+            FixedUpdate,
+            collect_stardust
+                .after(teleport_ships)
+                .before(replenish_shot)
+                .run_if(should_apply_physics),
+        );
+        app.add_systems(Update, (record_stardust, fade_collected_stardust));
     }
 }
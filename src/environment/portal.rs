@@ -0,0 +1,247 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    drawing::layering::{light_layer_u8, sprite_layer_u8},
+    math::Spleen,
+    meta::old_level_data::{ExportedPortal, LevelRoot},
+    physics::{
+        collider::{ColliderActive, ColliderTriggerStub, ColliderTriggerStubs},
+        dyno::IntDyno,
+    },
+    ship::Ship,
+    uid::fresh_uid,
+};
+
+use super::particle::{
+    ParticleBody, ParticleBundle, ParticleColoring, ParticleOptions, ParticleSizing,
+    ParticleSpawner, ParticleVel,
+};
+
+/// How close the ship has to get to an end to go through it
+pub const PORTAL_RADIUS: i32 = 8;
+
+/// One end of a portal. The other end is its own entity with the roles swapped
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Portal {
+    pub pos: IVec2,
+    /// Where the other end is
+    pub exit: IVec2,
+    /// How far anything that comes through gets turned, in radians
+    pub turn: f32,
+}
+
+/// A ship went through a portal. Positions are relative to the level root
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct PortalTravel {
+    pub from: IVec2,
+    pub to: IVec2,
+}
+
+#[derive(Bundle)]
+pub struct PortalBundle {
+    portal: Portal,
+    spawner: ParticleSpawner,
+    spatial: SpatialBundle,
+    triggers: ColliderTriggerStubs,
+    name: Name,
+    active: ColliderActive,
+}
+impl PortalBundle {
+    pub fn new(pos: IVec2, exit: IVec2, turn: f32) -> Self {
+        // Clockwise, starting from the left
+        let points = (0..8)
+            .map(|ix| {
+                let angle = PI - ix as f32 * PI / 4.0;
+                pos + (Vec2::from_angle(angle) * PORTAL_RADIUS as f32)
+                    .round()
+                    .as_ivec2()
+            })
+            .collect();
+        let trigger = ColliderTriggerStub {
+            uid: fresh_uid(),
+            refresh_period: 0,
+            points,
+            active: true,
+        };
+        let spawner = ParticleSpawner {
+            angle_range: (0.0, 2.0 * PI),
+            mag_range: (0.05, 0.2),
+            size_range: (1.0, 2.5),
+            color_range: (Color::PURPLE, Color::FUCHSIA),
+            lifespan_range: (0.6, 1.0),
+            frequency_secs: 0.05,
+            frequency_var: 0.05,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            num_per_spawn: 2,
+            segment: (-Vec3::Z, -Vec3::Z),
+            options: ParticleOptions {
+                sizing: Some(ParticleSizing {
+                    spleen: Spleen::EaseInQuad,
+                }),
+                coloring: Some(ParticleColoring {
+                    end_color: Color::BLUE.with_a(0.0),
+                    spleen: Spleen::EaseInQuad,
+                }),
+                ..default()
+            },
+        };
+        Self {
+            portal: Portal { pos, exit, turn },
+            spawner,
+            spatial: SpatialBundle::from_transform(Transform::from_translation(
+                pos.extend(0).as_vec3(),
+            )),
+            triggers: ColliderTriggerStubs(vec![trigger]),
+            name: Name::new("Portal"),
+            active: ColliderActive,
+        }
+    }
+}
+
+/// Spawns both ends of a portal
+pub fn spawn_portal(parent: &mut ChildBuilder, portal: ExportedPortal) {
+    // Going in head-on means moving opposite to where the entrance faces
+    let turn = if portal.rotate {
+        portal.b_angle - portal.a_angle + PI
+    } else {
+        0.0
+    };
+    parent.spawn(PortalBundle::new(portal.a, portal.b, turn));
+    parent.spawn(PortalBundle::new(portal.b, portal.a, -turn));
+}
+
+/// Sends the ship through whichever portal it's touching, if it's allowed to. Doesn't animate or
+/// play anything so that predictions (like the shot preview) can use it too.
+pub fn teleport_helper(
+    ship: &mut Ship,
+    dyno: &mut IntDyno,
+    portals: &Query<&Portal>,
+) -> Option<PortalTravel> {
+    let touching: Vec<&Portal> = dyno
+        .triggers
+        .keys()
+        .filter_map(|eid| portals.get(*eid).ok())
+        .collect();
+    // Coming out of an end puts you right on top of it, so it stays shut until you've left it
+    if ship
+        .portal_cooldown
+        .is_some_and(|exit| touching.iter().all(|portal| portal.pos != exit))
+    {
+        ship.portal_cooldown = None;
+    }
+    let portal = touching
+        .into_iter()
+        .find(|portal| ship.portal_cooldown != Some(portal.pos))?;
+    dyno.fpos = portal.exit.as_vec2().extend(dyno.fpos.z);
+    dyno.vel = Vec2::from_angle(portal.turn).rotate(dyno.vel);
+    ship.portal_cooldown = Some(portal.exit);
+    Some(PortalTravel {
+        from: portal.pos,
+        to: portal.exit,
+    })
+}
+
+pub(crate) fn teleport_ships(
+    mut ship_q: Query<(&mut Ship, &mut IntDyno)>,
+    portals: Query<&Portal>,
+    mut travel_writer: EventWriter<PortalTravel>,
+) {
+    for (mut ship, mut dyno) in ship_q.iter_mut() {
+        if let Some(travel) = teleport_helper(&mut ship, &mut dyno, &portals) {
+            travel_writer.send(travel);
+        }
+    }
+}
+
+/// A burst of sparks at both ends whenever something goes through
+pub(crate) fn spawn_portal_bursts(
+    mut commands: Commands,
+    mut travel_reader: EventReader<PortalTravel>,
+    level_root: Query<&GlobalTransform, With<LevelRoot>>,
+) {
+    let Ok(root) = level_root.get_single() else {
+        return;
+    };
+    let mut rng = thread_rng();
+    for travel in travel_reader.read() {
+        for end in [travel.from, travel.to] {
+            let pos = root.translation() + end.as_vec2().extend(-1.0);
+            for _ in 0..12 {
+                let start_vel = Vec2::from_angle(rng.gen::<f32>() * 2.0 * PI) * 0.6;
+                for (size, layer) in [(2.0, sprite_layer_u8()), (6.0, light_layer_u8())] {
+                    let body = ParticleBody {
+                        pos,
+                        vel: start_vel,
+                        size,
+                        color: Color::FUCHSIA,
+                        layer,
+                    };
+                    ParticleBundle::spawn_options(
+                        &mut commands,
+                        body,
+                        0.4,
+                        ParticleOptions {
+                            sizing: Some(ParticleSizing {
+                                spleen: Spleen::EaseInQuad,
+                            }),
+                            coloring: Some(ParticleColoring {
+                                end_color: Color::BLUE,
+                                spleen: Spleen::EaseInQuad,
+                            }),
+                            vel: Some(ParticleVel {
+                                start_vel,
+                                end_vel: Vec2::ZERO,
+                                spleen: Spleen::EaseInQuad,
+                            }),
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod portal_tests {
+    use super::*;
+    use crate::{meta::old_level_data::LevelData, physics::headless::HeadlessSim};
+
+    /// Nothing but a portal in the way of a ship heading right
+    fn portal_level(rotate: bool) -> LevelData {
+        LevelData {
+            start: IVec2::ZERO,
+            goal: IVec2::new(200, 200),
+            portals: vec![ExportedPortal {
+                a: IVec2::new(40, 0),
+                b: IVec2::new(40, 100),
+                a_angle: PI,
+                b_angle: PI / 2.0,
+                rotate,
+            }],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn portals_keep_speed_and_can_turn() {
+        let vel = Vec2::new(2.0, 0.0);
+        for rotate in [false, true] {
+            let sim = HeadlessSim::new(portal_level(rotate)).with_max_ticks(40);
+            let run = sim.launch(IVec2::ZERO, vel);
+            let warp = run
+                .trajectory
+                .iter()
+                .position(|pos| pos.y > 50.0)
+                .expect("never went through");
+            assert_eq!(run.trajectory[warp], Vec2::new(40.0, 100.0));
+            // Same speed on the way out, and no bouncing back through
+            let expected = if rotate { Vec2::new(0.0, 2.0) } else { vel };
+            for step in run.trajectory[warp..].windows(2) {
+                assert!((step[1] - step[0] - expected).length() < 0.001);
+            }
+        }
+    }
+}
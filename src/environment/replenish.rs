@@ -151,7 +151,7 @@ impl Rehydrate<ReplenishBundle> for ExportedReplenish {
 }
 
 /// Checks replenishes that have ReplenishCharging and updates them
-pub(super) fn update_replenishes(
+pub(crate) fn update_replenishes(
     mut commands: Commands,
     mut replenishes: Query<
         (Entity, &mut ReplenishCharging, &mut MultiAnimationManager),
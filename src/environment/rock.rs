@@ -1,12 +1,20 @@
 use crate::{
     drawing::{animation::SpriteInfo, bordered_mesh::BorderedMesh},
-    meta::old_level_data::{ExportedRock, Rehydrate},
-    physics::collider::{ColliderStaticStub, ColliderStaticStubs},
+    math::{icenter, Spleen},
+    meta::old_level_data::{
+        ExportedRock, ExportedRockPath, ExportedRockRotation, Rehydrate, RockPathMode,
+    },
+    physics::{
+        collider::{ColliderBoundary, ColliderStatic, ColliderStaticStub, ColliderStaticStubs},
+        BulletTime,
+    },
     uid::fresh_uid,
 };
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use super::switch::SwitchTarget;
+
 #[derive(Component, PartialEq, Debug, Clone, Copy, Default, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
 pub enum RockKind {
@@ -16,6 +24,8 @@ pub enum RockKind {
     MagLev,
 }
 impl RockKind {
+    pub const ALL: [Self; 3] = [Self::Normal, Self::SimpleKill, Self::MagLev];
+
     fn bounciness(&self) -> f32 {
         match *self {
             Self::Normal => 0.7,
@@ -120,12 +130,20 @@ pub struct RockBundle {
 impl Rehydrate<RockBundle> for ExportedRock {
     fn rehydrate(self) -> RockBundle {
         let rock = Rock { kind: self.kind };
-        let spatial = SpatialBundle::default();
+        let mut spatial =
+            SpatialBundle::from_transform(Transform::from_translation(Vec3::Z * self.z as f32));
         let key = self.kind.to_string();
         let (inner, outer) = self.kind.to_sprite_infos();
         let mut bm = BorderedMesh::new(vec![(key.clone(), inner)], vec![(key.clone(), outer)], 7.0);
         bm.set_points(self.points.clone());
-        let collider = self.kind.to_collider_stub(self.points.clone());
+        let mut collider = self.kind.to_collider_stub(self.points.clone());
+        if let Some(bounciness) = self.bounciness {
+            collider.bounciness = bounciness;
+        }
+        if self.target.as_ref().is_some_and(|target| target.starts_off) {
+            collider.active = false;
+            spatial.visibility = Visibility::Hidden;
+        }
         RockBundle {
             rock,
             spatial,
@@ -135,3 +153,200 @@ impl Rehydrate<RockBundle> for ExportedRock {
         }
     }
 }
+
+/// Spawns a rock, which starts moving if it has a path and spinning if it has a rotation, and
+/// can be switched on and off if it has a target
+pub fn spawn_rock(parent: &mut ChildBuilder, rock: ExportedRock) {
+    let target = rock.target.as_ref().map(SwitchTarget::new);
+    let path = rock.path.as_ref().and_then(RockPath::new);
+    let rotation = rock
+        .rotation
+        .as_ref()
+        .and_then(|rotation| RockRotation::new(rotation, &rock.points));
+    let mut rock_commands = parent.spawn(rock.rehydrate());
+    if let Some(path) = path {
+        rock_commands.insert(path);
+    }
+    if let Some(rotation) = rotation {
+        rock_commands.insert(rotation);
+    }
+    if let Some(target) = target {
+        rock_commands.insert(target);
+    }
+}
+
+/// Makes a rock move along a path, forever
+#[derive(Component, Debug, Clone)]
+pub struct RockPath {
+    /// Start and end of every leg of the path, as offsets from where the rock was placed
+    legs: Vec<(Vec2, Vec2)>,
+    speed: f32,
+    spleen: Option<Spleen>,
+    leg: usize,
+    /// How far along the current leg the rock is, from 0 to 1
+    frac: f32,
+    /// Where the rock is now, relative to where it was placed
+    pub offset: Vec2,
+    /// How far the rock moved on the last tick
+    pub vel: Vec2,
+}
+impl RockPath {
+    /// `None` if the path wouldn't go anywhere
+    pub fn new(exported: &ExportedRockPath) -> Option<Self> {
+        if exported.speed <= 0.0 {
+            return None;
+        }
+        let mut stops = vec![Vec2::ZERO];
+        stops.extend(exported.waypoints.iter().map(|waypoint| waypoint.as_vec2()));
+        let mut legs: Vec<(Vec2, Vec2)> = stops.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        match exported.mode {
+            RockPathMode::Loop => legs.push((*stops.last().unwrap(), Vec2::ZERO)),
+            RockPathMode::PingPong => {
+                let back: Vec<(Vec2, Vec2)> = legs.iter().rev().map(|(a, b)| (*b, *a)).collect();
+                legs.extend(back);
+            }
+        }
+        legs.retain(|(from, to)| from.distance(*to) > 0.0);
+        if legs.is_empty() {
+            return None;
+        }
+        Some(Self {
+            legs,
+            speed: exported.speed,
+            spleen: exported.spleen,
+            leg: 0,
+            frac: 0.0,
+            offset: Vec2::ZERO,
+            vel: Vec2::ZERO,
+        })
+    }
+
+    /// Moves `dist` further along the path and returns the new offset
+    pub fn advance(&mut self, dist: f32) -> Vec2 {
+        let total: f32 = self.legs.iter().map(|(from, to)| from.distance(*to)).sum();
+        let mut dist = dist.rem_euclid(total);
+        loop {
+            let (from, to) = self.legs[self.leg];
+            let len = from.distance(to);
+            let left = (1.0 - self.frac) * len;
+            if dist < left {
+                self.frac += dist / len;
+                break;
+            }
+            dist -= left;
+            self.frac = 0.0;
+            self.leg = (self.leg + 1) % self.legs.len();
+        }
+        let (from, to) = self.legs[self.leg];
+        let t = match self.spleen {
+            Some(spleen) => spleen.interp(self.frac),
+            None => self.frac,
+        };
+        let offset = from.lerp(to, t);
+        self.vel = offset - self.offset;
+        self.offset = offset;
+        offset
+    }
+}
+
+/// Makes a rock spin around a pivot, forever
+#[derive(Component, Debug, Clone)]
+pub struct RockRotation {
+    /// Where the rock spins around, in the same space as its points
+    pub pivot: Vec2,
+    /// In radians per tick
+    pub speed: f32,
+    pub angle: f32,
+}
+impl RockRotation {
+    /// `None` if the rock wouldn't actually spin
+    pub fn new(exported: &ExportedRockRotation, points: &Vec<IVec2>) -> Option<Self> {
+        if exported.speed == 0.0 || !exported.speed.is_finite() {
+            return None;
+        }
+        Some(Self {
+            pivot: (icenter(points) + exported.pivot).as_vec2(),
+            speed: exported.speed,
+            angle: 0.0,
+        })
+    }
+}
+
+/// Moves rocks along their paths and spins them around their pivots, dragging their colliders
+/// along with them
+pub fn move_rocks(
+    mut rocks_q: Query<(Entity, &mut Transform, &Children), With<Rock>>,
+    mut paths_q: Query<&mut RockPath>,
+    mut rotations_q: Query<&mut RockRotation>,
+    mut colliders_q: Query<(&mut ColliderBoundary, &mut ColliderStatic)>,
+    bullet_time: Res<BulletTime>,
+) {
+    for (eid, mut tran, children) in rocks_q.iter_mut() {
+        let (path, rotation) = (paths_q.get_mut(eid).ok(), rotations_q.get_mut(eid).ok());
+        if path.is_none() && rotation.is_none() {
+            continue;
+        }
+        let (offset, vel) = match path {
+            Some(mut path) => {
+                let dist = path.speed * bullet_time.factor();
+                (path.advance(dist), path.vel)
+            }
+            None => (Vec2::ZERO, Vec2::ZERO),
+        };
+        let (pivot, angle, spin) = match rotation {
+            Some(mut rotation) => {
+                let spin = rotation.speed * bullet_time.factor();
+                rotation.angle = (rotation.angle + spin).rem_euclid(std::f32::consts::TAU);
+                (rotation.pivot, rotation.angle, spin)
+            }
+            None => (Vec2::ZERO, 0.0, 0.0),
+        };
+        // The mesh is drawn from the same points as the collider, so it turns around the origin.
+        // Shift it so that it looks like it turned around the pivot instead
+        let translation = pivot - Vec2::from_angle(angle).rotate(pivot) + offset;
+        tran.translation.x = translation.x;
+        tran.translation.y = translation.y;
+        tran.rotation = Quat::from_rotation_z(angle);
+        for child in children.iter() {
+            if let Ok((mut boundary, mut stat)) = colliders_q.get_mut(*child) {
+                boundary.place(offset, pivot, angle);
+                stat.vel = vel;
+                stat.spin = spin;
+                stat.pivot = pivot + offset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod rock_tests {
+    use super::*;
+
+    #[test]
+    fn ping_pong_paths_retrace_their_steps() {
+        let mut path = RockPath::new(&ExportedRockPath {
+            waypoints: vec![IVec2::new(10, 0), IVec2::new(10, 10)],
+            speed: 1.0,
+            spleen: None,
+            mode: RockPathMode::PingPong,
+        })
+        .unwrap();
+        assert_eq!(path.advance(5.0), Vec2::new(5.0, 0.0));
+        assert_eq!(path.advance(10.0), Vec2::new(10.0, 5.0));
+        assert_eq!(path.vel, Vec2::new(5.0, 5.0));
+        // Out to (10, 10), then back down
+        assert_eq!(path.advance(10.0), Vec2::new(10.0, 5.0));
+        assert_eq!(path.advance(15.0), Vec2::ZERO);
+        let mut looping = RockPath::new(&ExportedRockPath {
+            waypoints: vec![IVec2::new(10, 0), IVec2::new(10, 10)],
+            speed: 1.0,
+            spleen: None,
+            mode: RockPathMode::Loop,
+        })
+        .unwrap();
+        // Straight back along the diagonal
+        let pos = looping.advance(20.0 + 50f32.sqrt());
+        assert!(pos.distance(Vec2::new(5.0, 5.0)) < 0.001);
+        assert!(RockPath::new(&ExportedRockPath::default()).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    math::Spleen,
+    meta::{
+        game_state::{GameState, MetaState, SetMetaState},
+        old_level_data::{ExportedStardust, Rehydrate},
+        progress::{ActiveSaveFile, GameProgress},
+    },
+    physics::{
+        collider::{ColliderActive, ColliderTriggerStub, ColliderTriggerStubs},
+        dyno::IntDyno,
+    },
+    replay::ReplayState,
+    ship::Ship,
+    uid::{fresh_uid, UId},
+};
+
+use super::particle::{ParticleColoring, ParticleOptions, ParticleSizing, ParticleSpawner};
+
+/// Sparkle colors for stardust that's never been collected, and stardust from an earlier run
+const FRESH_COLORS: (Color, Color) = (Color::GOLD, Color::WHITE);
+const COLLECTED_COLORS: (Color, Color) = (Color::rgba(1.0, 1.0, 1.0, 0.3), Color::GRAY);
+
+#[derive(Component, Debug, Clone)]
+pub struct Stardust {
+    pub uid: UId,
+}
+
+/// The ship picked up some stardust. `uid` is the one from the level file
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct CollectStardust {
+    pub uid: UId,
+}
+
+#[derive(Bundle)]
+pub struct StardustBundle {
+    stardust: Stardust,
+    spawner: ParticleSpawner,
+    spatial: SpatialBundle,
+    triggers: ColliderTriggerStubs,
+    name: Name,
+    active: ColliderActive,
+}
+impl StardustBundle {
+    pub fn new(uid: UId, pos: IVec2) -> Self {
+        let trigger = ColliderTriggerStub {
+            uid: fresh_uid(),
+            refresh_period: 0,
+            points: vec![
+                pos + IVec2::new(-4, 0),
+                pos + IVec2::new(0, 4),
+                pos + IVec2::new(4, 0),
+                pos + IVec2::new(0, -4),
+            ],
+            active: true,
+        };
+        let spawner = ParticleSpawner {
+            angle_range: (0.0, 2.0 * PI),
+            mag_range: (0.01, 0.05),
+            size_range: (0.5, 1.5),
+            color_range: FRESH_COLORS,
+            lifespan_range: (0.5, 1.0),
+            frequency_secs: 0.15,
+            frequency_var: 0.1,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            num_per_spawn: 1,
+            segment: (-Vec3::Z, -Vec3::Z),
+            options: ParticleOptions {
+                sizing: Some(ParticleSizing {
+                    spleen: Spleen::EaseInQuad,
+                }),
+                coloring: Some(ParticleColoring {
+                    end_color: Color::WHITE.with_a(0.0),
+                    spleen: Spleen::EaseInQuad,
+                }),
+                ..default()
+            },
+        };
+        Self {
+            stardust: Stardust { uid },
+            spawner,
+            spatial: SpatialBundle::from_transform(Transform::from_translation(
+                pos.extend(0).as_vec3(),
+            )),
+            triggers: ColliderTriggerStubs(vec![trigger]),
+            name: Name::new("Stardust"),
+            active: ColliderActive,
+        }
+    }
+}
+impl Rehydrate<StardustBundle> for ExportedStardust {
+    fn rehydrate(self) -> StardustBundle {
+        StardustBundle::new(self.uid, self.pos)
+    }
+}
+
+/// Picks up any stardust the ship is touching. It's gone for the rest of the attempt
+pub(crate) fn collect_stardust(
+    mut commands: Commands,
+    ship_q: Query<&IntDyno, With<Ship>>,
+    stardust_q: Query<(Entity, &Stardust)>,
+    mut collect_writer: EventWriter<CollectStardust>,
+) {
+    for (eid, stardust) in stardust_q.iter() {
+        if ship_q.iter().any(|dyno| dyno.triggers.contains_key(&eid)) {
+            commands.entity(eid).despawn_recursive();
+            collect_writer.send(CollectStardust { uid: stardust.uid });
+        }
+    }
+}
+
+/// Counts collected stardust towards the level, and saves it so it stays collected (unless it was
+/// collected by a replay)
+pub(crate) fn record_stardust(
+    mut collect_reader: EventReader<CollectStardust>,
+    gs: Res<GameState>,
+    mut gs_writer: EventWriter<SetMetaState>,
+    mut game_progress: Query<&mut GameProgress, With<ActiveSaveFile>>,
+    replay_state: Res<ReplayState>,
+) {
+    let Some(mut ls) = gs.get_level_state() else {
+        collect_reader.clear();
+        return;
+    };
+    let mut changed = false;
+    for collect in collect_reader.read() {
+        if !ls.stardust.contains(&collect.uid) {
+            ls.stardust.push(collect.uid);
+            changed = true;
+        }
+        if replay_state.is_playing() {
+            continue;
+        }
+        if let Ok(mut game_progress) = game_progress.get_single_mut() {
+            game_progress.record_stardust(&ls.id, collect.uid);
+        }
+    }
+    if changed {
+        gs_writer.send(SetMetaState(MetaState::Level(ls)));
+    }
+}
+
+/// Stardust collected on an earlier attempt still shows up (it can be collected again), but faded
+pub(crate) fn fade_collected_stardust(
+    mut stardust_q: Query<(&Stardust, &mut ParticleSpawner), Added<Stardust>>,
+    gs: Res<GameState>,
+    game_progress: Query<&GameProgress, With<ActiveSaveFile>>,
+) {
+    let (Some(ls), Ok(game_progress)) = (gs.get_level_state(), game_progress.get_single()) else {
+        return;
+    };
+    let collected = game_progress.get_level_stats(&ls.id).stardust;
+    for (stardust, mut spawner) in stardust_q.iter_mut() {
+        if collected.contains(&stardust.uid) {
+            spawner.color_range = COLLECTED_COLORS;
+        }
+    }
+}
+
+#[cfg(test)]
+mod stardust_tests {
+    use super::*;
+    use crate::{meta::old_level_data::LevelData, physics::headless::HeadlessSim};
+
+    #[test]
+    fn stardust_is_collected_once() {
+        let level_data = LevelData {
+            start: IVec2::ZERO,
+            goal: IVec2::new(400, 400),
+            stardust: vec![
+                ExportedStardust {
+                    uid: 3,
+                    pos: IVec2::new(30, 0),
+                },
+                ExportedStardust {
+                    uid: 4,
+                    pos: IVec2::new(30, 60),
+                },
+            ],
+            ..default()
+        };
+        let run = HeadlessSim::new(level_data)
+            .with_max_ticks(40)
+            .launch(IVec2::ZERO, Vec2::new(2.0, 0.0));
+        assert_eq!(run.stardust, vec![3]);
+    }
+}
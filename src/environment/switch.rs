@@ -0,0 +1,234 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    math::Spleen,
+    meta::old_level_data::{ExportedSwitch, ExportedSwitchTarget, Rehydrate},
+    physics::{
+        collider::{
+            ColliderActive, ColliderTriggerStub, ColliderTriggerStubs, TrickleColliderActive,
+        },
+        dyno::IntDyno,
+        BulletTime,
+    },
+    ship::Ship,
+    uid::{fresh_uid, UId},
+};
+
+use super::particle::{ParticleColoring, ParticleOptions, ParticleSizing, ParticleSpawner};
+
+/// Spark colors for a switch that's off and one that's on
+const OFF_COLORS: (Color, Color) = (Color::GRAY, Color::WHITE);
+const ON_COLORS: (Color, Color) = (Color::ORANGE, Color::YELLOW);
+
+/// A rock or field that switches can turn on and off
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct SwitchTarget {
+    pub uid: UId,
+    pub starts_off: bool,
+}
+impl SwitchTarget {
+    pub fn new(exported: &ExportedSwitchTarget) -> Self {
+        Self {
+            uid: exported.uid,
+            starts_off: exported.starts_off,
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Switch {
+    pub targets: Vec<UId>,
+    pub reset: Option<f32>,
+    /// Whether the targets are flipped from how they started
+    pub on: bool,
+    /// Whether the ship was touching the switch last tick. Switches only do something when first
+    /// touched, so resting on one doesn't flicker its targets
+    held: bool,
+    timer: Option<Timer>,
+}
+
+#[derive(Bundle)]
+pub struct SwitchBundle {
+    switch: Switch,
+    spawner: ParticleSpawner,
+    spatial: SpatialBundle,
+    triggers: ColliderTriggerStubs,
+    name: Name,
+    active: ColliderActive,
+}
+impl SwitchBundle {
+    pub fn new(pos: IVec2, targets: Vec<UId>, reset: Option<f32>) -> Self {
+        let trigger = ColliderTriggerStub {
+            uid: fresh_uid(),
+            refresh_period: 0,
+            points: vec![
+                pos + IVec2::new(-5, 0),
+                pos + IVec2::new(0, 5),
+                pos + IVec2::new(5, 0),
+                pos + IVec2::new(0, -5),
+            ],
+            active: true,
+        };
+        let spawner = ParticleSpawner {
+            angle_range: (0.0, 2.0 * PI),
+            mag_range: (0.02, 0.1),
+            size_range: (1.0, 2.0),
+            color_range: OFF_COLORS,
+            lifespan_range: (0.4, 0.8),
+            frequency_secs: 0.1,
+            frequency_var: 0.05,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            num_per_spawn: 1,
+            segment: (-Vec3::Z, -Vec3::Z),
+            options: ParticleOptions {
+                sizing: Some(ParticleSizing {
+                    spleen: Spleen::EaseInQuad,
+                }),
+                coloring: Some(ParticleColoring {
+                    end_color: Color::WHITE.with_a(0.0),
+                    spleen: Spleen::EaseInQuad,
+                }),
+                ..default()
+            },
+        };
+        Self {
+            switch: Switch {
+                targets,
+                reset,
+                on: false,
+                held: false,
+                timer: None,
+            },
+            spawner,
+            spatial: SpatialBundle::from_transform(Transform::from_translation(
+                pos.extend(0).as_vec3(),
+            )),
+            triggers: ColliderTriggerStubs(vec![trigger]),
+            name: Name::new("Switch"),
+            active: ColliderActive,
+        }
+    }
+}
+impl Rehydrate<SwitchBundle> for ExportedSwitch {
+    fn rehydrate(self) -> SwitchBundle {
+        SwitchBundle::new(self.pos, self.targets, self.reset)
+    }
+}
+
+/// Flips switches the ship just touched. Switches without a reset toggle, switches with one
+/// turn on (or stay on) and restart their countdown
+pub(crate) fn press_switches(
+    ship_q: Query<&IntDyno, With<Ship>>,
+    mut switches: Query<(Entity, &mut Switch, &mut ParticleSpawner)>,
+    time: Res<Time>,
+    bullet_time: Res<BulletTime>,
+) {
+    for (eid, mut switch, mut spawner) in switches.iter_mut() {
+        if let Some(timer) = switch.timer.as_mut() {
+            timer.tick(time.delta().mul_f32(bullet_time.factor()));
+            if timer.finished() {
+                switch.on = false;
+                switch.timer = None;
+            }
+        }
+        let touching = ship_q.iter().any(|dyno| dyno.triggers.contains_key(&eid));
+        if touching && !switch.held {
+            match switch.reset {
+                Some(secs) => {
+                    switch.on = true;
+                    switch.timer = Some(Timer::from_seconds(secs, TimerMode::Once));
+                }
+                None => switch.on = !switch.on,
+            }
+        }
+        switch.held = touching;
+        spawner.color_range = if switch.on { ON_COLORS } else { OFF_COLORS };
+    }
+}
+
+/// Turns every target on or off to match its switches. A target is flipped from how it started
+/// when an odd number of the switches pointing at it are on
+pub(crate) fn apply_switches(
+    mut commands: Commands,
+    switches: Query<&Switch>,
+    mut targets: Query<(
+        Entity,
+        &SwitchTarget,
+        &mut Visibility,
+        Option<&TrickleColliderActive>,
+    )>,
+) {
+    for (eid, target, mut visibility, trickle) in targets.iter_mut() {
+        let flips = switches
+            .iter()
+            .filter(|switch| switch.on && switch.targets.contains(&target.uid))
+            .count();
+        let active = target.starts_off == (flips % 2 == 1);
+        if trickle.map(|trickle| trickle.0) != Some(active) {
+            commands.entity(eid).insert(TrickleColliderActive(active));
+        }
+        visibility.set_if_neq(if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod switch_tests {
+    use super::*;
+    use crate::{
+        environment::rock::RockKind,
+        meta::old_level_data::{ExportedRock, LevelData},
+        physics::headless::HeadlessSim,
+    };
+
+    /// A wall in the way of a ship heading right, with a switch in front of it
+    fn door_level(reset: Option<f32>) -> LevelData {
+        LevelData {
+            start: IVec2::ZERO,
+            goal: IVec2::new(400, 400),
+            rocks: vec![ExportedRock {
+                kind: RockKind::Normal,
+                points: vec![
+                    IVec2::new(60, -40),
+                    IVec2::new(60, 40),
+                    IVec2::new(70, 40),
+                    IVec2::new(70, -40),
+                ],
+                target: Some(ExportedSwitchTarget {
+                    uid: 7,
+                    starts_off: false,
+                }),
+                ..default()
+            }],
+            switches: vec![ExportedSwitch {
+                pos: IVec2::new(20, 0),
+                targets: vec![7],
+                reset,
+            }],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn switches_open_doors_until_they_reset() {
+        let vel = Vec2::new(2.0, 0.0);
+        let passes = |level_data: LevelData| {
+            let run = HeadlessSim::new(level_data)
+                .with_max_ticks(80)
+                .launch(IVec2::ZERO, vel);
+            run.trajectory.iter().any(|pos| pos.x > 80.0)
+        };
+        let mut unlinked = door_level(None);
+        unlinked.switches[0].targets.clear();
+        assert!(!passes(unlinked));
+        assert!(passes(door_level(None)));
+        assert!(passes(door_level(Some(5.0))));
+        // Closes again long before the ship gets there
+        assert!(!passes(door_level(Some(0.05))));
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
     },
     environment::convo::Convo,
     meta::{
-        consts::{MENU_GROWTH, SCREEN_WIDTH},
+        consts::{MENU_GROWTH, MENU_HEIGHT, MENU_WIDTH, SCREEN_WIDTH},
         game_state::{in_editor, in_level, GameState},
     },
     physics::should_apply_physics,
@@ -48,6 +48,13 @@ pub struct LaunchEvent {
 }
 
 const MULT_THINGY: f32 = 0.32;
+
+/// The fastest launch `watch_mouse` can produce, i.e. dragging corner to corner of the window
+pub fn max_launch_speed() -> f32 {
+    let diag = ((MENU_WIDTH * MENU_WIDTH + MENU_HEIGHT * MENU_HEIGHT) as f32).sqrt();
+    diag.sqrt() * MULT_THINGY
+}
+
 pub fn watch_mouse(
     buttons: Res<ButtonInput<MouseButton>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
@@ -198,7 +205,14 @@ pub fn watch_camera_input(
         ver -= 1.0;
     }
     let raw_dir = Vec2 { x: hor, y: ver };
-    camera_control_state.wasd_dir = if raw_dir.length_squared() > 0.1 {
+    // Ctrl+<key> is an editor shortcut, not a pan
+    let ctrl = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    camera_control_state.wasd_dir = if raw_dir.length_squared() > 0.1 && !ctrl {
         raw_dir.normalize()
     } else {
         Vec2::ZERO
@@ -243,9 +257,22 @@ impl LongKeyPress {
         }
         false
     }
+
+    /// Forget about however long the key has been held
+    pub fn reset(&mut self) {
+        self.ticks_held = 0;
+    }
+
+    /// Makes the next `was_activated` return true, regardless of the keyboard (i.e. for replays)
+    pub fn force_activate(&mut self) {
+        self.ticks_held = self.ticks + 1;
+    }
 }
 
-fn update_long_presses(mut lps: Query<&mut LongKeyPress>, keys: Res<ButtonInput<KeyCode>>) {
+pub(crate) fn update_long_presses(
+    mut lps: Query<&mut LongKeyPress>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
     for mut lp in lps.iter_mut() {
         if keys.pressed(lp.key_code) {
             lp.ticks_held += 1;
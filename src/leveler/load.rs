@@ -12,7 +12,7 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub(super) struct ActivelyLoading(Handle<LevelData>);
 
-pub(super) fn did_level_change(old_state: Res<PrevGameState>, new_state: Res<GameState>) -> bool {
+pub(crate) fn did_level_change(old_state: Res<PrevGameState>, new_state: Res<GameState>) -> bool {
     let Some(current_level) = new_state.get_level_state() else {
         return false;
     };
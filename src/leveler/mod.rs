@@ -1,29 +1,66 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use load::{actively_load, destroy_level, did_level_change, is_actively_loading_level, start_load};
 
 use crate::{
     camera::CameraMarker,
+    cutscenes::is_not_in_cutscene,
     drawing::effects::{ScreenEffect, ScreenEffectManager},
     meta::{
         consts::{FRAMERATE, MENU_GROWTH, MENU_HEIGHT, MENU_WIDTH},
         game_state::{in_level, left_level, GameState, LevelState, MenuState, MetaState},
-        progress::{ActiveSaveFile, GameProgress},
+        progress::{ActiveSaveFile, Galaxies, GameProgress},
     },
-    physics::dyno::{IntDyno, IntMoveable},
+    physics::{
+        dyno::{IntDyno, IntMoveable},
+        should_apply_physics,
+    },
+    replay::{cycle_replay_runs, ReplayState},
     ship::{Dead, Ship},
     sound::effect::SoundEffect,
 };
 
 pub mod load;
 
+/// `FixedUpdate` ticks the ship has spent trying to finish the current level
+#[derive(Resource, Debug, Default)]
+pub struct LevelClock {
+    pub ticks: u32,
+}
+
+fn reset_level_clock(mut clock: ResMut<LevelClock>) {
+    clock.ticks = 0;
+}
+
+/// Stops once the ship makes it to the goal
+fn tick_level_clock(mut clock: ResMut<LevelClock>, ships: Query<&Ship>) {
+    if ships.is_empty() || ships.iter().any(|ship| ship.finished) {
+        return;
+    }
+    clock.ticks += 1;
+}
+
+/// Everything that decides what finishing a level is worth
+#[derive(SystemParam)]
+struct Scoring<'w> {
+    replay_state: Res<'w, ReplayState>,
+    clock: Res<'w, LevelClock>,
+    galaxies: Res<'w, Galaxies>,
+}
+
 fn progress_level(
     mut ships: Query<(&mut Ship, &IntDyno), Without<Dead>>,
     gs: Res<GameState>,
     mut game_progress: Query<&mut GameProgress, With<ActiveSaveFile>>,
     mut screen_effect: ResMut<ScreenEffectManager>,
     cam: Query<&IntMoveable, With<CameraMarker>>,
+    scoring: Scoring,
     mut commands: Commands,
 ) {
+    let Scoring {
+        replay_state,
+        clock,
+        galaxies,
+    } = scoring;
     let Some(level_state) = gs.get_level_state() else {
         // warn!("Weird stuff happening in progress_level level_state");
         return;
@@ -35,13 +72,9 @@ fn progress_level(
     let Ok(cam) = cam.get_single() else {
         return;
     };
-    let saturated_goal = ships.iter().any(|(ship, dyno)| {
-        // fuck it hopefully the compiler optimizes this
-        let enough_time = !ship.finished && ship.time_in_goal > FRAMERATE as f32 * 0.75;
-        let close_enough = ship.dist_to_goal_center_sq < 1.0;
-        let slow_enough = dyno.vel.length_squared() < 0.05;
-        enough_time && slow_enough && close_enough
-    });
+    let saturated_goal = ships
+        .iter()
+        .any(|(ship, dyno)| ship.is_settled_in_goal(dyno));
     if !saturated_goal {
         return;
     }
@@ -71,13 +104,28 @@ fn progress_level(
             screen_effect.queue_effect(ScreenEffect::UnfadeToBlack);
         }
     };
-    match game_progress.try_mark_completed(level_state.kind, level_state.id.clone()) {
+    // Watching a replay doesn't count as playing, so it can't earn or unlock anything
+    if replay_state.is_playing() {
+        go_to_meta(MetaState::Menu(MenuState::GalaxyOverworld), true);
+        return;
+    }
+    let num_shots = level_state.num_shots.max(0) as u32;
+    let stars = galaxies
+        .get_level_meta(&level_state.kind, &level_state.id)
+        .map_or(1, |meta| meta.stars_for(num_shots));
+    game_progress.record_completion(
+        &level_state.id,
+        num_shots,
+        clock.ticks as f32 / FRAMERATE as f32,
+        stars,
+    );
+    match game_progress.try_mark_completed(&galaxies, &level_state.kind, level_state.id.clone()) {
         Err(e) => {
             warn!("Can't mark completed: {e:?}");
             go_to_meta(MetaState::Menu(MenuState::GalaxyOverworld), true);
         }
         Ok(_) => {
-            let galaxy_progress = game_progress.get_galaxy_progress(level_state.kind);
+            let galaxy_progress = game_progress.get_galaxy_progress(&galaxies, &level_state.kind);
             match galaxy_progress.next_level {
                 Some(level_id) => {
                     go_to_meta(
@@ -97,12 +145,68 @@ fn progress_level(
     }
 }
 
+fn count_level_attempts(
+    gs: Res<GameState>,
+    mut game_progress: Query<&mut GameProgress, With<ActiveSaveFile>>,
+    replay_state: Res<ReplayState>,
+) {
+    let (Some(level_state), Ok(mut game_progress)) =
+        (gs.get_level_state(), game_progress.get_single_mut())
+    else {
+        return;
+    };
+    if replay_state.is_playing() {
+        return;
+    }
+    game_progress.record_attempt(&level_state.id);
+}
+
+fn count_level_deaths(
+    dead_ships: Query<&Dead, (With<Ship>, Added<Dead>)>,
+    gs: Res<GameState>,
+    mut game_progress: Query<&mut GameProgress, With<ActiveSaveFile>>,
+    replay_state: Res<ReplayState>,
+) {
+    let (Some(level_state), Ok(mut game_progress)) =
+        (gs.get_level_state(), game_progress.get_single_mut())
+    else {
+        return;
+    };
+    if replay_state.is_playing() {
+        return;
+    }
+    for dead in dead_ships.iter() {
+        let Dead::Explosion(cause) = dead;
+        game_progress.record_death(&level_state.id, *cause);
+    }
+}
+
 pub struct LevelerPlugin;
 impl Plugin for LevelerPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<LevelClock>();
+        app.add_systems(
+            Update,
+            reset_level_clock.run_if(did_level_change.or_else(left_level)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            tick_level_clock
+                .run_if(in_level)
+                .run_if(should_apply_physics)
+                .run_if(is_not_in_cutscene),
+        );
         app.add_systems(Update, start_load.run_if(did_level_change));
         app.add_systems(Update, actively_load.run_if(is_actively_loading_level));
         app.add_systems(Update, destroy_level.run_if(left_level));
         app.add_systems(Update, progress_level.run_if(in_level));
+        // After the replay module decides whether this attempt is being played back
+        app.add_systems(
+            Update,
+            count_level_attempts
+                .run_if(did_level_change)
+                .after(cycle_replay_runs),
+        );
+        app.add_systems(Update, count_level_deaths.run_if(in_level));
     }
 }
@@ -1,4 +1,5 @@
 pub mod camera;
+pub mod cli;
 pub mod cutscenes;
 pub mod drawing;
 pub mod editor;
@@ -10,6 +11,7 @@ pub mod menu;
 pub mod meta;
 pub mod old_editor;
 pub mod physics;
+pub mod replay;
 pub mod ship;
 pub mod sound;
 pub mod uid;
@@ -31,6 +33,7 @@ use meta::{
     MetaPlugin,
 };
 use physics::PhysicsPlugin;
+use replay::{register_replay, ReplayState};
 use ship::register_ship;
 use sound::SoundPlugin;
 use uid::UIdPlugin;
@@ -55,6 +58,10 @@ fn main_update(
 
 fn main() {
     env_logger::init();
+    let replay = match cli::run_cli() {
+        cli::CliAction::Exit(code) => std::process::exit(code),
+        cli::CliAction::Play(replay) => replay,
+    };
     let mut app = App::new();
     app.add_plugins(
         DefaultPlugins
@@ -100,5 +107,9 @@ fn main() {
     register_drawing(&mut app);
     register_input(&mut app);
     register_ship(&mut app);
+    register_replay(&mut app);
+    if let Some(replay) = replay {
+        app.insert_resource(ReplayState::queue(replay));
+    }
     app.run();
 }
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use linreg::linear_regression;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MathLine {
     pub p1: Vec2,
     pub p2: Vec2,
@@ -112,7 +112,7 @@ impl MathLine {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MathTriangle {
     pub lines: [MathLine; 3],
 }
@@ -290,7 +290,7 @@ pub fn uvec2mesh_points(v: UVec2) -> Vec<Vec2> {
     ]
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, serde::Serialize, serde::Deserialize)]
 pub enum Spleen {
     EaseInCubic,
     EaseOutCubic,
@@ -307,6 +307,21 @@ pub enum Spleen {
 }
 
 impl Spleen {
+    pub const ALL: [Self; 12] = [
+        Self::EaseInCubic,
+        Self::EaseOutCubic,
+        Self::EaseInOutCubic,
+        Self::EaseInQuad,
+        Self::EaseOutQuad,
+        Self::EaseInOutQuad,
+        Self::EaseInQuartic,
+        Self::EaseOutQuartic,
+        Self::EaseInOutQuartic,
+        Self::EaseInQuintic,
+        Self::EaseOutQuintic,
+        Self::EaseInOutQuintic,
+    ];
+
     pub fn interp(&self, x: f32) -> f32 {
         match *self {
             Self::EaseInCubic => ease_in_cubic(x),
@@ -328,6 +343,25 @@ impl Spleen {
         min + self.interp(x) * (max - min)
     }
 }
+impl std::fmt::Display for Spleen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match *self {
+            Self::EaseInCubic => "ease_in_cubic",
+            Self::EaseOutCubic => "ease_out_cubic",
+            Self::EaseInOutCubic => "ease_in_out_cubic",
+            Self::EaseInQuad => "ease_in_quad",
+            Self::EaseOutQuad => "ease_out_quad",
+            Self::EaseInOutQuad => "ease_in_out_quad",
+            Self::EaseInQuartic => "ease_in_quartic",
+            Self::EaseOutQuartic => "ease_out_quartic",
+            Self::EaseInOutQuartic => "ease_in_out_quartic",
+            Self::EaseInQuintic => "ease_in_quintic",
+            Self::EaseOutQuintic => "ease_out_quintic",
+            Self::EaseInOutQuintic => "ease_in_out_quintic",
+        };
+        write!(f, "{}", s)
+    }
+}
 
 fn ease_in_cubic(x: f32) -> f32 {
     x * x * x
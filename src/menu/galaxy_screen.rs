@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 use crate::{
     drawing::{
@@ -16,7 +16,10 @@ use crate::{
     math::Spleen,
     meta::{
         game_state::{GameState, LevelState, MenuState, MetaState},
-        progress::{ActiveSaveFile, GalaxyKind, GameProgress},
+        old_level_data::LevelData,
+        progress::{
+            ActiveSaveFile, CatalogLevels, Galaxies, GalaxyKind, GalaxyMetaData, GameProgress,
+        },
     },
     physics::dyno::IntMoveableBundle,
     ship::Ship,
@@ -49,7 +52,7 @@ struct LittleShipBundle {
     name: Name,
 }
 impl LittleShipBundle {
-    pub fn new(kind: GalaxyKind) -> Self {
+    pub fn new(galaxies: &Galaxies, kind: &GalaxyKind) -> Self {
         let multi = MultiAnimationManager::from_pairs(vec![
             (
                 "ship",
@@ -71,7 +74,7 @@ impl LittleShipBundle {
                 .force_render_layer(light_layer_u8()),
             ),
         ]);
-        let x_offset = galaxy_offset(kind) as f32;
+        let x_offset = galaxy_offset(galaxies, kind) as f32;
         let spatial = SpatialBundle::from_transform(Transform {
             translation: Vec3::new(x_offset, 0.0, 2.0),
             scale: Vec3::new(0.0, 0.0, 1.0),
@@ -86,8 +89,8 @@ impl LittleShipBundle {
     }
 }
 
-fn galaxy_offset(kind: GalaxyKind) -> i32 {
-    kind.rank() as i32 * 96
+fn galaxy_offset(galaxies: &Galaxies, kind: &GalaxyKind) -> i32 {
+    galaxies.rank(kind) as i32 * 96
 }
 
 #[derive(Bundle)]
@@ -99,53 +102,38 @@ struct GalaxyChoiceBundle {
     spatial: SpatialBundle,
 }
 impl GalaxyChoiceBundle {
-    fn from_kind(kind: GalaxyKind, selected: bool, game_progress: &GameProgress) -> Self {
-        let galaxy_progress = game_progress.get_galaxy_progress(kind);
-        let multi = match kind {
-            GalaxyKind::Basic => MultiAnimationManager::from_pairs(vec![
-                (
-                    "galaxy",
-                    AnimationManager::single_static(SpriteInfo {
-                        path: "sprites/menu/galaxy/basic.png".to_string(),
-                        size: UVec2::new(36, 36),
-                        ..default()
-                    }),
-                ),
-                (
-                    "light",
-                    AnimationManager::single_static(SpriteInfo {
-                        path: "sprites/menu/galaxy/basicL.png".to_string(),
-                        size: UVec2::new(36, 36),
-                        ..default()
-                    })
-                    .force_render_layer(light_layer_u8()),
-                ),
-            ]),
-            GalaxyKind::Springy => MultiAnimationManager::from_pairs(vec![
-                (
-                    "galaxy",
-                    AnimationManager::single_static(SpriteInfo {
-                        path: "sprites/menu/galaxy/basic.png".to_string(),
-                        size: UVec2::new(36, 36),
-                        ..default()
-                    }),
-                ),
-                (
-                    "light",
-                    AnimationManager::single_static(SpriteInfo {
-                        path: "sprites/menu/galaxy/basicL.png".to_string(),
-                        size: UVec2::new(36, 36),
-                        ..default()
-                    })
-                    .force_render_layer(light_layer_u8()),
-                ),
-            ]),
-        };
-        let meta = kind.to_meta_data();
-        let text = if game_progress.is_playable(kind) {
-            let (num, den) = galaxy_progress.portion_completed(kind);
-            let progress_str = format!("{} / {}", num, den);
-            let pairs = vec![
+    fn from_meta(
+        galaxies: &Galaxies,
+        meta: &GalaxyMetaData,
+        selected: bool,
+        game_progress: &GameProgress,
+        num_stardust: impl Fn(&str) -> u32,
+    ) -> Self {
+        let kind = &meta.id;
+        let multi = MultiAnimationManager::from_pairs(vec![
+            (
+                "galaxy",
+                AnimationManager::single_static(SpriteInfo {
+                    path: format!("sprites/menu/galaxy/{}.png", meta.sprite),
+                    size: UVec2::new(36, 36),
+                    ..default()
+                }),
+            ),
+            (
+                "light",
+                AnimationManager::single_static(SpriteInfo {
+                    path: format!("sprites/menu/galaxy/{}L.png", meta.sprite),
+                    size: UVec2::new(36, 36),
+                    ..default()
+                })
+                .force_render_layer(light_layer_u8()),
+            ),
+        ]);
+        let text = if game_progress.is_playable(galaxies, kind) {
+            let completion = game_progress.galaxy_completion(galaxies, kind, num_stardust);
+            let (stars, max_stars) = game_progress.galaxy_stars(galaxies, kind);
+            let progress_str = format!("{}%   {} / {} *", completion, stars, max_stars);
+            let mut pairs = vec![
                 (
                     "title",
                     TextNode {
@@ -167,12 +155,36 @@ impl GalaxyChoiceBundle {
                     },
                 ),
             ];
+            // Stats for each level, listed under the progress
+            let level_keys: Vec<String> = meta
+                .levels
+                .iter()
+                .map(|level| format!("level_{}", level.id))
+                .collect();
+            for (ix, level) in meta.levels.iter().enumerate() {
+                let stats = game_progress.get_level_stats(&level.id);
+                pairs.push((
+                    level_keys[ix].as_str(),
+                    TextNode {
+                        content: format!("{}: {}", level.title, stats.summary()),
+                        size: 8.0,
+                        pos: IVec3::new(0, -48 - 8 * ix as i32, 0),
+                        light: selected,
+                        ..default()
+                    },
+                ));
+            }
             TextManager::from_pairs(pairs)
         } else {
+            let content = if game_progress.has_stars_for(galaxies, kind) {
+                "???".to_string()
+            } else {
+                format!("??? ({} *)", meta.required_stars)
+            };
             TextManager::from_pairs(vec![(
                 "title",
                 TextNode {
-                    content: "???".to_string(),
+                    content,
                     size: 16.0,
                     pos: IVec3::new(0, -24, 0),
                     light: selected,
@@ -181,12 +193,15 @@ impl GalaxyChoiceBundle {
             )])
         };
         let spatial = SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
-            galaxy_offset(kind) as f32,
+            galaxy_offset(galaxies, kind) as f32,
             0.0,
             0.0,
         )));
         Self {
-            info: GalaxyChoice { kind, ..default() },
+            info: GalaxyChoice {
+                kind: kind.clone(),
+                ..default()
+            },
             multi,
             name: Name::new(format!("galaxy_choice_{}", kind)),
             text,
@@ -198,42 +213,56 @@ impl GalaxyChoiceBundle {
 fn setup_galaxy_screen(
     mut commands: Commands,
     progress: Query<&GameProgress, With<ActiveSaveFile>>,
+    galaxies: Res<Galaxies>,
+    catalog_levels: Res<CatalogLevels>,
+    levels: Res<Assets<LevelData>>,
 ) {
     let progress = progress.single();
-    let active_galaxy = progress.first_incomplete_galaxy();
+    let num_stardust = |id: &str| catalog_levels.num_stardust(&levels, id);
+    let active_galaxy = progress.first_incomplete_galaxy(&galaxies);
+    let root_offset = -galaxy_offset(&galaxies, &active_galaxy);
     commands
         .spawn((
             GalaxyScreenRoot {
-                selected: active_galaxy,
+                selected: active_galaxy.clone(),
             },
             Name::new("galaxy_screen"),
-            IntMoveableBundle::new(IVec3::new(-galaxy_offset(active_galaxy), 28, 0)),
+            IntMoveableBundle::new(IVec3::new(root_offset, 28, 0)),
         ))
         .with_children(|parent| {
-            for kind in GalaxyKind::all() {
-                parent.spawn(GalaxyChoiceBundle::from_kind(
-                    kind,
-                    kind == active_galaxy,
+            for meta in galaxies.galaxies.iter() {
+                parent.spawn(GalaxyChoiceBundle::from_meta(
+                    &galaxies,
+                    meta,
+                    meta.id == active_galaxy,
                     progress,
+                    num_stardust,
                 ));
             }
-            parent.spawn(LittleShipBundle::new(active_galaxy));
+            parent.spawn(LittleShipBundle::new(&galaxies, &active_galaxy));
         });
 }
 
+/// The parts of the galaxy screen that slide around when the selected galaxy changes
+#[derive(SystemParam)]
+struct GalaxyScreenParts<'w, 's> {
+    root: Query<'w, 's, (Entity, &'static mut GalaxyScreenRoot, &'static Transform)>,
+    little_ship: Query<'w, 's, (Entity, &'static Transform), With<LittleShip>>,
+}
+
 fn handle_galaxy_screen_input(
-    mut root: Query<(Entity, &mut GalaxyScreenRoot, &Transform)>,
-    little_ship: Query<(Entity, &Transform), With<LittleShip>>,
+    mut parts: GalaxyScreenParts,
     mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut progress: Query<&mut GameProgress, With<ActiveSaveFile>>,
     bg_manager: Res<BgManager>,
     mut screen_manager: ResMut<ScreenEffectManager>,
+    galaxies: Res<Galaxies>,
 ) {
-    let Ok((eid, mut root, tran)) = root.get_single_mut() else {
+    let Ok((eid, mut root, tran)) = parts.root.get_single_mut() else {
         return;
     };
-    let Ok((ship_eid, ship_tran)) = little_ship.get_single() else {
+    let Ok((ship_eid, ship_tran)) = parts.little_ship.get_single() else {
         return;
     };
     let mut progress = progress.single_mut();
@@ -242,33 +271,42 @@ fn handle_galaxy_screen_input(
         && !bg_manager.has_stateful_effect()
         && screen_manager.is_effect_none()
     {
-        let Some(next_level) = progress.get_galaxy_progress(root.selected).next_level else {
-            if let Err(e) = progress.try_restart_galaxy(root.selected) {
+        let Some(next_level) = progress
+            .get_galaxy_progress(&galaxies, &root.selected)
+            .next_level
+        else {
+            if let Err(e) = progress.try_restart_galaxy(&galaxies, &root.selected) {
                 warn!("Couldn't restart galaxy {:?}, err: {e:?}", root.selected);
                 return;
             }
             let next_level = progress
-                .get_galaxy_progress(root.selected)
+                .get_galaxy_progress(&galaxies, &root.selected)
                 .next_level
                 .clone()
                 .unwrap();
             screen_manager.queue_effect(ScreenEffect::FadeToBlack(Some(GameState {
-                meta: MetaState::Level(LevelState::from_galaxy_n_level(root.selected, next_level)),
+                meta: MetaState::Level(LevelState::from_galaxy_n_level(
+                    root.selected.clone(),
+                    next_level,
+                )),
                 pause: None,
             })));
             return;
         };
         screen_manager.queue_effect(ScreenEffect::FadeToBlack(Some(GameState {
-            meta: MetaState::Level(LevelState::from_galaxy_n_level(root.selected, next_level)),
+            meta: MetaState::Level(LevelState::from_galaxy_n_level(
+                root.selected.clone(),
+                next_level,
+            )),
             pause: None,
         })));
         return;
     }
     let new_kind = {
         if keyboard.just_pressed(KeyCode::ArrowLeft) {
-            root.selected.prev()
+            galaxies.prev(&root.selected)
         } else if keyboard.just_pressed(KeyCode::ArrowRight) {
-            root.selected.next()
+            galaxies.next(&root.selected)
         } else {
             None
         }
@@ -277,14 +315,14 @@ fn handle_galaxy_screen_input(
         // Nothing to do
         return;
     };
-    if !progress.is_playable(new_kind) {
+    if !progress.is_playable(&galaxies, &new_kind) {
         // Can't play this level
         return;
     }
     let duration = 0.75;
     // Insert the root effect
     let start_val = tran.translation.x;
-    let end_val = -galaxy_offset(new_kind) as f32;
+    let end_val = -galaxy_offset(&galaxies, &new_kind) as f32;
     root.selected = new_kind;
     let effect_val = EffectVal::new(start_val, end_val, Spleen::EaseInOutCubic, duration);
     commands.entity(eid).insert(effect_val);
@@ -10,7 +10,9 @@ use crate::{
     meta::{
         consts::{MENU_HEIGHT, MENU_WIDTH},
         game_state::{GameState, MenuState, MetaState, PauseState, PrevGameState, SetPaused},
+        progress::{ActiveSaveFile, Galaxies, GameProgress},
     },
+    physics::preview::AssistSettings,
     sound::SoundSettings,
 };
 
@@ -163,6 +165,8 @@ pub(super) fn setup_any_pause(gs: Res<GameState>, mut commands: Commands) {
 pub(super) fn setup_specific_pause(
     gs: Res<GameState>,
     roots: Query<Entity, With<PauseRoot>>,
+    progress: Query<&GameProgress, With<ActiveSaveFile>>,
+    galaxies: Res<Galaxies>,
     mut commands: Commands,
 ) {
     let Some(pause) = gs.pause else {
@@ -174,9 +178,35 @@ pub(super) fn setup_specific_pause(
     }
     match pause {
         PauseState::Level => {
+            // Par and best stars for the current level
+            let level_info = gs.get_level_state().and_then(|level_state| {
+                let meta = galaxies.get_level_meta(&level_state.kind, &level_state.id)?;
+                let par_str = match meta.par {
+                    Some(par) => format!("par {par}"),
+                    None => "no par".to_string(),
+                };
+                let stars_str = match progress.get_single() {
+                    Ok(progress) => progress.get_level_stats(&meta.id).stars_str(),
+                    Err(_) => String::new(),
+                };
+                Some(format!(
+                    "{}   {} shots, {par_str}   {stars_str}",
+                    meta.title, level_state.num_shots
+                ))
+            });
             commands
                 .spawn(PauseRoot::new_root("level"))
                 .with_children(|parent| {
+                    if let Some(level_info) = level_info {
+                        parent.spawn(TextBoxBundle::new_menu_text(
+                            &level_info,
+                            24.0,
+                            GameRelativePlacement::new(IVec3::new(0, 48, 12), 0.5),
+                            Color::WHITE,
+                            TextWeight::default(),
+                            TextAlign::Center,
+                        ));
+                    }
                     parent.spawn(MenuButtonBundle::new(
                         MenuButton::basic("go_settings", "Settings"),
                         GameRelativePlacement::new(IVec3::new(0, 24, 12), 1.0),
@@ -261,6 +291,21 @@ pub(super) fn setup_specific_pause(
                             GameRelativePlacement::new(IVec3::new(x, -42, 12), 1.0),
                         ));
                     }
+
+                    // Shot preview assist toggle
+                    let preview_bund = TextBoxBundle::new_menu_text(
+                        "Shot Preview",
+                        24.0,
+                        GameRelativePlacement::new(IVec3::new(0, -62, 12), 0.5),
+                        Color::WHITE,
+                        TextWeight::default(),
+                        TextAlign::Center,
+                    );
+                    parent.spawn(preview_bund);
+                    parent.spawn(MenuButtonBundle::new(
+                        MenuButton::basic("toggle_shot_preview", " "),
+                        GameRelativePlacement::new(IVec3::new(0, -78, 12), 1.0),
+                    ));
                 });
         }
     }
@@ -269,6 +314,7 @@ pub(super) fn setup_specific_pause(
 pub(super) fn update_pause(
     gs: Res<GameState>,
     mut sound_settings: ResMut<SoundSettings>,
+    mut assist_settings: ResMut<AssistSettings>,
     mut buttons: Query<&mut MenuButtonFill>,
     mut button_pressed: EventReader<MenuButtonPressed>,
     mut screen_effects: ResMut<ScreenEffectManager>,
@@ -358,6 +404,9 @@ pub(super) fn update_pause(
                         .unwrap();
                     sound_settings.effect_volume = last_char_int as f32 / 5.0;
                 }
+                if last_button.0 == "toggle_shot_preview" {
+                    assist_settings.shot_preview = !assist_settings.shot_preview;
+                }
             }
 
             // Whether each fill should be marked as selected
@@ -375,6 +424,10 @@ pub(super) fn update_pause(
             let effect_id = format!("set_effect_volume{effect_selected}");
             selected_set.insert(effect_id);
 
+            if assist_settings.shot_preview {
+                selected_set.insert("toggle_shot_preview".to_string());
+            }
+
             for mut fill in buttons.iter_mut() {
                 let id = fill.id.clone();
                 fill.is_selected = selected_set.contains(&id);
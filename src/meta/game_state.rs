@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 
-use crate::environment::convo::{data::ConvoKind, StartConvo};
+use crate::{
+    environment::{
+        convo::{data::ConvoKind, StartConvo},
+        segment::SegmentKind,
+    },
+    uid::UId,
+};
 
 use super::progress::GalaxyKind;
 
@@ -19,6 +25,20 @@ pub enum EditingMode {
     EditingRock(Entity),
     CreatingField(Entity),
     EditingField(Entity),
+    /// Right click attaches a segment to the closest rock edge
+    PlacingSegment(SegmentKind),
+    /// Right click drops a replenish
+    PlacingReplenish,
+    /// Right click drops a switch
+    PlacingSwitch,
+    /// Right click drops a stardust
+    PlacingStardust,
+    /// Right click drops both ends of a portal
+    PlacingPortal,
+    /// Right click moves the start
+    PlacingStart,
+    /// Right click moves the goal
+    PlacingGoal,
 }
 impl EditingMode {
     pub fn to_meta_state(&self) -> MetaState {
@@ -56,6 +76,13 @@ impl EditorState {
                 EditingMode::EditingRock(id) => Some(EditingMode::EditingRock(id)),
                 EditingMode::CreatingField(id) => Some(EditingMode::CreatingField(id)),
                 EditingMode::EditingField(id) => Some(EditingMode::EditingField(id)),
+                EditingMode::PlacingSegment(kind) => Some(EditingMode::PlacingSegment(kind)),
+                EditingMode::PlacingReplenish => Some(EditingMode::PlacingReplenish),
+                EditingMode::PlacingSwitch => Some(EditingMode::PlacingSwitch),
+                EditingMode::PlacingStardust => Some(EditingMode::PlacingStardust),
+                EditingMode::PlacingPortal => Some(EditingMode::PlacingPortal),
+                EditingMode::PlacingStart => Some(EditingMode::PlacingStart),
+                EditingMode::PlacingGoal => Some(EditingMode::PlacingGoal),
             },
             _ => None,
         }
@@ -72,6 +99,8 @@ pub struct LevelState {
     pub id: String,
     pub is_won: bool,
     pub num_shots: i32,
+    /// Uids of the stardust picked up this attempt
+    pub stardust: Vec<UId>,
 }
 impl LevelState {
     pub fn from_galaxy_n_level(kind: GalaxyKind, level_id: String) -> Self {
@@ -80,6 +109,7 @@ impl LevelState {
             id: level_id,
             is_won: false,
             num_shots: 0,
+            stardust: vec![],
         }
     }
 }
@@ -217,6 +247,7 @@ pub fn register_game_state(app: &mut App) {
         kind: GalaxyKind::default(),
         is_won: false,
         num_shots: 0,
+        stardust: vec![],
     });
     app.insert_resource(GameState {
         meta: initial_state.clone(),
@@ -0,0 +1,460 @@
+//! Versioning for level files.
+//! `LevelData` (in `old_level_data`) is always the newest shape, and writes its `version`.
+//! Older files are parsed as the newest shape and then upgraded one version at a time, so
+//! changing `LevelData` (or `ExportedRock`, `ExportedField`...) goes like:
+//! 1. New fields get `#[serde(default)]`, so older files still parse (no version bump needed)
+//! 2. Anything defaults can't cover bumps `LEVEL_VERSION` and migrates in `LevelData::upgrade`
+//! 3. `zenith upgrade` to rewrite the levels on disk
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    environment::{field::FieldDrag, rock::RockKind, segment::SegmentKind},
+    uid::UId,
+};
+
+use super::old_level_data::{
+    ExportedField, ExportedReplenish, ExportedRock, ExportedSegment, LevelData,
+};
+
+/// Version written by this build
+pub const LEVEL_VERSION: u32 = 9;
+
+/// Only used to peek at the version of a level, so levels from newer builds are turned away.
+/// Files from before versioning don't have one, and count as version 0
+#[derive(Deserialize)]
+#[serde(rename = "LevelData")]
+struct VersionPeek {
+    #[serde(default)]
+    version: u32,
+}
+
+impl LevelData {
+    /// Moves a level up one version.
+    /// Added fields are `#[serde(default)]`, so they're already filled in by the time this runs.
+    /// Changes that rename or reinterpret a field migrate it here, keyed on `self.version`
+    fn upgrade(mut self) -> Self {
+        // v8 -> v9: `FieldDrag::Normal` used to do nothing (drag wasn't applied), now it's real
+        if self.version == 8 {
+            for field in self.fields.iter_mut() {
+                if field.drag == FieldDrag::Normal {
+                    field.drag = FieldDrag::Off;
+                }
+            }
+        }
+        self.version += 1;
+        self
+    }
+}
+
+/// Parses a level of any version, migrating it up to `LEVEL_VERSION`.
+/// Also returns the version it was saved as
+pub fn parse_level(contents: &str) -> Result<(LevelData, u32), String> {
+    let peek: VersionPeek = ron::from_str(contents).map_err(|e| e.to_string())?;
+    if peek.version > LEVEL_VERSION {
+        return Err(format!(
+            "level is version {}, but this build only understands up to {LEVEL_VERSION}",
+            peek.version
+        ));
+    }
+    let mut level_data: LevelData = ron::from_str(contents).map_err(|e| e.to_string())?;
+    while level_data.version < LEVEL_VERSION {
+        level_data = level_data.upgrade();
+    }
+    Ok((level_data, peek.version))
+}
+
+/// The bits of the old editor's saved scenes (`.scn.ron`) that make up a level.
+/// Scenes name components by their full type path, which has moved since they were saved
+/// (`editor` -> `old_editor`), so components are matched on just the type name
+mod scene {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct Vec3 {
+        pub x: f32,
+        pub y: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Affine {
+        pub translation: Vec3,
+    }
+
+    #[derive(Deserialize)]
+    pub struct GlobalTransform(pub Affine);
+    impl GlobalTransform {
+        pub fn ipos(&self) -> IVec2 {
+            let translation = &self.0.translation;
+            IVec2::new(translation.x.round() as i32, translation.y.round() as i32)
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct UIdMarker(pub UId);
+
+    #[derive(Deserialize)]
+    pub struct Field {
+        pub field_points: Vec<UId>,
+        pub dir: Vec2,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Planet {
+        pub rock_points: Vec<UId>,
+        pub rock_kind: RockKind,
+        pub fields: Vec<Field>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SegmentParents {
+        pub left_uid: UId,
+        pub right_uid: UId,
+    }
+
+    #[derive(Default)]
+    pub struct Components {
+        pub global_transform: Option<GlobalTransform>,
+        pub uid: Option<UId>,
+        pub is_start: bool,
+        pub is_goal: bool,
+        pub is_replenish: bool,
+        pub planet: Option<Planet>,
+        pub standalone_field: Option<Field>,
+        pub segment_parents: Option<SegmentParents>,
+        pub segment_kind: Option<SegmentKind>,
+    }
+    impl<'de> Deserialize<'de> for Components {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ComponentsVisitor;
+            impl<'de> Visitor<'de> for ComponentsVisitor {
+                type Value = Components;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    write!(f, "a map of component type paths to components")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Components, A::Error> {
+                    let mut result = Components::default();
+                    while let Some(type_path) = map.next_key::<String>()? {
+                        let type_name = type_path.rsplit("::").next().unwrap_or_default();
+                        match type_name {
+                            "GlobalTransform" => result.global_transform = Some(map.next_value()?),
+                            "UIdMarker" => {
+                                result.uid = Some(map.next_value::<UIdMarker>()?.0);
+                            }
+                            "EPlanet" => result.planet = Some(map.next_value()?),
+                            "EStandaloneField" => result.standalone_field = Some(map.next_value()?),
+                            "SegmentParents" => result.segment_parents = Some(map.next_value()?),
+                            "SegmentKind" => result.segment_kind = Some(map.next_value()?),
+                            "EStart" => {
+                                map.next_value::<IgnoredAny>()?;
+                                result.is_start = true;
+                            }
+                            "EGoal" => {
+                                map.next_value::<IgnoredAny>()?;
+                                result.is_goal = true;
+                            }
+                            "EReplenish" => {
+                                map.next_value::<IgnoredAny>()?;
+                                result.is_replenish = true;
+                            }
+                            _ => {
+                                map.next_value::<IgnoredAny>()?;
+                            }
+                        }
+                    }
+                    Ok(result)
+                }
+            }
+            deserializer.deserialize_map(ComponentsVisitor)
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Entity {
+        pub components: Components,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Scene {
+        pub entities: HashMap<u64, Entity>,
+    }
+}
+
+/// Crystallizes a scene saved by the old editor into a level
+pub fn level_from_scene(contents: &str) -> Result<LevelData, String> {
+    let scene: scene::Scene = ron::from_str(contents).map_err(|e| e.to_string())?;
+    let entities: Vec<&scene::Components> = scene
+        .entities
+        .values()
+        .map(|entity| &entity.components)
+        .collect();
+    let ipos = |components: &scene::Components| {
+        components
+            .global_transform
+            .as_ref()
+            .map(|gt| gt.ipos())
+            .ok_or_else(|| "entity is missing its GlobalTransform".to_string())
+    };
+    // Points are referenced by uid, but standalone fields were saved pointing at entities
+    let mut points = HashMap::new();
+    for (entity_id, entity) in scene.entities.iter() {
+        if let Some(gt) = entity.components.global_transform.as_ref() {
+            points.insert(*entity_id, gt.ipos());
+            if let Some(uid) = entity.components.uid {
+                points.insert(uid, gt.ipos());
+            }
+        }
+    }
+    let resolve = |ids: &[UId]| -> Result<Vec<IVec2>, String> {
+        ids.iter()
+            .map(|id| {
+                points
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| format!("scene references missing point {id}"))
+            })
+            .collect()
+    };
+    let find_one = |pred: fn(&scene::Components) -> bool, what: &str| {
+        let found = entities.iter().find(|components| pred(components));
+        match found {
+            Some(components) => ipos(components),
+            None => Err(format!("scene has no {what}")),
+        }
+    };
+    let mut level_data = LevelData {
+        start: find_one(|components| components.is_start, "start")?,
+        goal: find_one(|components| components.is_goal, "goal")?,
+        ..default()
+    };
+    // Sort so that the output doesn't depend on hash order
+    let mut planets: Vec<&scene::Planet> = entities
+        .iter()
+        .filter_map(|components| components.planet.as_ref())
+        .collect();
+    planets.sort_by_key(|planet| planet.rock_points.first().copied());
+    for planet in planets {
+        level_data.rocks.push(ExportedRock {
+            kind: planet.rock_kind,
+            points: resolve(&planet.rock_points)?,
+            z: 0,
+            bounciness: None,
+            path: None,
+            rotation: None,
+            target: None,
+        });
+        for field in planet.fields.iter() {
+            level_data.fields.push(ExportedField {
+                points: resolve(&field.field_points)?,
+                dir: field.dir,
+                drag: FieldDrag::Off,
+                ..default()
+            });
+        }
+    }
+    for components in entities.iter() {
+        if let Some(field) = components.standalone_field.as_ref() {
+            level_data.fields.push(ExportedField {
+                points: resolve(&field.field_points)?,
+                dir: field.dir,
+                drag: FieldDrag::Off,
+                ..default()
+            });
+        }
+        if let (Some(parents), Some(kind)) =
+            (components.segment_parents.as_ref(), components.segment_kind)
+        {
+            let ends = resolve(&[parents.left_uid, parents.right_uid])?;
+            level_data.segments.push(ExportedSegment {
+                kind,
+                left_parent: ends[0],
+                right_parent: ends[1],
+            });
+        }
+        if components.is_replenish {
+            level_data.replenishes.push(ExportedReplenish {
+                pos: ipos(components)?,
+            });
+        }
+    }
+    level_data
+        .fields
+        .sort_by_key(|field| field.points.first().map(|p| (p.x, p.y)));
+    level_data
+        .segments
+        .sort_by_key(|segment| (segment.left_parent.x, segment.left_parent.y));
+    level_data
+        .replenishes
+        .sort_by_key(|replenish| (replenish.pos.x, replenish.pos.y));
+    Ok(level_data)
+}
+
+/// What `upgrade_file` did (or would do)
+#[derive(Debug, PartialEq)]
+pub enum Upgrade {
+    UpToDate,
+    Migrated {
+        from: u32,
+    },
+    FromScene {
+        level_path: PathBuf,
+    },
+    /// Scene that's already been turned into a level, left alone so edits to the level aren't lost
+    SceneAlreadyConverted {
+        level_path: PathBuf,
+    },
+}
+
+/// Rewrites a level at `path` as the current version. Scenes (`.scn.ron`) are written
+/// next to themselves as `.level.ron`. With `dry_run` nothing is written
+pub fn upgrade_file(path: &Path, dry_run: bool) -> Result<Upgrade, String> {
+    let name = path.to_string_lossy().to_string();
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (level_data, result) = match name.strip_suffix(".scn.ron") {
+        Some(stem) => {
+            let level_path = PathBuf::from(format!("{stem}.level.ron"));
+            if level_path.exists() {
+                return Ok(Upgrade::SceneAlreadyConverted { level_path });
+            }
+            let level_data = level_from_scene(&contents)?;
+            (level_data, Upgrade::FromScene { level_path })
+        }
+        None => {
+            let (level_data, version) = parse_level(&contents)?;
+            if version == LEVEL_VERSION {
+                return Ok(Upgrade::UpToDate);
+            }
+            (level_data, Upgrade::Migrated { from: version })
+        }
+    };
+    if !dry_run {
+        let out_path = match &result {
+            Upgrade::FromScene { level_path } => level_path.as_path(),
+            _ => path,
+        };
+        level_data.write_to_disk(out_path)?;
+    }
+    Ok(result)
+}
+
+/// Loads `.level.ron` files of any version, migrating them as they load
+#[derive(Default)]
+pub struct LevelDataLoader;
+impl AssetLoader for LevelDataLoader {
+    type Asset = LevelData;
+    type Settings = ();
+    type Error = String;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<LevelData, String>> {
+        Box::pin(async move {
+            let path = load_context.path().display().to_string();
+            let mut bytes = vec![];
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(|e| format!("{path}: {e}"))?;
+            let contents = String::from_utf8(bytes).map_err(|e| format!("{path}: {e}"))?;
+            let (level_data, version) =
+                parse_level(&contents).map_err(|e| format!("{path}: {e}"))?;
+            if version != LEVEL_VERSION {
+                warn!("{path} is level version {version}, run `zenith upgrade` to update it");
+            }
+            Ok(level_data)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod level_data_tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_levels_migrate() {
+        let old = "(start:(1,2),goal:(3,4),rocks:[(kind:Normal,points:[(0,0),(0,5),(5,0)],z:1)],fields:[(points:[(0,0),(0,5),(5,0)],dir:(0.0,-1.0),strength:Normal,drag:Normal)],segments:[],replenishes:[(pos:(7,7))])";
+        let (level_data, version) = parse_level(old).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(level_data.version, LEVEL_VERSION);
+        assert_eq!(level_data.goal, IVec2::new(3, 4));
+        assert_eq!(level_data.rocks[0].z, 1);
+        assert_eq!(level_data.fields[0].dir, Vec2::NEG_Y);
+        assert_eq!(level_data.fields[0].drag, FieldDrag::Off);
+        assert_eq!(level_data.replenishes[0].pos, IVec2::new(7, 7));
+        // And the migrated level should round trip as the current version
+        let text = ron::to_string(&level_data).unwrap();
+        assert_eq!(parse_level(&text).unwrap(), (level_data, LEVEL_VERSION));
+    }
+
+    #[test]
+    fn rocks_gain_bounciness() {
+        let v1 = "(version:1,start:(1,2),goal:(3,4),rocks:[(kind:MagLev,points:[(0,0),(0,5),(5,0)],z:0)],fields:[],segments:[],replenishes:[])";
+        let (level_data, version) = parse_level(v1).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(level_data.rocks[0].kind, RockKind::MagLev);
+        assert_eq!(level_data.rocks[0].bounciness, None);
+    }
+
+    #[test]
+    fn normal_drag_used_to_be_off() {
+        let v8 = "(version:8,start:(1,2),goal:(3,4),rocks:[],fields:[(points:[(0,0),(0,5),(5,0)],dir:(0.0,-1.0),strength:Normal,drag:Normal),(points:[(0,0),(0,5),(5,0)],dir:(0.0,-1.0),strength:Normal,drag:Thick)],segments:[],replenishes:[])";
+        let (level_data, version) = parse_level(v8).unwrap();
+        assert_eq!(version, 8);
+        assert_eq!(level_data.fields[0].drag, FieldDrag::Off);
+        assert_eq!(level_data.fields[1].drag, FieldDrag::Thick);
+        // Levels saved since mean what they say
+        let text = ron::to_string(&LevelData {
+            fields: level_data
+                .fields
+                .iter()
+                .map(|field| ExportedField {
+                    drag: FieldDrag::Normal,
+                    ..field.clone()
+                })
+                .collect(),
+            ..level_data
+        })
+        .unwrap();
+        let (level_data, _) = parse_level(&text).unwrap();
+        assert_eq!(level_data.fields[0].drag, FieldDrag::Normal);
+    }
+
+    #[test]
+    fn future_levels_are_rejected() {
+        let future = format!("(version:{},start:(0,0))", LEVEL_VERSION + 1);
+        assert!(parse_level(&future).is_err());
+    }
+
+    #[test]
+    fn scenes_match_exported_levels() {
+        let contents = std::fs::read_to_string("assets/levels/editing/basic_1.scn.ron").unwrap();
+        let from_scene = level_from_scene(&contents).unwrap();
+        let exported = LevelData::read_from_disk("basic_1").unwrap();
+        assert_eq!(from_scene.start, exported.start);
+        assert_eq!(from_scene.goal, exported.goal);
+        assert_eq!(from_scene.rocks, exported.rocks);
+        assert_eq!(from_scene.fields.len(), exported.fields.len());
+    }
+}
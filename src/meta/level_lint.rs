@@ -0,0 +1,457 @@
+//! Static checks for level data, i.e. the stuff that doesn't crash anything right away but
+//! makes physics/rendering do weird things later. Run over every level with `zenith lint-levels`.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    environment::portal::PORTAL_RADIUS,
+    math::{MathLine, MathTriangle},
+    ship::Ship,
+    uid::UId,
+};
+
+use super::old_level_data::LevelData;
+
+/// A single problem with a level
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    /// Which part of the level, i.e. `rocks[2]` or `goal`
+    pub entity: String,
+    pub message: String,
+}
+impl LintIssue {
+    fn new(entity: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            entity: entity.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Twice the signed area of a polygon. Negative for clockwise points
+pub fn signed_area2(points: &[IVec2]) -> i64 {
+    let mut result = 0;
+    for ix in 0..points.len() {
+        let p1 = points[ix].as_i64vec2();
+        let p2 = points[(ix + 1) % points.len()].as_i64vec2();
+        result += p1.x * p2.y - p2.x * p1.y;
+    }
+    result
+}
+
+/// Do segments a1-a2 and b1-b2 cross (or touch)?
+fn segments_intersect(a1: IVec2, a2: IVec2, b1: IVec2, b2: IVec2) -> bool {
+    let orient = |p: IVec2, q: IVec2, r: IVec2| {
+        let (p, q, r) = (p.as_i64vec2(), q.as_i64vec2(), r.as_i64vec2());
+        ((q - p).perp_dot(r - p)).signum()
+    };
+    let on_segment = |p: IVec2, q: IVec2, r: IVec2| {
+        r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+    };
+    let (o1, o2) = (orient(a1, a2, b1), orient(a1, a2, b2));
+    let (o3, o4) = (orient(b1, b2, a1), orient(b1, b2, a2));
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+    (o1 == 0 && on_segment(a1, a2, b1))
+        || (o2 == 0 && on_segment(a1, a2, b2))
+        || (o3 == 0 && on_segment(b1, b2, a1))
+        || (o4 == 0 && on_segment(b1, b2, a2))
+}
+
+/// Checks that a polygon (rock or field) has enough points, is clockwise and doesn't cross itself
+fn lint_polygon(entity: &str, points: &[IVec2], issues: &mut Vec<LintIssue>) {
+    if points.len() < 3 {
+        issues.push(LintIssue::new(entity, "has fewer than 3 points"));
+        return;
+    }
+    let area2 = signed_area2(points);
+    if area2 == 0 {
+        issues.push(LintIssue::new(entity, "has no area"));
+    } else if area2 > 0 {
+        issues.push(LintIssue::new(entity, "points aren't in clockwise order"));
+    }
+    let num = points.len();
+    for ix in 0..num {
+        // Skip neighboring edges, they always share a point
+        for jx in (ix + 2)..num {
+            if ix == 0 && jx == num - 1 {
+                continue;
+            }
+            let (a1, a2) = (points[ix], points[(ix + 1) % num]);
+            let (b1, b2) = (points[jx], points[(jx + 1) % num]);
+            if segments_intersect(a1, a2, b1, b2) {
+                issues.push(LintIssue::new(
+                    entity,
+                    format!("intersects itself (edges {ix} and {jx})"),
+                ));
+                return;
+            }
+        }
+    }
+}
+
+fn triangulate(points: &[IVec2]) -> Vec<MathTriangle> {
+    let points: Vec<Vec2> = points.iter().map(|p| p.as_vec2()).collect();
+    MathTriangle::triangulate(&points)
+}
+
+fn triangles_contain(triangles: &[MathTriangle], point: Vec2) -> bool {
+    triangles
+        .iter()
+        .any(|triangle| triangle.signed_distance_from_point(&point) < 0.0)
+}
+
+fn polygon_contains(points: &[IVec2], point: Vec2) -> bool {
+    triangles_contain(&triangulate(points), point)
+}
+
+fn distance_to_edges(lines: &[MathLine], point: Vec2) -> f32 {
+    lines
+        .iter()
+        .map(|line| line.closest_point_on_segment(&point).distance(point))
+        .fold(f32::MAX, f32::min)
+}
+
+/// Same bounds as the `LivePolyBundle` that gets spawned with the level
+fn live_bounds(level_data: &LevelData) -> (Vec2, Vec2) {
+    let mut min = Vec2::MAX;
+    let mut max = Vec2::MIN;
+    let mut all_points = vec![level_data.start, level_data.goal];
+    for rock in level_data.rocks.iter() {
+        all_points.extend(rock.points.iter());
+    }
+    for field in level_data.fields.iter() {
+        all_points.extend(field.points.iter());
+    }
+    for replenish in level_data.replenishes.iter() {
+        all_points.push(replenish.pos);
+    }
+    for portal in level_data.portals.iter() {
+        all_points.extend([portal.a, portal.b]);
+    }
+    for switch in level_data.switches.iter() {
+        all_points.push(switch.pos);
+    }
+    for stardust in level_data.stardust.iter() {
+        all_points.push(stardust.pos);
+    }
+    for point in all_points {
+        min = min.min(point.as_vec2());
+        max = max.max(point.as_vec2());
+    }
+    let margin = Vec2::ONE * Ship::radius() * 5.0;
+    (min - margin, max + margin)
+}
+
+/// Flood fills the live bounds from the start, through every spot the ship fits (and through
+/// portals). Ignores physics entirely, this only catches goals that are walled off. Rocks that
+/// can be switched off might not be there, so they never wall anything off
+fn goal_is_reachable(level_data: &LevelData) -> bool {
+    let cell_size = Ship::radius();
+    let (min, max) = live_bounds(level_data);
+    let to_cell = |pos: Vec2| ((pos - min) / cell_size).floor().as_ivec2();
+    let to_pos = |cell: IVec2| min + (cell.as_vec2() + 0.5) * cell_size;
+    let num_cells = to_cell(max) + IVec2::ONE;
+    // Every cell checks every rock, so only work out each rock's shape once
+    let walls: Vec<(Vec<MathTriangle>, Vec<MathLine>)> = level_data
+        .rocks
+        .iter()
+        .filter(|rock| rock.target.is_none())
+        .map(|rock| {
+            let points: Vec<Vec2> = rock.points.iter().map(|p| p.as_vec2()).collect();
+            (triangulate(&rock.points), MathLine::from_points(&points))
+        })
+        .collect();
+    let is_open = |cell: IVec2| {
+        let pos = to_pos(cell);
+        walls.iter().all(|(triangles, lines)| {
+            !triangles_contain(triangles, pos) && distance_to_edges(lines, pos) >= Ship::radius()
+        })
+    };
+    let warps: Vec<(Vec2, Vec2)> = level_data
+        .portals
+        .iter()
+        .flat_map(|portal| {
+            let (a, b) = (portal.a.as_vec2(), portal.b.as_vec2());
+            [(a, b), (b, a)]
+        })
+        .collect();
+    let goal_cell = to_cell(level_data.goal.as_vec2());
+    let start_cell = to_cell(level_data.start.as_vec2());
+    let mut seen = HashSet::new();
+    seen.insert(start_cell);
+    let mut frontier = vec![start_cell];
+    while let Some(cell) = frontier.pop() {
+        if (cell - goal_cell).abs().max_element() <= 1 {
+            return true;
+        }
+        let warped = warps
+            .iter()
+            .filter(|(from, _)| to_pos(cell).distance(*from) <= PORTAL_RADIUS as f32)
+            .map(|(_, to)| to_cell(*to));
+        let neighbors = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .map(|dir| cell + dir)
+            .into_iter()
+            .chain(warped);
+        for next in neighbors {
+            let in_bounds = next.cmpge(IVec2::ZERO).all() && next.cmplt(num_cells).all();
+            if in_bounds && !seen.contains(&next) && is_open(next) {
+                seen.insert(next);
+                frontier.push(next);
+            }
+        }
+    }
+    false
+}
+
+/// Everything wrong with a level, in the order it appears in the file
+pub fn lint_level(level_data: &LevelData) -> Vec<LintIssue> {
+    let mut issues = vec![];
+    for (ix, rock) in level_data.rocks.iter().enumerate() {
+        lint_polygon(&format!("rocks[{ix}]"), &rock.points, &mut issues);
+    }
+    for (ix, field) in level_data.fields.iter().enumerate() {
+        let entity = format!("fields[{ix}]");
+        lint_polygon(&entity, &field.points, &mut issues);
+        let strength = field.strength.to_f32();
+        if !strength.is_finite() || strength < 0.0 {
+            issues.push(LintIssue::new(entity.clone(), "has a nonsense strength"));
+        }
+        if !(0.0..1.0).contains(&field.drag.to_f32()) {
+            issues.push(LintIssue::new(entity.clone(), "has a nonsense drag"));
+        }
+        match field.well.as_ref() {
+            Some(well) => {
+                if !well.radius.is_finite() || well.radius <= 0.0 {
+                    issues.push(LintIssue::new(entity, "has a well with no reach"));
+                }
+            }
+            None => {
+                if !field.dir.is_finite() || field.dir.length() < 0.001 {
+                    issues.push(LintIssue::new(entity, "has no direction"));
+                }
+            }
+        }
+    }
+    for (entity, pos) in [("start", level_data.start), ("goal", level_data.goal)] {
+        for (ix, rock) in level_data.rocks.iter().enumerate() {
+            if polygon_contains(&rock.points, pos.as_vec2()) {
+                issues.push(LintIssue::new(entity, format!("is inside rocks[{ix}]")));
+            }
+        }
+    }
+    for (ix, segment) in level_data.segments.iter().enumerate() {
+        let on_edge = level_data.rocks.iter().any(|rock| {
+            let points: Vec<Vec2> = rock.points.iter().map(|p| p.as_vec2()).collect();
+            MathLine::from_points(&points).iter().any(|line| {
+                [segment.left_parent, segment.right_parent]
+                    .iter()
+                    .all(|end| {
+                        let end = end.as_vec2();
+                        line.closest_point_on_segment(&end).distance(end) <= 1.0
+                    })
+            })
+        });
+        if !on_edge {
+            issues.push(LintIssue::new(
+                format!("segments[{ix}]"),
+                "doesn't lie on a rock edge",
+            ));
+        }
+    }
+    for (ix, portal) in level_data.portals.iter().enumerate() {
+        let entity = format!("portals[{ix}]");
+        if portal.a.as_vec2().distance(portal.b.as_vec2()) <= 2.0 * PORTAL_RADIUS as f32 {
+            issues.push(LintIssue::new(entity.clone(), "has ends that overlap"));
+        }
+        for end in [portal.a, portal.b] {
+            for (rx, rock) in level_data.rocks.iter().enumerate() {
+                if polygon_contains(&rock.points, end.as_vec2()) {
+                    issues.push(LintIssue::new(
+                        entity.clone(),
+                        format!("has an end inside rocks[{rx}]"),
+                    ));
+                }
+            }
+        }
+    }
+    let target_uids: Vec<UId> = level_data
+        .rocks
+        .iter()
+        .filter_map(|rock| rock.target.as_ref())
+        .chain(
+            level_data
+                .fields
+                .iter()
+                .filter_map(|field| field.target.as_ref()),
+        )
+        .map(|target| target.uid)
+        .collect();
+    for (ix, switch) in level_data.switches.iter().enumerate() {
+        let entity = format!("switches[{ix}]");
+        if switch.targets.is_empty() {
+            issues.push(LintIssue::new(entity.clone(), "doesn't target anything"));
+        }
+        for uid in switch.targets.iter() {
+            if !target_uids.contains(uid) {
+                issues.push(LintIssue::new(
+                    entity.clone(),
+                    format!("targets {uid}, which no rock or field has"),
+                ));
+            }
+        }
+        if switch
+            .reset
+            .is_some_and(|reset| !reset.is_finite() || reset <= 0.0)
+        {
+            issues.push(LintIssue::new(entity, "has a nonsense reset"));
+        }
+    }
+    for (ix, stardust) in level_data.stardust.iter().enumerate() {
+        let entity = format!("stardust[{ix}]");
+        if level_data.stardust[..ix]
+            .iter()
+            .any(|other| other.uid == stardust.uid)
+        {
+            issues.push(LintIssue::new(
+                entity.clone(),
+                format!("has uid {}, which is already taken", stardust.uid),
+            ));
+        }
+        for (rx, rock) in level_data.rocks.iter().enumerate() {
+            if polygon_contains(&rock.points, stardust.pos.as_vec2()) {
+                issues.push(LintIssue::new(
+                    entity.clone(),
+                    format!("is inside rocks[{rx}]"),
+                ));
+            }
+        }
+    }
+    if !goal_is_reachable(level_data) {
+        issues.push(LintIssue::new(
+            "goal",
+            "can't be reached from the start without passing through rock",
+        ));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod level_lint_tests {
+    use super::*;
+    use crate::{
+        environment::segment::SegmentKind,
+        meta::old_level_data::{
+            ExportedField, ExportedPortal, ExportedRock, ExportedSegment, ExportedStardust,
+            ExportedSwitch,
+        },
+    };
+
+    fn entities(issues: &[LintIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.entity.as_str()).collect()
+    }
+
+    #[test]
+    fn shipped_levels_are_clean() {
+        for id in LevelData::ids_on_disk() {
+            let level_data = LevelData::read_from_disk(&id).unwrap();
+            assert_eq!(lint_level(&level_data), vec![], "{id} has lint");
+        }
+    }
+
+    #[test]
+    fn catches_broken_levels() {
+        let level_data = LevelData {
+            start: IVec2::new(-40, 5),
+            goal: IVec2::new(0, -5),
+            rocks: vec![
+                // Floor, fine
+                ExportedRock::from_points(&[(-60, 0), (60, 0), (60, -10), (-60, -10)]),
+                // Counter-clockwise
+                ExportedRock::from_points(&[(100, 0), (110, 0), (110, 10), (100, 10)]),
+                // Bowtie
+                ExportedRock::from_points(&[(200, 0), (210, 10), (210, 0), (200, 10)]),
+            ],
+            fields: vec![ExportedField {
+                points: vec![IVec2::new(-60, 0), IVec2::new(-60, 20), IVec2::new(0, 20)],
+                dir: Vec2::ZERO,
+                ..default()
+            }],
+            segments: vec![ExportedSegment {
+                kind: SegmentKind::Spring,
+                left_parent: IVec2::new(-20, 5),
+                right_parent: IVec2::new(20, 5),
+            }],
+            switches: vec![ExportedSwitch {
+                pos: IVec2::new(-40, 20),
+                targets: vec![7],
+                reset: None,
+            }],
+            stardust: vec![
+                ExportedStardust {
+                    uid: 1,
+                    pos: IVec2::new(30, 30),
+                },
+                // Same uid
+                ExportedStardust {
+                    uid: 1,
+                    pos: IVec2::new(-30, 30),
+                },
+            ],
+            ..default()
+        };
+        let issues = lint_level(&level_data);
+        let mut found = entities(&issues);
+        found.sort();
+        found.dedup();
+        assert_eq!(
+            found,
+            vec![
+                "fields[0]",
+                "goal",
+                "rocks[1]",
+                "rocks[2]",
+                "segments[0]",
+                "stardust[1]",
+                "switches[0]"
+            ]
+        );
+    }
+
+    #[test]
+    fn walled_off_goals_are_unreachable() {
+        let mut level_data = LevelData::floor_level();
+        assert!(goal_is_reachable(&level_data));
+        // A box around the goal
+        level_data.rocks.push(ExportedRock::from_points(&[
+            (20, 0),
+            (20, 30),
+            (60, 30),
+            (60, 0),
+            (55, 0),
+            (55, 25),
+            (25, 25),
+            (25, 0),
+        ]));
+        assert!(lint_polygon_is_clean(&level_data.rocks[1].points));
+        assert!(!goal_is_reachable(&level_data));
+        // Or the box is a door that might be open
+        level_data.rocks[1].target = Some(default());
+        assert!(goal_is_reachable(&level_data));
+        level_data.rocks[1].target = None;
+        // Unless there's a way in
+        level_data.portals.push(ExportedPortal {
+            a: IVec2::new(-20, 20),
+            b: IVec2::new(40, 15),
+            ..default()
+        });
+        assert!(goal_is_reachable(&level_data));
+    }
+
+    fn lint_polygon_is_clean(points: &[IVec2]) -> bool {
+        let mut issues = vec![];
+        lint_polygon("rock", points, &mut issues);
+        issues.is_empty()
+    }
+}
@@ -1,30 +1,21 @@
-use bevy::{
-    ecs::system::{SystemId, SystemState},
-    prelude::*,
-};
+use bevy::{ecs::system::SystemId, prelude::*};
 
 use crate::{
     camera::CameraMarker,
     environment::{
-        field::{FieldDrag, FieldStrength},
+        field::{spawn_field, FieldDrag, FieldStrength, WellFalloff},
         goal::{GoalBundle, GoalSize},
         live_poly::LivePolyBundle,
-        rock::RockKind,
+        portal::spawn_portal,
+        rock::{spawn_rock, RockKind},
         segment::SegmentKind,
         start::{StartBundle, StartSize},
     },
-    math::ToIVec2,
-    old_editor::{
-        // field::EStandaloneField,
-        // planet::EPlanet,
-        // point::EPoint,
-        // replenish::EReplenish,
-        // segment::SegmentParents,
-        // start_goal::{EGoal, EStart},
-    },
+    math::Spleen,
+    meta::level_data::{parse_level, LEVEL_VERSION},
     physics::dyno::IntMoveable,
     ship::ShipBundle,
-    uid::{UId, UIdMarker, UIdTranslator},
+    uid::{UId, UIdMarker},
 };
 
 /// For rehydrating exported level data (saves, intermediate editor output) into spawnable things
@@ -40,6 +31,61 @@ pub struct ExportedRock {
     pub kind: RockKind,
     pub points: Vec<IVec2>,
     pub z: i32,
+    /// Overrides the bounciness that comes with `kind`
+    #[serde(default)]
+    pub bounciness: Option<f32>,
+    /// Rocks with a path move along it forever
+    #[serde(default)]
+    pub path: Option<ExportedRockPath>,
+    /// Rocks with a rotation spin forever
+    #[serde(default)]
+    pub rotation: Option<ExportedRockRotation>,
+    /// Rocks with a target can be switched on and off (i.e. doors)
+    #[serde(default)]
+    pub target: Option<ExportedSwitchTarget>,
+}
+
+/// What a moving rock does once it reaches the end of its path
+#[derive(
+    serde::Serialize, serde::Deserialize, Reflect, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+pub enum RockPathMode {
+    /// Heads straight back to where it started
+    #[default]
+    Loop,
+    /// Retraces its steps
+    PingPong,
+}
+impl RockPathMode {
+    pub const ALL: [Self; 2] = [Self::Loop, Self::PingPong];
+}
+impl std::fmt::Display for RockPathMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match *self {
+            Self::Loop => "loop",
+            Self::PingPong => "ping_pong",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Reflect, Debug, PartialEq, Clone, Default)]
+pub struct ExportedRockPath {
+    /// Offsets from where the rock was placed, visited in order after its starting position
+    pub waypoints: Vec<IVec2>,
+    /// Average speed, in units per tick
+    pub speed: f32,
+    /// Easing between waypoints. `None` moves at a constant speed
+    pub spleen: Option<Spleen>,
+    pub mode: RockPathMode,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Reflect, Debug, PartialEq, Clone, Default)]
+pub struct ExportedRockRotation {
+    /// What the rock spins around, as an offset from the middle of its points
+    pub pivot: IVec2,
+    /// In radians per tick. Positive spins counter-clockwise
+    pub speed: f32,
 }
 
 #[derive(
@@ -50,6 +96,23 @@ pub struct ExportedField {
     pub dir: Vec2,
     pub strength: FieldStrength,
     pub drag: FieldDrag,
+    /// Fields with a well pull toward (or push away from) a point instead of along `dir`
+    #[serde(default)]
+    pub well: Option<ExportedWell>,
+    /// Fields with a target can be switched on and off
+    #[serde(default)]
+    pub target: Option<ExportedSwitchTarget>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Reflect, Debug, PartialEq, Clone, Default)]
+pub struct ExportedWell {
+    /// What the well pulls toward, as an offset from the middle of the field's points
+    pub center: IVec2,
+    pub falloff: WellFalloff,
+    /// The well does nothing further than this from its center
+    pub radius: f32,
+    /// Pushes away from the center instead
+    pub repel: bool,
 }
 
 #[derive(
@@ -68,8 +131,55 @@ pub struct ExportedReplenish {
     pub pos: IVec2,
 }
 
+/// Two linked ends. Going into either one comes out of the other, just as fast
+#[derive(
+    serde::Serialize, serde::Deserialize, bevy::reflect::TypePath, Debug, PartialEq, Clone, Default,
+)]
+pub struct ExportedPortal {
+    pub a: IVec2,
+    pub b: IVec2,
+    /// Which way each end faces, in radians. Only matters for portals that `rotate`
+    pub a_angle: f32,
+    pub b_angle: f32,
+    /// Turns the ship so that going in one end head-on comes out the other end head-on
+    pub rotate: bool,
+}
+
+/// Lets switches find the rock or field it's on
+#[derive(serde::Serialize, serde::Deserialize, Reflect, Debug, PartialEq, Clone, Default)]
+pub struct ExportedSwitchTarget {
+    /// What switches refer to this by. Only has to be unique within the level
+    pub uid: UId,
+    /// Starts switched off, e.g. a door that starts open or a field that has to be turned on
+    pub starts_off: bool,
+}
+
+/// Touching it flips everything it targets on or off
+#[derive(
+    serde::Serialize, serde::Deserialize, bevy::reflect::TypePath, Debug, PartialEq, Clone, Default,
+)]
+pub struct ExportedSwitch {
+    pub pos: IVec2,
+    /// The `uid`s of the targets it flips
+    pub targets: Vec<UId>,
+    /// Seconds until the targets flip back. `None` leaves them until the switch is touched again
+    pub reset: Option<f32>,
+}
+
+/// Optional pickup. Which ones have been collected is saved per level, so `uid` has to stay the
+/// same for as long as the level exists
+#[derive(
+    serde::Serialize, serde::Deserialize, bevy::reflect::TypePath, Debug, PartialEq, Clone, Default,
+)]
+pub struct ExportedStardust {
+    pub uid: UId,
+    pub pos: IVec2,
+}
+
 /// All the data that exists about a level.
 /// Just the data that needs to be used to load/play the level
+/// NOTE: Changing this (or anything in it) changes the file format, see `level_data` for how to
+/// keep existing levels loading
 #[derive(
     serde::Serialize,
     serde::Deserialize,
@@ -79,158 +189,125 @@ pub struct ExportedReplenish {
     PartialEq,
     Clone,
     Resource,
-    Default,
 )]
 pub struct LevelData {
-    start: IVec2,
-    goal: IVec2,
-    rocks: Vec<ExportedRock>,
-    fields: Vec<ExportedField>,
-    segments: Vec<ExportedSegment>,
-    replenishes: Vec<ExportedReplenish>,
+    /// Missing from files saved before levels were versioned (version 0)
+    #[serde(default)]
+    pub version: u32,
+    pub start: IVec2,
+    pub goal: IVec2,
+    pub rocks: Vec<ExportedRock>,
+    pub fields: Vec<ExportedField>,
+    pub segments: Vec<ExportedSegment>,
+    pub replenishes: Vec<ExportedReplenish>,
+    #[serde(default)]
+    pub portals: Vec<ExportedPortal>,
+    #[serde(default)]
+    pub switches: Vec<ExportedSwitch>,
+    #[serde(default)]
+    pub stardust: Vec<ExportedStardust>,
+}
+
+impl Default for LevelData {
+    fn default() -> Self {
+        Self {
+            version: LEVEL_VERSION,
+            start: default(),
+            goal: default(),
+            rocks: default(),
+            fields: default(),
+            segments: default(),
+            replenishes: default(),
+            portals: default(),
+            switches: default(),
+            stardust: default(),
+        }
+    }
+}
+
+impl LevelData {
+    /// Reads `assets/levels/<id>.level.ron` straight off disk, bypassing the asset server.
+    /// Useful for tools/tests that run without the full app. Old versions are migrated
+    pub fn read_from_disk(id: &str) -> Result<Self, String> {
+        let path = format!("assets/levels/{id}.level.ron");
+        let contents = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+        parse_level(&contents)
+            .map(|(level_data, _)| level_data)
+            .map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn write_to_disk(&self, path: &std::path::Path) -> Result<(), String> {
+        let contents = ron::to_string(self).map_err(|e| format!("{}: {e}", path.display()))?;
+        std::fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Ids of every level in `assets/levels`, sorted
+    pub fn ids_on_disk() -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir("assets/levels") else {
+            return vec![];
+        };
+        let mut ids: Vec<String> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".level.ron").map(|id| id.to_string())
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+}
+
+#[cfg(test)]
+impl ExportedRock {
+    /// A plain rock with the given points, for tests
+    pub fn from_points(points: &[(i32, i32)]) -> Self {
+        Self {
+            points: points.iter().map(|(x, y)| IVec2::new(*x, *y)).collect(),
+            ..default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl LevelData {
+    /// A floor from (-60, 0) to (60, -10) with gravity above it, starting at (-40, 5) and ending
+    /// at (40, 5). For tests that want something small to play around in
+    pub fn floor_level() -> Self {
+        Self {
+            start: IVec2::new(-40, 5),
+            goal: IVec2::new(40, 5),
+            rocks: vec![ExportedRock::from_points(&[
+                (-60, 0),
+                (60, 0),
+                (60, -10),
+                (-60, -10),
+            ])],
+            fields: vec![ExportedField {
+                points: vec![
+                    IVec2::new(-60, 0),
+                    IVec2::new(-60, 120),
+                    IVec2::new(60, 120),
+                    IVec2::new(60, 0),
+                ],
+                dir: Vec2::NEG_Y,
+                ..default()
+            }],
+            ..default()
+        }
+    }
 }
 
 /// A struct that contains SystemIds for systems relating to exporting/loading levels
 #[derive(Resource, Clone)]
 pub struct LevelDataOneshots {
-    pub crystallize_level_data_id: SystemId<(), LevelData>,
     pub old_spawn_level: SystemId<(u64, LevelData, IVec2)>,
     pub spawn_level: SystemId<(Entity, LevelData)>,
 }
 
-pub(super) fn crystallize_level_data(
-    world: &mut World,
-    params: &mut SystemState<(
-        // Query<&GlobalTransform, With<EPoint>>,
-        // Query<&EPlanet>,
-        // Query<&GlobalTransform, With<EStart>>,
-        // Query<&GlobalTransform, With<EGoal>>,
-        // Query<(&SegmentParents, &SegmentKind)>,
-        // Query<&GlobalTransform, With<EReplenish>>,
-        // Query<&EStandaloneField>,
-        // Res<UIdTranslator>,
-    )>,
-) -> LevelData {
-    panic!("ahhhh fuck refactoring");
-    // let (points_q, planets_q, estart, egoal, segments_q, replenish_q, esf_q, ut) =
-    //     params.get(world);
-    // let start = match estart.get_single() {
-    //     Ok(gt) => IVec2::new(
-    //         gt.translation().x.round() as i32,
-    //         gt.translation().y.round() as i32,
-    //     ),
-    //     _ => IVec2::ZERO,
-    // };
-    // let goal = match egoal.get_single() {
-    //     Ok(gt) => IVec2::new(
-    //         gt.translation().x.round() as i32,
-    //         gt.translation().y.round() as i32,
-    //     ),
-    //     _ => IVec2::ZERO,
-    // };
-    // let rocks = planets_q
-    //     .iter()
-    //     .map(|planet| ExportedRock {
-    //         kind: planet.rock_kind,
-    //         points: planet
-    //             .rock_points
-    //             .iter()
-    //             .map(|uid| {
-    //                 let eid = ut.get_entity(*uid).unwrap();
-    //                 let pos = points_q.get(eid).unwrap();
-    //                 let pos = pos.translation().truncate();
-    //                 IVec2::new(pos.x.round() as i32, pos.y.round() as i32)
-    //             })
-    //             .collect(),
-    //         z: 0,
-    //     })
-    //     .collect();
-    // let mut fields = vec![];
-    // for planet in planets_q.iter() {
-    //     for field in planet.fields.iter() {
-    //         let points = field
-    //             .field_points
-    //             .iter()
-    //             .map(|uid| {
-    //                 let eid = ut.get_entity(*uid).unwrap();
-    //                 let pos = points_q.get(eid).unwrap();
-    //                 let pos = pos.translation().truncate();
-    //                 IVec2::new(pos.x.round() as i32, pos.y.round() as i32)
-    //             })
-    //             .collect();
-    //         fields.push(ExportedField {
-    //             points,
-    //             dir: field.dir,
-    //             strength: FieldStrength::Normal,
-    //             drag: FieldDrag::Normal,
-    //         })
-    //     }
-    // }
-    // let mut segments = vec![];
-    // for (parents, kind) in segments_q.iter() {
-    //     let Some(eid_left) = ut.get_entity(parents.left_uid) else {
-    //         continue;
-    //     };
-    //     let Some(eid_right) = ut.get_entity(parents.right_uid) else {
-    //         continue;
-    //     };
-    //     let Ok(left_point) = points_q.get(eid_left) else {
-    //         continue;
-    //     };
-    //     let Ok(right_point) = points_q.get(eid_right) else {
-    //         continue;
-    //     };
-    //     let left_parent = left_point.translation().truncate();
-    //     let left_parent = IVec2::new(left_parent.x.round() as i32, left_parent.y.round() as i32);
-    //     let right_parent = right_point.translation().truncate();
-    //     let right_parent = IVec2::new(right_parent.x.round() as i32, right_parent.y.round() as i32);
-
-    //     segments.push(ExportedSegment {
-    //         kind: *kind,
-    //         left_parent,
-    //         right_parent,
-    //     });
-    // }
-    // let mut replenishes = vec![];
-    // for replenish in replenish_q.iter() {
-    //     replenishes.push(ExportedReplenish {
-    //         pos: IVec2::new(
-    //             replenish.translation().x.round() as i32,
-    //             replenish.translation().y.round() as i32,
-    //         ),
-    //     });
-    // }
-    // for esf in esf_q.iter() {
-    //     let points = esf
-    //         .field_points
-    //         .clone()
-    //         .into_iter()
-    //         .map(|eid| {
-    //             let tran = points_q.get(eid).unwrap().translation().truncate();
-    //             IVec2::new(tran.x.round() as i32, tran.y.round() as i32)
-    //         })
-    //         .collect();
-    //     fields.push(ExportedField {
-    //         points,
-    //         dir: esf.dir,
-    //         strength: FieldStrength::Normal,
-    //         drag: FieldDrag::Normal,
-    //     })
-    // }
-    // LevelData {
-    //     start,
-    //     goal,
-    //     rocks,
-    //     fields,
-    //     segments,
-    //     replenishes,
-    // }
-}
-
 #[derive(Component)]
 pub struct LevelRoot;
 
-pub(super) fn old_spawn_level(
+pub(crate) fn old_spawn_level(
     In((uid, level_data, home)): In<(UId, LevelData, IVec2)>,
     mut commands: Commands,
     mut camera_q: Query<&mut IntMoveable, With<CameraMarker>>,
@@ -254,13 +331,13 @@ pub(super) fn old_spawn_level(
                 for point in rock.points.iter() {
                     all_points.push(point.as_vec2());
                 }
-                parent.spawn(rock.rehydrate());
+                spawn_rock(parent, rock);
             }
             for field in level_data.fields {
                 for point in field.points.iter() {
                     all_points.push(point.as_vec2());
                 }
-                parent.spawn(field.rehydrate());
+                spawn_field(parent, field);
             }
             for segment in level_data.segments {
                 parent.spawn(segment.rehydrate());
@@ -269,6 +346,19 @@ pub(super) fn old_spawn_level(
                 all_points.push(repl.pos.as_vec2());
                 parent.spawn(repl.rehydrate());
             }
+            for portal in level_data.portals {
+                all_points.push(portal.a.as_vec2());
+                all_points.push(portal.b.as_vec2());
+                spawn_portal(parent, portal);
+            }
+            for switch in level_data.switches {
+                all_points.push(switch.pos.as_vec2());
+                parent.spawn(switch.rehydrate());
+            }
+            for stardust in level_data.stardust {
+                all_points.push(stardust.pos.as_vec2());
+                parent.spawn(stardust.rehydrate());
+            }
             let live_poly = LivePolyBundle::new(all_points);
             parent.spawn(live_poly);
         });
@@ -300,13 +390,13 @@ pub(super) fn spawn_level(
                 for point in rock.points.iter() {
                     all_points.push(point.as_vec2());
                 }
-                parent.spawn(rock.rehydrate());
+                spawn_rock(parent, rock);
             }
             for field in level_data.fields {
                 for point in field.points.iter() {
                     all_points.push(point.as_vec2());
                 }
-                parent.spawn(field.rehydrate());
+                spawn_field(parent, field);
             }
             for segment in level_data.segments {
                 parent.spawn(segment.rehydrate());
@@ -315,6 +405,19 @@ pub(super) fn spawn_level(
                 all_points.push(repl.pos.as_vec2());
                 parent.spawn(repl.rehydrate());
             }
+            for portal in level_data.portals {
+                all_points.push(portal.a.as_vec2());
+                all_points.push(portal.b.as_vec2());
+                spawn_portal(parent, portal);
+            }
+            for switch in level_data.switches {
+                all_points.push(switch.pos.as_vec2());
+                parent.spawn(switch.rehydrate());
+            }
+            for stardust in level_data.stardust {
+                all_points.push(stardust.pos.as_vec2());
+                parent.spawn(stardust.rehydrate());
+            }
             let live_poly = LivePolyBundle::new(all_points);
             parent.spawn(live_poly);
         });
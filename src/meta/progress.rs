@@ -1,94 +1,147 @@
 use std::{fs::File, io::Write};
 
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashMap},
+};
 
-#[derive(Debug, Clone)]
+use crate::{add_hot_resource, ship::DeathCause, uid::UId};
+
+use super::old_level_data::LevelData;
+
+/// A level, as listed in `catalog.galaxies.ron`
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct LevelMetaData {
     pub id: String,
     pub title: String,
     pub description: String,
+    /// Number of shots a good run should take. Levels without one award full stars for completing
+    #[serde(default)]
+    pub par: Option<u32>,
 }
+impl LevelMetaData {
+    pub const MAX_STARS: u32 = 3;
 
+    /// Full stars for making par, one less for being within two shots of it, otherwise 1 for
+    /// finishing
+    pub fn stars_for(&self, num_shots: u32) -> u32 {
+        match self.par {
+            None => Self::MAX_STARS,
+            Some(par) if num_shots <= par => Self::MAX_STARS,
+            Some(par) if num_shots <= par + 2 => Self::MAX_STARS - 1,
+            Some(_) => 1,
+        }
+    }
+}
+
+/// Id of a galaxy. Everything else about it (order, title, levels...) lives in `Galaxies`
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Default, Hash, Reflect, serde::Serialize, serde::Deserialize,
+    Debug, Clone, PartialEq, Eq, Default, Hash, Reflect, serde::Serialize, serde::Deserialize,
 )]
-pub enum GalaxyKind {
-    #[default]
-    Basic,
-    Springy,
+#[serde(transparent)]
+pub struct GalaxyKind(pub String);
+impl GalaxyKind {
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
 }
 impl std::fmt::Display for GalaxyKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug, Clone)]
+/// A galaxy, as listed in `catalog.galaxies.ron`
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct GalaxyMetaData {
+    pub id: GalaxyKind,
     pub title: String,
     pub description: String,
+    /// Name of the sprite in `sprites/menu/galaxy` shown on the galaxy screen.
+    /// The light uses the same name with an `L` on the end
+    pub sprite: String,
+    /// How many stars (across all galaxies) the player needs before this galaxy unlocks
+    #[serde(default)]
+    pub required_stars: u32,
     pub levels: Vec<LevelMetaData>,
 }
 
-// TODO: Yeah so this ends up just being stored on code segment.
-// probably not terrible but probably should shove into a file at some point
-impl GalaxyKind {
-    pub fn all() -> Vec<Self> {
-        vec![Self::Basic, Self::Springy]
+/// Every galaxy in the game, in the order they're played.
+/// Hot reloaded from `catalog.galaxies.ron`, so adding a level or galaxy doesn't need a recompile
+#[derive(
+    serde::Deserialize,
+    bevy::asset::Asset,
+    bevy::reflect::TypePath,
+    Debug,
+    PartialEq,
+    Clone,
+    Resource,
+    Default,
+)]
+pub struct Galaxies {
+    pub galaxies: Vec<GalaxyMetaData>,
+}
+impl Galaxies {
+    pub const PATH: &'static str = "catalog.galaxies.ron";
+
+    /// Reads the catalog straight off disk, for when there's no asset server (tests, cli tools)
+    pub fn read_from_disk() -> Result<Self, String> {
+        let path = format!("assets/{}", Self::PATH);
+        let contents = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+        ron::from_str(&contents).map_err(|e| format!("{path}: {e}"))
     }
 
-    pub fn rank(&self) -> u32 {
-        match self {
-            Self::Basic => 0,
-            Self::Springy => 1,
-        }
+    pub fn all(&self) -> Vec<GalaxyKind> {
+        self.galaxies
+            .iter()
+            .map(|galaxy| galaxy.id.clone())
+            .collect()
     }
 
-    pub fn next(&self) -> Option<Self> {
-        match self {
-            Self::Basic => Some(Self::Springy),
-            Self::Springy => None,
-        }
+    pub fn get(&self, kind: &GalaxyKind) -> Option<&GalaxyMetaData> {
+        self.galaxies.iter().find(|galaxy| &galaxy.id == kind)
     }
 
-    pub fn prev(&self) -> Option<Self> {
-        match self {
-            Self::Basic => None,
-            Self::Springy => Some(Self::Basic),
-        }
+    pub fn rank(&self, kind: &GalaxyKind) -> u32 {
+        self.galaxies
+            .iter()
+            .position(|galaxy| &galaxy.id == kind)
+            .unwrap_or_default() as u32
     }
 
-    pub fn to_levels(&self) -> Vec<LevelMetaData> {
-        match self {
-            Self::Basic => vec![
-                LevelMetaData {
-                    id: "basic_1".to_string(),
-                    title: "First level".to_string(),
-                    description: "Just testing 1".to_string(),
-                },
-                LevelMetaData {
-                    id: "basic_2".to_string(),
-                    title: "Second level".to_string(),
-                    description: "Just testing 2".to_string(),
-                },
-            ],
-            Self::Springy => vec![
-                LevelMetaData {
-                    id: "springy_1".to_string(),
-                    title: "Spring intro".to_string(),
-                    description: "Introducing the player to springs".to_string(),
-                },
-                LevelMetaData {
-                    id: "springy_2".to_string(),
-                    title: "Springs go brrr".to_string(),
-                    description: "Yeah, so, springs".to_string(),
-                },
-            ],
+    pub fn next(&self, kind: &GalaxyKind) -> Option<GalaxyKind> {
+        let ix = self.galaxies.iter().position(|galaxy| &galaxy.id == kind)?;
+        self.galaxies.get(ix + 1).map(|galaxy| galaxy.id.clone())
+    }
+
+    pub fn prev(&self, kind: &GalaxyKind) -> Option<GalaxyKind> {
+        let ix = self.galaxies.iter().position(|galaxy| &galaxy.id == kind)?;
+        let prev_ix = ix.checked_sub(1)?;
+        self.galaxies.get(prev_ix).map(|galaxy| galaxy.id.clone())
+    }
+
+    /// Levels in this galaxy, in order. Empty for galaxies that aren't in the catalog
+    pub fn to_levels(&self, kind: &GalaxyKind) -> &[LevelMetaData] {
+        match self.get(kind) {
+            Some(galaxy) => &galaxy.levels,
+            None => &[],
         }
     }
 
-    pub fn get_next_level_id(&self, level_id: &str) -> Option<String> {
-        let mut it = self.to_levels().into_iter().map(|meta| meta.id);
+    pub fn required_stars(&self, kind: &GalaxyKind) -> u32 {
+        self.get(kind).map_or(0, |galaxy| galaxy.required_stars)
+    }
+
+    pub fn get_level_meta(&self, kind: &GalaxyKind, level_id: &str) -> Option<LevelMetaData> {
+        self.to_levels(kind)
+            .iter()
+            .find(|meta| meta.id == level_id)
+            .cloned()
+    }
+
+    pub fn get_next_level_id(&self, kind: &GalaxyKind, level_id: &str) -> Option<String> {
+        let mut it = self.to_levels(kind).iter().map(|meta| meta.id.clone());
         while let Some(val) = it.next() {
             if val.as_str() == level_id {
                 break;
@@ -96,16 +149,36 @@ impl GalaxyKind {
         }
         it.next()
     }
+}
 
-    pub fn to_meta_data(&self) -> GalaxyMetaData {
-        let (title, description) = match self {
-            Self::Basic => ("Basic", "A basic, test galaxy"),
-            Self::Springy => ("Spring", "For learning about springs"),
-        };
-        GalaxyMetaData {
-            title: title.to_string(),
-            description: description.to_string(),
-            levels: self.to_levels(),
+add_hot_resource!(Galaxies, Galaxies::PATH, setup_galaxies, update_galaxies);
+
+/// Every level in the catalog, loaded ahead of time so menus can count what's in them
+#[derive(Resource, Debug, Default)]
+pub struct CatalogLevels {
+    handles: HashMap<String, Handle<LevelData>>,
+}
+impl CatalogLevels {
+    /// How much stardust is in the level file. 0 until it's loaded
+    pub fn num_stardust(&self, levels: &Assets<LevelData>, id: &str) -> u32 {
+        self.handles
+            .get(id)
+            .and_then(|handle| levels.get(handle))
+            .map_or(0, |level_data| level_data.stardust.len() as u32)
+    }
+}
+
+pub(super) fn load_catalog_levels(
+    galaxies: Res<Galaxies>,
+    asset_server: Res<AssetServer>,
+    mut catalog_levels: ResMut<CatalogLevels>,
+) {
+    for galaxy in galaxies.galaxies.iter() {
+        for level in galaxy.levels.iter() {
+            if !catalog_levels.handles.contains_key(&level.id) {
+                let handle = asset_server.load(format!("levels/{}.level.ron", level.id));
+                catalog_levels.handles.insert(level.id.clone(), handle);
+            }
         }
     }
 }
@@ -130,24 +203,38 @@ pub struct GalaxyProgress {
     pub next_level: Option<String>,
 }
 impl GalaxyProgress {
+    /// Progress for a galaxy the player hasn't touched yet
+    fn fresh(galaxies: &Galaxies, kind: &GalaxyKind) -> Self {
+        Self {
+            completed: false,
+            next_level: galaxies.to_levels(kind).first().map(|meta| meta.id.clone()),
+        }
+    }
+
     /// In the players current epoch, returns (num_complete, num_in_galaxy)
-    pub fn portion_completed(&self, kind: GalaxyKind) -> (u32, u32) {
+    pub fn portion_completed(&self, galaxies: &Galaxies, kind: &GalaxyKind) -> (u32, u32) {
+        let levels = galaxies.to_levels(kind);
         let Some(next_level) = self.next_level.as_ref() else {
-            let as_u32 = kind.to_levels().len() as u32;
+            let as_u32 = levels.len() as u32;
             return (as_u32, as_u32);
         };
         let mut rank = 0;
-        for (ix, level) in kind.to_levels().into_iter().enumerate() {
+        for (ix, level) in levels.iter().enumerate() {
             if &level.id == next_level {
                 rank = ix as u32;
                 break;
             }
         }
-        (rank, kind.to_levels().len() as u32)
+        (rank, levels.len() as u32)
     }
 
     /// Attempts to mark a level as complete. The level must match `next_level` and exist as expected
-    fn try_mark_completed(&mut self, kind: GalaxyKind, level_id: String) -> Result<(), String> {
+    fn try_mark_completed(
+        &mut self,
+        galaxies: &Galaxies,
+        kind: &GalaxyKind,
+        level_id: String,
+    ) -> Result<(), String> {
         let Some(old_next_level) = self.next_level.clone() else {
             let warning = format!(
                 "Tried to mark completed with no next_level in galaxy {}",
@@ -164,14 +251,84 @@ impl GalaxyProgress {
             warn!("{warning}");
             return Err(warning);
         }
-        let next = kind.get_next_level_id(&level_id);
+        let next = galaxies.get_next_level_id(kind, &level_id);
         self.completed = self.completed || next.is_none();
         self.next_level = next;
         Ok(())
     }
 }
 
-/// Maps galaxy (enum as string) to (completed, id_of_level_on)
+/// How many times the player has died in a level, by cause
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default, Reflect,
+)]
+pub struct DeathCounts {
+    pub kill_rock: u32,
+    #[serde(default)]
+    pub spike: u32,
+    pub out_of_bounds: u32,
+    pub respawn: u32,
+}
+impl DeathCounts {
+    pub fn add(&mut self, cause: DeathCause) {
+        match cause {
+            DeathCause::KillRock => self.kill_rock += 1,
+            DeathCause::Spike => self.spike += 1,
+            DeathCause::OutOfBounds => self.out_of_bounds += 1,
+            DeathCause::Respawn => self.respawn += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.kill_rock + self.spike + self.out_of_bounds + self.respawn
+    }
+}
+
+/// Lifetime stats for a single level
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone, Default, Reflect)]
+pub struct LevelStats {
+    /// Fewest shots the level has been completed in
+    pub best_shots: Option<u32>,
+    /// How many times the player has started the level
+    pub num_attempts: u32,
+    pub deaths: DeathCounts,
+    /// Fastest completion, in seconds
+    pub fastest_time: Option<f32>,
+    /// Most stars earned in a single completion
+    #[serde(default)]
+    pub stars: u32,
+    /// Uids of every stardust ever collected, from any attempt
+    #[serde(default)]
+    pub stardust: Vec<UId>,
+}
+impl LevelStats {
+    /// Stars as text, i.e. "**-" for two out of three
+    pub fn stars_str(&self) -> String {
+        let filled = self.stars.min(LevelMetaData::MAX_STARS);
+        let empty = LevelMetaData::MAX_STARS - filled;
+        format!(
+            "{}{}",
+            "*".repeat(filled as usize),
+            "-".repeat(empty as usize)
+        )
+    }
+
+    /// One line summary for menus
+    pub fn summary(&self) -> String {
+        let best = match (self.best_shots, self.fastest_time) {
+            (Some(shots), Some(time)) => format!("best {shots} shots, {time:.1}s"),
+            _ => "not completed".to_string(),
+        };
+        format!(
+            "[{}] {best}, {} tries, {} deaths",
+            self.stars_str(),
+            self.num_attempts,
+            self.deaths.total()
+        )
+    }
+}
+
+/// Maps galaxy id to (completed, id_of_level_on)
 #[derive(
     Component,
     serde::Serialize,
@@ -187,63 +344,265 @@ impl GalaxyProgress {
 pub struct GameProgress {
     needs_save: bool,
     galaxy_map: HashMap<GalaxyKind, GalaxyProgress>,
+    /// Maps level id to stats. Defaulted so that saves from before stats existed still load
+    #[serde(default)]
+    level_stats: HashMap<String, LevelStats>,
 }
 impl GameProgress {
-    /// Gets the (completed, active_level) status for a given galaxy
-    pub fn get_galaxy_progress(&self, kind: GalaxyKind) -> GalaxyProgress {
-        self.galaxy_map.get(&kind).unwrap().clone()
+    /// Gets the (completed, active_level) status for a given galaxy.
+    /// Galaxies missing from the save (i.e. newly added to the catalog) start fresh
+    pub fn get_galaxy_progress(&self, galaxies: &Galaxies, kind: &GalaxyKind) -> GalaxyProgress {
+        match self.galaxy_map.get(kind) {
+            Some(progress) => progress.clone(),
+            None => GalaxyProgress::fresh(galaxies, kind),
+        }
+    }
+
+    fn galaxy_progress_mut(
+        &mut self,
+        galaxies: &Galaxies,
+        kind: &GalaxyKind,
+    ) -> &mut GalaxyProgress {
+        self.galaxy_map
+            .entry(kind.clone())
+            .or_insert_with(|| GalaxyProgress::fresh(galaxies, kind))
     }
 
-    /// Returns the earliest incomplete galaxy, i.e., the galaxy the player is actively playing
-    pub fn first_incomplete_galaxy(&self) -> GalaxyKind {
-        for kind in GalaxyKind::all() {
-            let progress = self.get_galaxy_progress(kind);
-            if !progress.completed {
+    /// Returns the earliest incomplete galaxy that has enough stars to be unlocked,
+    /// i.e., the galaxy the player is actively playing
+    pub fn first_incomplete_galaxy(&self, galaxies: &Galaxies) -> GalaxyKind {
+        for kind in galaxies.all() {
+            let progress = self.get_galaxy_progress(galaxies, &kind);
+            if !progress.completed && self.has_stars_for(galaxies, &kind) {
                 return kind;
             }
         }
-        GalaxyKind::Basic
+        galaxies.all().into_iter().next().unwrap_or_default()
     }
 
     /// Returns true if this galaxy is playable, a.k.a if it should be selectable from the
-    /// galaxy overworld. Translates to: complete OR first incomplete
-    pub fn is_playable(&self, kind: GalaxyKind) -> bool {
-        let progress = self.get_galaxy_progress(kind);
-        progress.completed || kind == self.first_incomplete_galaxy()
+    /// galaxy overworld. Translates to: enough stars AND (complete OR first incomplete)
+    pub fn is_playable(&self, galaxies: &Galaxies, kind: &GalaxyKind) -> bool {
+        let progress = self.get_galaxy_progress(galaxies, kind);
+        self.has_stars_for(galaxies, kind)
+            && (progress.completed || *kind == self.first_incomplete_galaxy(galaxies))
+    }
+
+    /// Stars earned across every level
+    pub fn total_stars(&self) -> u32 {
+        self.level_stats.values().map(|stats| stats.stars).sum()
+    }
+
+    /// Returns (stars_earned, stars_possible) for the levels in this galaxy
+    pub fn galaxy_stars(&self, galaxies: &Galaxies, kind: &GalaxyKind) -> (u32, u32) {
+        let levels = galaxies.to_levels(kind);
+        let earned = levels
+            .iter()
+            .map(|level| self.get_level_stats(&level.id).stars)
+            .sum();
+        (earned, levels.len() as u32 * LevelMetaData::MAX_STARS)
+    }
+
+    /// How much of this galaxy is done, as a whole percentage. Counts the levels completed in the
+    /// current epoch and every stardust ever collected. `num_stardust` is how much stardust is in
+    /// the level with the given id
+    pub fn galaxy_completion(
+        &self,
+        galaxies: &Galaxies,
+        kind: &GalaxyKind,
+        num_stardust: impl Fn(&str) -> u32,
+    ) -> u32 {
+        let levels = galaxies.to_levels(kind);
+        let (levels_done, num_levels) = self
+            .get_galaxy_progress(galaxies, kind)
+            .portion_completed(galaxies, kind);
+        let stardust_done: u32 = levels
+            .iter()
+            .map(|level| {
+                let collected = self.get_level_stats(&level.id).stardust.len() as u32;
+                collected.min(num_stardust(&level.id))
+            })
+            .sum();
+        let num_stardust: u32 = levels.iter().map(|level| num_stardust(&level.id)).sum();
+        let total = num_levels + num_stardust;
+        if total == 0 {
+            return 0;
+        }
+        (levels_done + stardust_done) * 100 / total
+    }
+
+    pub fn has_stars_for(&self, galaxies: &Galaxies, kind: &GalaxyKind) -> bool {
+        self.total_stars() >= galaxies.required_stars(kind)
     }
 
     /// Restarts the progress in this galaxy but does not clear `completed`.
-    pub fn try_restart_galaxy(&mut self, kind: GalaxyKind) -> Result<(), String> {
-        let galaxy_progress = self.galaxy_map.get_mut(&kind).unwrap();
+    pub fn try_restart_galaxy(
+        &mut self,
+        galaxies: &Galaxies,
+        kind: &GalaxyKind,
+    ) -> Result<(), String> {
+        let Some(first_level) = galaxies.to_levels(kind).first().map(|meta| meta.id.clone()) else {
+            let warning = format!("Tried to replay {kind} galaxy but it has no levels");
+            warn!(warning);
+            return Err(warning);
+        };
+        let galaxy_progress = self.galaxy_progress_mut(galaxies, kind);
         if galaxy_progress.next_level.is_some() {
             let warning = format!(
-                "Tried to replay {kind:?} galaxy but next_level has non-None state {}",
+                "Tried to replay {kind} galaxy but next_level has non-None state {}",
                 galaxy_progress.next_level.as_ref().unwrap()
             );
             warn!(warning);
             return Err(warning);
         }
         if !galaxy_progress.completed {
-            let warning = format!("Tried to replay {kind:?} galaxy but it's not complete",);
+            let warning = format!("Tried to replay {kind} galaxy but it's not complete",);
             warn!(warning);
             return Err(warning);
         }
-        galaxy_progress.next_level = Some(kind.to_levels()[0].id.clone());
+        galaxy_progress.next_level = Some(first_level);
         Ok(())
     }
 
+    pub fn get_level_stats(&self, level_id: &str) -> LevelStats {
+        self.level_stats.get(level_id).cloned().unwrap_or_default()
+    }
+
+    fn level_stats_mut(&mut self, level_id: &str) -> &mut LevelStats {
+        self.needs_save = true;
+        self.level_stats.entry(level_id.to_string()).or_default()
+    }
+
+    pub fn record_attempt(&mut self, level_id: &str) {
+        self.level_stats_mut(level_id).num_attempts += 1;
+    }
+
+    pub fn record_death(&mut self, level_id: &str, cause: DeathCause) {
+        self.level_stats_mut(level_id).deaths.add(cause);
+    }
+
+    pub fn record_stardust(&mut self, level_id: &str, uid: UId) {
+        if self.get_level_stats(level_id).stardust.contains(&uid) {
+            return;
+        }
+        self.level_stats_mut(level_id).stardust.push(uid);
+    }
+
+    /// Keeps the best shot count, fastest time and most stars
+    /// (independently, they can come from different runs)
+    pub fn record_completion(&mut self, level_id: &str, num_shots: u32, time: f32, stars: u32) {
+        let stats = self.level_stats_mut(level_id);
+        stats.stars = stats.stars.max(stars);
+        stats.best_shots = Some(
+            stats
+                .best_shots
+                .map_or(num_shots, |best| best.min(num_shots)),
+        );
+        stats.fastest_time = Some(stats.fastest_time.map_or(time, |fastest| fastest.min(time)));
+    }
+
     /// Assumes the player just completed kind::level_id, advances to the next level
     /// Returns an error if the underlying `GalaxyProgress` rejects this kind/level combo.
-    pub fn try_mark_completed(&mut self, kind: GalaxyKind, level_id: String) -> Result<(), String> {
-        self.galaxy_map
-            .get_mut(&kind)
-            .unwrap()
-            .try_mark_completed(kind, level_id.clone())?;
+    pub fn try_mark_completed(
+        &mut self,
+        galaxies: &Galaxies,
+        kind: &GalaxyKind,
+        level_id: String,
+    ) -> Result<(), String> {
+        self.galaxy_progress_mut(galaxies, kind)
+            .try_mark_completed(galaxies, kind, level_id)?;
         self.needs_save = true;
         Ok(())
     }
 }
 
+/// Galaxies used to be an enum, so saves from back then have bare identifiers for galaxy ids
+/// (`Basic`) where they're now strings (`"Basic"`)
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct LegacyGalaxyKind(GalaxyKind);
+impl<'de> serde::Deserialize<'de> for LegacyGalaxyKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdentVisitor;
+        impl<'de> serde::de::Visitor<'de> for IdentVisitor {
+            type Value = LegacyGalaxyKind;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a galaxy identifier")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, id: &str) -> Result<Self::Value, E> {
+                Ok(LegacyGalaxyKind(GalaxyKind::new(id)))
+            }
+        }
+        deserializer.deserialize_identifier(IdentVisitor)
+    }
+}
+
+/// A save from before galaxy ids were strings
+#[derive(serde::Deserialize)]
+#[serde(rename = "GameProgress")]
+struct LegacyGameProgress {
+    needs_save: bool,
+    galaxy_map: HashMap<LegacyGalaxyKind, GalaxyProgress>,
+    #[serde(default)]
+    level_stats: HashMap<String, LevelStats>,
+}
+impl From<LegacyGameProgress> for GameProgress {
+    fn from(old: LegacyGameProgress) -> Self {
+        Self {
+            needs_save: old.needs_save,
+            galaxy_map: old
+                .galaxy_map
+                .into_iter()
+                .map(|(kind, progress)| (kind.0, progress))
+                .collect(),
+            level_stats: old.level_stats,
+        }
+    }
+}
+
+impl GameProgress {
+    /// Parses a save, including ones from before galaxy ids were strings
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        ron::from_str::<Self>(contents).or_else(|e| {
+            ron::from_str::<LegacyGameProgress>(contents)
+                .map(Self::from)
+                .map_err(|_| e.to_string())
+        })
+    }
+}
+
+/// Loads `.progress.ron` saves of any age
+#[derive(Default)]
+pub struct GameProgressLoader;
+impl AssetLoader for GameProgressLoader {
+    type Asset = GameProgress;
+    type Settings = ();
+    type Error = String;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<GameProgress, String>> {
+        Box::pin(async move {
+            let path = load_context.path().display().to_string();
+            let mut bytes = vec![];
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(|e| format!("{path}: {e}"))?;
+            let contents = String::from_utf8(bytes).map_err(|e| format!("{path}: {e}"))?;
+            GameProgress::parse(&contents).map_err(|e| format!("{path}: {e}"))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["progress.ron"]
+    }
+}
+
 /// Marks a GameProgress as being active.
 /// Basically, the game will load both save files, but only one of them will be given
 /// this component. So whenever it needs to be queried, just do Query<&GameProgress, With<ActiveSaveFile>>
@@ -324,3 +683,135 @@ pub(super) fn save_game_progress(mut game_progresses: Query<(&mut GameProgress,
         }
     }
 }
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn saves_without_stats_still_load() {
+        let old = "(needs_save:false,galaxy_map:{Basic:(completed:true,next_level:None),Springy:(completed:false,next_level:Some(\"springy_1\"))})";
+        let progress = GameProgress::parse(old).unwrap();
+        let galaxies = Galaxies::read_from_disk().unwrap();
+        assert!(
+            progress
+                .get_galaxy_progress(&galaxies, &GalaxyKind::new("Basic"))
+                .completed
+        );
+        assert_eq!(progress.get_level_stats("basic_1"), LevelStats::default());
+        // And saves written now load back the same
+        let resaved = ron::to_string(&progress).unwrap();
+        assert_eq!(GameProgress::parse(&resaved), Ok(progress));
+        for letter in ["A", "B"] {
+            let path = format!("assets/saves/{letter}.progress.ron");
+            let contents = std::fs::read_to_string(&path).unwrap();
+            assert!(
+                GameProgress::parse(&contents).is_ok(),
+                "{path} doesn't load"
+            );
+        }
+    }
+
+    #[test]
+    fn stats_keep_the_best_of_each() {
+        let mut progress = GameProgress::default();
+        progress.record_attempt("basic_1");
+        progress.record_completion("basic_1", 3, 4.0, 1);
+        progress.record_attempt("basic_1");
+        progress.record_death("basic_1", DeathCause::KillRock);
+        progress.record_death("basic_1", DeathCause::Respawn);
+        progress.record_death("basic_1", DeathCause::Spike);
+        progress.record_completion("basic_1", 2, 9.5, 3);
+        let stats = progress.get_level_stats("basic_1");
+        assert_eq!(stats.best_shots, Some(2));
+        assert_eq!(stats.fastest_time, Some(4.0));
+        assert_eq!(stats.num_attempts, 2);
+        assert_eq!(stats.deaths.total(), 3);
+        assert_eq!(stats.deaths.kill_rock, 1);
+        assert_eq!(stats.deaths.spike, 1);
+        assert_eq!(stats.stars, 3);
+        assert_eq!(stats.stars_str(), "***");
+        assert!(progress.needs_save);
+    }
+
+    #[test]
+    fn stars_compare_against_par() {
+        let galaxies = Galaxies::read_from_disk().unwrap();
+        let mut meta = galaxies.galaxies[0].levels[0].clone();
+        meta.par = Some(2);
+        assert_eq!(meta.stars_for(1), 3);
+        assert_eq!(meta.stars_for(2), 3);
+        assert_eq!(meta.stars_for(4), 2);
+        assert_eq!(meta.stars_for(5), 1);
+        meta.par = None;
+        assert_eq!(meta.stars_for(50), LevelMetaData::MAX_STARS);
+    }
+
+    #[test]
+    fn catalog_matches_levels_on_disk() {
+        let galaxies = Galaxies::read_from_disk().unwrap();
+        let on_disk = LevelData::ids_on_disk();
+        for (ix, kind) in galaxies.all().iter().enumerate() {
+            assert_eq!(galaxies.rank(kind), ix as u32);
+            assert_eq!(
+                galaxies.prev(kind),
+                ix.checked_sub(1).map(|i| galaxies.all()[i].clone())
+            );
+            assert!(!galaxies.to_levels(kind).is_empty(), "{kind} has no levels");
+            for meta in galaxies.to_levels(kind) {
+                assert!(on_disk.contains(&meta.id), "{} isn't on disk", meta.id);
+            }
+        }
+        assert_eq!(galaxies.next(galaxies.all().last().unwrap()), None);
+    }
+
+    #[test]
+    fn stardust_counts_towards_completion() {
+        let mut galaxies = Galaxies::read_from_disk().unwrap();
+        galaxies.galaxies.truncate(1);
+        let kind = galaxies.all()[0].clone();
+        galaxies.galaxies[0].levels.truncate(1);
+        let level_id = galaxies.galaxies[0].levels[0].id.clone();
+        // Every level has 3 stardust in it
+        let completion =
+            |progress: &GameProgress| progress.galaxy_completion(&galaxies, &kind, |_| 3);
+        let mut progress = GameProgress::default();
+        assert_eq!(completion(&progress), 0);
+        progress.record_stardust(&level_id, 5);
+        progress.record_stardust(&level_id, 5);
+        assert_eq!(progress.get_level_stats(&level_id).stardust, vec![5]);
+        assert_eq!(completion(&progress), 25);
+        progress
+            .try_mark_completed(&galaxies, &kind, level_id.clone())
+            .unwrap();
+        assert_eq!(completion(&progress), 50);
+        progress.record_stardust(&level_id, 6);
+        progress.record_stardust(&level_id, 7);
+        assert_eq!(completion(&progress), 100);
+    }
+
+    #[test]
+    fn new_galaxies_start_fresh() {
+        let galaxies = Galaxies::read_from_disk().unwrap();
+        let kind = galaxies.all()[0].clone();
+        let mut progress = GameProgress::default();
+        let fresh = progress.get_galaxy_progress(&galaxies, &kind);
+        assert!(!fresh.completed);
+        assert_eq!(
+            fresh.next_level,
+            Some(galaxies.to_levels(&kind)[0].id.clone())
+        );
+        assert_eq!(progress.first_incomplete_galaxy(&galaxies), kind);
+        let first_level = fresh.next_level.unwrap();
+        assert!(progress
+            .try_mark_completed(&galaxies, &kind, first_level)
+            .is_ok());
+        assert_eq!(
+            progress
+                .get_galaxy_progress(&galaxies, &kind)
+                .portion_completed(&galaxies, &kind)
+                .0,
+            1
+        );
+    }
+}
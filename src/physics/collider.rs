@@ -15,6 +15,15 @@ pub struct ColliderBoundary {
     pub triangles: Vec<MathTriangle>,
     pub center: Vec2,
     pub bound_squared: f32,
+    /// How far the boundary has been moved from `points`
+    pub offset: Vec2,
+    /// How far the boundary has been rotated around `pivot`, before being moved
+    pub angle: f32,
+    pub pivot: Vec2,
+    /// `lines`, `triangles` and `center` as they were built from `points`
+    base_lines: Vec<MathLine>,
+    base_triangles: Vec<MathTriangle>,
+    base_center: Vec2,
 }
 impl ColliderBoundary {
     pub fn from_points(boundary_points: Vec<IVec2>) -> Self {
@@ -34,13 +43,42 @@ impl ColliderBoundary {
         let triangles = MathTriangle::triangulate(&fpoints);
         ColliderBoundary {
             points: boundary_points,
+            base_lines: lines.clone(),
+            base_triangles: triangles.clone(),
+            base_center: center,
             lines,
             triangles,
             center,
             bound_squared: max_dist_sq,
+            offset: Vec2::ZERO,
+            angle: 0.0,
+            pivot: Vec2::ZERO,
         }
     }
 
+    /// Puts the boundary where `points` would be after rotating `angle` around `pivot`, and then
+    /// moving by `offset`. Always starts from the original points, so errors don't pile up
+    pub fn place(&mut self, offset: Vec2, pivot: Vec2, angle: f32) {
+        let rot = Vec2::from_angle(angle);
+        let place = |point: Vec2| pivot + rot.rotate(point - pivot) + offset;
+        let place_line = |line: &MathLine| MathLine {
+            p1: place(line.p1),
+            p2: place(line.p2),
+        };
+        for (line, base) in self.lines.iter_mut().zip(self.base_lines.iter()) {
+            *line = place_line(base);
+        }
+        for (triangle, base) in self.triangles.iter_mut().zip(self.base_triangles.iter()) {
+            for (line, base_line) in triangle.lines.iter_mut().zip(base.lines.iter()) {
+                *line = place_line(base_line);
+            }
+        }
+        self.center = place(self.base_center);
+        self.offset = offset;
+        self.pivot = pivot;
+        self.angle = angle;
+    }
+
     pub fn closest_point(&self, point: Vec2) -> Vec2 {
         let mut min_dist_sq = f32::MAX;
         let mut min_point = Vec2 {
@@ -75,6 +113,18 @@ impl ColliderBoundary {
 pub struct ColliderStatic {
     pub bounciness: f32,
     pub friction: f32,
+    /// How far the collider moved this tick. Only moving rocks have one
+    pub vel: Vec2,
+    /// How far the collider turned around `pivot` this tick, in radians. Only rotating rocks
+    /// have one
+    pub spin: f32,
+    pub pivot: Vec2,
+}
+impl ColliderStatic {
+    /// How fast the surface of the collider is moving at `point`
+    pub fn vel_at(&self, point: Vec2) -> Vec2 {
+        self.vel + self.spin * (point - self.pivot).perp()
+    }
 }
 
 #[derive(Component, Debug, Default)]
@@ -137,6 +187,9 @@ pub(super) fn materialize_collider_stubs(
                         _static: ColliderStatic {
                             bounciness: stub.bounciness,
                             friction: stub.friction,
+                            vel: Vec2::ZERO,
+                            spin: 0.0,
+                            pivot: Vec2::ZERO,
                         },
                         boundary: ColliderBoundary::from_points(stub.points.clone()),
                     },
@@ -213,25 +266,28 @@ pub(super) fn resolve_static_collisions(
 
     let diff = fpos - min_point;
     let normal = diff.normalize_or_zero();
-    if normal.dot(dyno.vel) >= 0.0 {
+    // Bounce in the collider's frame, so moving (and spinning) rocks carry the ship along
+    let surface_vel = stat.vel_at(min_point);
+    let rel_vel = dyno.vel - surface_vel;
+    if normal.dot(rel_vel) >= 0.0 {
         return false;
     }
 
-    let pure_parr = -1.0 * dyno.vel.dot(normal) * normal + dyno.vel;
+    let pure_parr = -rel_vel.dot(normal) * normal + rel_vel;
     if dyno.statics.len() < MAX_COLLISIONS_PER_FRAME {
         dyno.statics.insert(
             min_parent_id,
             StaticCollision {
                 pos: fpos,
-                norm_vel: normal * normal.dot(dyno.vel),
+                norm_vel: normal * normal.dot(rel_vel),
                 par_vel: pure_parr,
             },
         );
     }
 
     let new_vel =
-        pure_parr * (1.0 - stat.friction) - 1.0 * dyno.vel.dot(normal) * normal * stat.bounciness;
-    dyno.vel = new_vel;
+        pure_parr * (1.0 - stat.friction) - 1.0 * rel_vel.dot(normal) * normal * stat.bounciness;
+    dyno.vel = new_vel + surface_vel;
     let diff = fpos - min_point;
     let normal = diff.normalize_or_zero();
     fpos += normal * (dyno.radius - fpos.distance(min_point));
@@ -266,19 +322,52 @@ pub(super) fn resolve_trigger_collisions(
 /// to the parent and have it auto-update the child
 pub(super) fn trickle_active(
     parents: Query<&TrickleColliderActive>,
-    colliders: Query<(Entity, &Parent), With<ColliderBoundary>>,
+    colliders: Query<(Entity, &Parent, Has<ColliderActive>), With<ColliderBoundary>>,
     mut commands: Commands,
 ) {
-    for (cid, parent) in colliders.iter() {
+    for (cid, parent, is_active) in colliders.iter() {
         let Ok(trickle) = parents.get(parent.get()) else {
             continue;
         };
-        if trickle.0 {
+        if trickle.0 && !is_active {
             commands.entity(cid).insert(ColliderActive);
-        } else {
+        } else if !trickle.0 && is_active {
             commands.entity(cid).remove::<ColliderActive>();
         }
     }
 }
 
 pub fn update_triggers() {}
+
+#[cfg(test)]
+mod collider_tests {
+    use super::*;
+
+    #[test]
+    fn rotated_boundaries_spin_around_their_pivot() {
+        let mut boundary = ColliderBoundary::from_points(vec![
+            IVec2::new(0, 0),
+            IVec2::new(0, 10),
+            IVec2::new(10, 10),
+            IVec2::new(10, 0),
+        ]);
+        let quarter = std::f32::consts::FRAC_PI_2;
+        for _ in 0..2 {
+            boundary.place(Vec2::new(5.0, 0.0), Vec2::ZERO, quarter);
+            assert!(boundary.center.distance(Vec2::new(0.0, 5.0)) < 0.001);
+        }
+        // The corner at (10, 0) ends up at (0, 10), then gets moved
+        let corner = boundary.closest_point(Vec2::new(5.0, 20.0));
+        assert!(corner.distance(Vec2::new(5.0, 10.0)) < 0.001);
+        let stat = ColliderStatic {
+            bounciness: 0.0,
+            friction: 0.0,
+            vel: Vec2::new(1.0, 0.0),
+            spin: 0.1,
+            pivot: Vec2::ZERO,
+        };
+        assert!(stat
+            .vel_at(Vec2::new(10.0, 0.0))
+            .abs_diff_eq(Vec2::new(1.0, 1.0), 0.001));
+    }
+}
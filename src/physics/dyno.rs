@@ -109,17 +109,19 @@ impl IntDyno {
     }
 }
 
+/// Moves a dyno along its velocity, resolving collisions on the way. Doesn't animate or play
+/// anything so that predictions (like the shot preview) can use it too. Returns the springs the
+/// dyno bounced off of, along with where it was when it happened.
 pub(super) fn move_int_dyno_helper(
     dyno: &mut IntDyno,
     statics: &Query<(Entity, &ColliderBoundary, &ColliderStatic, &Parent), With<ColliderActive>>,
     triggers: &Query<(&ColliderBoundary, &ColliderTrigger, &Parent), With<ColliderActive>>,
-    segments: &mut Query<(&Segment, &mut AnimationManager)>,
-    bullet_time: &Res<BulletTime>,
-    commands: &mut Commands,
-) {
+    segments: &Query<&Segment>,
+    bullet_time: &BulletTime,
+) -> Vec<(Entity, Vec3)> {
     let mut amt_travlled = 0.0;
     let to_travel = dyno.vel.length();
-    let mut spawned_sprung_this_frame = false;
+    let mut springs = vec![];
 
     while amt_travlled < to_travel && amt_travlled < dyno.vel.length() {
         let this_step = if dyno.vel.length() - amt_travlled >= 1.0 {
@@ -138,7 +140,7 @@ pub(super) fn move_int_dyno_helper(
         let mut killing_ids = HashSet::new();
         let mut sprung = false;
         for (eid, _emult) in dyno.triggers.iter() {
-            let Ok((segment, mut anim)) = segments.get_mut(*eid) else {
+            let Ok(segment) = segments.get(*eid) else {
                 continue;
             };
             match segment.kind {
@@ -151,16 +153,7 @@ pub(super) fn move_int_dyno_helper(
                         let new_vel = pure_parr + norm * 3.0 * bullet_time.factor();
                         dyno.vel = new_vel;
                         sprung = true;
-                        anim.reset_key("bounce");
-                        if !spawned_sprung_this_frame {
-                            commands.spawn((
-                                SoundEffect::spatial("sound_effects/spring.ogg", 0.5, false),
-                                SpatialBundle::from_transform(Transform::from_translation(
-                                    dyno.fpos,
-                                )),
-                            ));
-                            spawned_sprung_this_frame = true;
-                        }
+                        springs.push((*eid, dyno.fpos));
                     }
                 }
                 SegmentKind::Spike => {
@@ -173,6 +166,10 @@ pub(super) fn move_int_dyno_helper(
         dyno.triggers.retain(|id, _| !killing_ids.contains(id));
         amt_travlled += this_step;
     }
+    if amt_travlled == 0.0 {
+        // A ship sitting still can still get hit by a moving rock
+        resolve_static_collisions(dyno, statics);
+    }
     resolve_trigger_collisions(dyno, triggers);
 
     dyno.ipos = IVec3::new(
@@ -180,13 +177,15 @@ pub(super) fn move_int_dyno_helper(
         dyno.fpos.y.round() as i32,
         dyno.fpos.z.round() as i32,
     );
+    springs
 }
 
 pub(super) fn move_int_dynos(
     mut dynos: Query<(&mut IntDyno, &mut Transform)>,
     statics: Query<(Entity, &ColliderBoundary, &ColliderStatic, &Parent), With<ColliderActive>>,
     triggers: Query<(&ColliderBoundary, &ColliderTrigger, &Parent), With<ColliderActive>>,
-    mut segments: Query<(&Segment, &mut AnimationManager)>,
+    segments: Query<&Segment>,
+    mut segment_anims: Query<&mut AnimationManager, With<Segment>>,
     bullet_time: Res<BulletTime>,
     mut commands: Commands,
 ) {
@@ -194,14 +193,19 @@ pub(super) fn move_int_dynos(
         // Clear the old collisions/triggers
         dyno.statics = HashMap::new();
         dyno.triggers = HashMap::new();
-        move_int_dyno_helper(
-            dyno.as_mut(),
-            &statics,
-            &triggers,
-            &mut segments,
-            &bullet_time,
-            &mut commands,
-        );
+        let springs =
+            move_int_dyno_helper(dyno.as_mut(), &statics, &triggers, &segments, &bullet_time);
+        for (eid, _) in springs.iter() {
+            if let Ok(mut anim) = segment_anims.get_mut(*eid) {
+                anim.reset_key("bounce");
+            }
+        }
+        if let Some((_, pos)) = springs.first() {
+            commands.spawn((
+                SoundEffect::spatial("sound_effects/spring.ogg", 0.5, false),
+                SpatialBundle::from_transform(Transform::from_translation(*pos)),
+            ));
+        }
 
         // Update the long statics (for replenishing shot)
         let statics = dyno.statics.clone();
@@ -236,50 +240,72 @@ pub fn apply_fields(
     bullet_time: Res<BulletTime>,
 ) {
     for (mut dyno, dyno_gt, mut ship) in dynos.iter_mut() {
-        let mut goal_diff_dir = None;
-        let mut goal_dist_sq = None;
-        for (trigger_id, _) in dyno.triggers.iter() {
-            let Ok(goal_gt) = goals.get(*trigger_id) else {
-                continue;
-            };
-            let raw_diff = goal_gt.translation().truncate() - dyno_gt.translation().truncate();
-            goal_dist_sq = Some(raw_diff.length_squared());
-            goal_diff_dir = Some(raw_diff.normalize_or_zero());
-            break;
-        }
-        if let Some(goal_diff_dir) = goal_diff_dir {
-            ship.time_in_goal += bullet_time.factor();
-            ship.dist_to_goal_center_sq = goal_dist_sq.unwrap();
-            let frac = (ship.time_in_goal / FRAMERATE as f32).min(1.0);
-            let strength_range = (0.1, 0.4);
-            let drag_range = (1.0, 0.5);
-            let dirty_interp = |frac: f32, pair: (f32, f32)| pair.0 + frac * (pair.1 - pair.0);
-            let strength = dirty_interp(frac, strength_range);
-            let drag = dirty_interp(frac, drag_range);
-            dyno.vel += goal_diff_dir * strength * bullet_time.factor();
-            dyno.vel *= drag;
-            return;
-        }
+        let dyno_pos = dyno_gt.translation().truncate();
+        apply_fields_helper(
+            &mut dyno,
+            dyno_pos,
+            &mut ship,
+            &fields,
+            &goals,
+            &bullet_time,
+        );
     }
-    for (mut dyno, _, mut ship) in dynos.iter_mut() {
-        ship.time_in_goal = 0.0;
-        ship.dist_to_goal_center_sq = f32::MAX;
-        let mut diff = Vec2::ZERO;
-        let mut killing_ids = HashSet::new();
-        for (trigger_id, mult) in dyno.triggers.iter() {
-            if let Ok(field) = fields.get(*trigger_id) {
-                killing_ids.insert(*trigger_id);
-                diff += field.dir
-                    * field.strength.to_f32()
-                    * *mult
-                    * bullet_time.factor()
-                    * bullet_time.factor();
-                // slowdown *= (1.0 - field.drag.to_f32()).powf(*mult);
-            }
+}
+
+/// Pulls the ship towards the center of the goal if it's in one, otherwise pushes it along any
+/// fields it's in. `dyno_pos` is the ship's position in world space (goals are compared in world
+/// space).
+pub(super) fn apply_fields_helper(
+    dyno: &mut IntDyno,
+    dyno_pos: Vec2,
+    ship: &mut Ship,
+    fields: &Query<&Field>,
+    goals: &Query<&GlobalTransform, With<GoalMarker>>,
+    bullet_time: &BulletTime,
+) {
+    let mut goal_diff_dir = None;
+    let mut goal_dist_sq = None;
+    for (trigger_id, _) in dyno.triggers.iter() {
+        let Ok(goal_gt) = goals.get(*trigger_id) else {
+            continue;
+        };
+        let raw_diff = goal_gt.translation().truncate() - dyno_pos;
+        goal_dist_sq = Some(raw_diff.length_squared());
+        goal_diff_dir = Some(raw_diff.normalize_or_zero());
+        break;
+    }
+    if let Some(goal_diff_dir) = goal_diff_dir {
+        ship.time_in_goal += bullet_time.factor();
+        ship.dist_to_goal_center_sq = goal_dist_sq.unwrap();
+        let frac = (ship.time_in_goal / FRAMERATE as f32).min(1.0);
+        let strength_range = (0.1, 0.4);
+        let drag_range = (1.0, 0.5);
+        let dirty_interp = |frac: f32, pair: (f32, f32)| pair.0 + frac * (pair.1 - pair.0);
+        let strength = dirty_interp(frac, strength_range);
+        let drag = dirty_interp(frac, drag_range);
+        dyno.vel += goal_diff_dir * strength * bullet_time.factor();
+        dyno.vel *= drag;
+        return;
+    }
+    ship.time_in_goal = 0.0;
+    ship.dist_to_goal_center_sq = f32::MAX;
+    let mut diff = Vec2::ZERO;
+    let mut slowdown = 1.0;
+    let mut killing_ids = HashSet::new();
+    for (trigger_id, mult) in dyno.triggers.iter() {
+        if let Ok(field) = fields.get(*trigger_id) {
+            killing_ids.insert(*trigger_id);
+            diff += field.pull(dyno.fpos.truncate())
+                * field.strength.to_f32()
+                * *mult
+                * bullet_time.factor()
+                * bullet_time.factor();
+            slowdown *= (1.0 - field.drag.to_f32()).powf(*mult * bullet_time.factor());
         }
-        dyno.vel += diff;
-        dyno.triggers.retain(|id, _| !killing_ids.contains(id));
     }
+    dyno.vel += diff;
+    dyno.vel *= slowdown;
+    dyno.triggers.retain(|id, _| !killing_ids.contains(id));
 }
 
 pub(super) fn collision_sounds(
@@ -0,0 +1,299 @@
+//! A headless harness for running levels through the real physics.
+//! No window, renderer or audio, just a `MinimalPlugins` app that we tick by hand so that
+//! every run is deterministic. Useful for testing level solvability/regressions in CI.
+
+use std::time::Duration;
+
+use bevy::{
+    ecs::schedule::{ExecutorKind, ScheduleLabel},
+    ecs::system::RunSystemOnce,
+    prelude::*,
+    transform::systems::{propagate_transforms, sync_simple_transforms},
+};
+
+use crate::{
+    environment::{
+        live_poly::{mark_live_polys_ready, LivePolyMarker},
+        portal::{teleport_ships, PortalTravel},
+        replenish::update_replenishes,
+        rock::{move_rocks, Rock, RockKind},
+        segment::{Segment, SegmentKind},
+        stardust::{collect_stardust, CollectStardust},
+        switch::{apply_switches, press_switches},
+    },
+    meta::{
+        consts::FRAMERATE,
+        old_level_data::{old_spawn_level, LevelData},
+    },
+    ship::{replenish_shot, Ship},
+    uid::UId,
+};
+
+use super::{
+    collider::{materialize_collider_stubs, trickle_active, update_triggers},
+    dyno::{apply_fields, move_int_dynos, IntDyno},
+    BulletTime,
+};
+
+/// Thirty seconds of simulated time
+pub const DEFAULT_MAX_TICKS: u32 = FRAMERATE as u32 * 30;
+
+/// How a simulated run ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimOutcome {
+    /// The ship settled into the goal (i.e. the level would be completed)
+    Goal,
+    /// The ship touched a spike segment
+    Spike,
+    /// The ship touched a `RockKind::SimpleKill` rock
+    KillRock,
+    /// The ship left the `LivePolyBundle` bounds
+    OutOfBounds,
+    /// The ship can shoot again, but there are no more shots to take
+    OutOfShots { pos: IVec2 },
+    /// None of the above happened within the tick budget
+    Timeout,
+}
+
+/// Everything we learned from a simulated run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimRun {
+    /// Position of the ship at the end of every tick
+    pub trajectory: Vec<Vec2>,
+    /// Tick index that each shot was launched on
+    pub shot_ticks: Vec<usize>,
+    /// Uids of the stardust picked up, in the order it was picked up
+    pub stardust: Vec<UId>,
+    pub outcome: SimOutcome,
+}
+
+/// The schedule that plays the role of `FixedUpdate` (+ the handful of `Update` systems physics
+/// depends on) for headless runs
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+struct HeadlessTick;
+
+#[derive(Debug, Clone)]
+pub struct HeadlessSim {
+    level_data: LevelData,
+    max_ticks: u32,
+}
+impl HeadlessSim {
+    pub fn new(level_data: LevelData) -> Self {
+        Self {
+            level_data,
+            max_ticks: DEFAULT_MAX_TICKS,
+        }
+    }
+
+    pub fn with_max_ticks(mut self, max_ticks: u32) -> Self {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    pub fn level_data(&self) -> &LevelData {
+        &self.level_data
+    }
+
+    /// Launch the ship once from `start` with velocity `vel`
+    pub fn launch(&self, start: IVec2, vel: Vec2) -> SimRun {
+        self.run(start, &[vel])
+    }
+
+    /// Place the ship at `start` and take `shots` in order, each one as soon as the ship is able
+    /// to shoot (same rules as the real game, so replenishes and resting on rocks count)
+    pub fn run(&self, start: IVec2, shots: &[Vec2]) -> SimRun {
+        let mut app = self.build_app(start);
+        let mut shots = shots.iter();
+        let mut result = SimRun {
+            trajectory: vec![],
+            shot_ticks: vec![],
+            stardust: vec![],
+            outcome: SimOutcome::Timeout,
+        };
+        for tick in 0..self.max_ticks as usize {
+            let can_shoot = ship_mut(&mut app.world).1.can_shoot;
+            if can_shoot {
+                let Some(vel) = shots.next() else {
+                    let pos = ship_mut(&mut app.world).0.get_ipos().truncate();
+                    result.outcome = SimOutcome::OutOfShots { pos };
+                    return result;
+                };
+                // Mirrors `ship::launch_ship`. We're never in bullet time
+                let (mut dyno, mut ship) = ship_mut(&mut app.world);
+                dyno.vel = *vel;
+                ship.can_shoot = false;
+                result.shot_ticks.push(tick);
+            }
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f64(1.0 / FRAMERATE));
+            app.world.run_schedule(HeadlessTick);
+            let mut collected = app.world.resource_mut::<Events<CollectStardust>>();
+            result
+                .stardust
+                .extend(collected.drain().map(|collect| collect.uid));
+            result
+                .trajectory
+                .push(ship_mut(&mut app.world).0.fpos.truncate());
+            if let Some(outcome) = app.world.run_system_once(classify_ship) {
+                result.outcome = outcome;
+                return result;
+            }
+        }
+        result
+    }
+
+    pub(super) fn build_app(&self, start: IVec2) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, HierarchyPlugin));
+        app.insert_resource(BulletTime::new());
+        app.add_event::<PortalTravel>();
+        app.add_event::<CollectStardust>();
+        let mut schedule = Schedule::new(HeadlessTick);
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_systems(
+            (
+                move_rocks,
+                move_int_dynos,
+                update_triggers,
+                apply_fields,
+                teleport_ships,
+                press_switches,
+                apply_switches,
+                collect_stardust,
+                replenish_shot,
+                mark_live_polys_ready,
+                update_replenishes,
+                trickle_active,
+                materialize_collider_stubs,
+                sync_simple_transforms,
+                propagate_transforms,
+            )
+                .chain(),
+        );
+        app.add_schedule(schedule);
+
+        app.world
+            .run_system_once_with((0, self.level_data.clone(), IVec2::ZERO), old_spawn_level);
+        {
+            let (mut dyno, mut ship) = ship_mut(&mut app.world);
+            dyno.fpos = start.as_vec2().extend(dyno.fpos.z);
+            ship.last_safe_location = start;
+        }
+        // Let the colliders materialize and the transforms settle before anything moves
+        app.world.run_system_once(materialize_collider_stubs);
+        app.world.run_system_once(sync_simple_transforms);
+        app.world.run_system_once(propagate_transforms);
+        app.world.run_system_once(mark_live_polys_ready);
+        app
+    }
+}
+
+fn ship_mut(world: &mut World) -> (Mut<'_, IntDyno>, Mut<'_, Ship>) {
+    let mut q = world.query::<(&mut IntDyno, &mut Ship)>();
+    let (dyno, ship) = q.single_mut(world);
+    (dyno, ship)
+}
+
+/// Decides if the run is over. Mirrors the checks in `ship.rs` and `leveler/mod.rs`
+fn classify_ship(
+    ship_q: Query<(&Ship, &IntDyno)>,
+    rocks: Query<&Rock>,
+    segments: Query<&Segment>,
+    live_polys: Query<(Entity, &LivePolyMarker)>,
+) -> Option<SimOutcome> {
+    let (ship, dyno) = ship_q.single();
+    if ship.is_settled_in_goal(dyno) {
+        return Some(SimOutcome::Goal);
+    }
+    let on_spike = dyno.triggers.keys().any(|eid| {
+        segments
+            .get(*eid)
+            .is_ok_and(|seg| seg.kind == SegmentKind::Spike)
+    });
+    if on_spike {
+        return Some(SimOutcome::Spike);
+    }
+    let on_kill_rock = dyno.statics.keys().any(|eid| {
+        rocks
+            .get(*eid)
+            .is_ok_and(|rock| rock.kind == RockKind::SimpleKill)
+    });
+    if on_kill_rock {
+        return Some(SimOutcome::KillRock);
+    }
+    let in_bounds = live_polys
+        .iter()
+        .any(|(lpid, lp)| !lp.ready || dyno.triggers.contains_key(&lpid));
+    if !in_bounds {
+        return Some(SimOutcome::OutOfBounds);
+    }
+    None
+}
+
+#[cfg(test)]
+mod headless_tests {
+    use super::*;
+
+    fn load_all_levels() -> Vec<(String, LevelData)> {
+        let ids = LevelData::ids_on_disk();
+        assert!(!ids.is_empty());
+        ids.into_iter()
+            .map(|id| {
+                let level_data = LevelData::read_from_disk(&id).unwrap();
+                (id, level_data)
+            })
+            .collect()
+    }
+
+    /// A flat floor (with gravity) with the start on the left and the goal on the right
+    fn floor_level(kind: RockKind) -> LevelData {
+        let mut level_data = LevelData::floor_level();
+        level_data.rocks[0].kind = kind;
+        level_data
+    }
+
+    #[test]
+    fn levels_are_deterministic() {
+        for (name, level_data) in load_all_levels() {
+            let sim = HeadlessSim::new(level_data.clone()).with_max_ticks(FRAMERATE as u32 * 5);
+            let vel = Vec2::new(1.5, 2.0);
+            let first = sim.launch(level_data.start, vel);
+            let second = sim.launch(level_data.start, vel);
+            assert!(!first.trajectory.is_empty(), "{name} didn't simulate");
+            assert_eq!(first, second, "{name} isn't deterministic");
+        }
+    }
+
+    #[test]
+    fn launching_into_space_is_oob() {
+        for (name, level_data) in load_all_levels() {
+            let sim = HeadlessSim::new(level_data.clone());
+            let result = sim.launch(level_data.start + IVec2::new(0, 2_000), Vec2::ZERO);
+            assert_eq!(result.outcome, SimOutcome::OutOfBounds, "{name}");
+        }
+    }
+
+    #[test]
+    fn dropping_onto_kill_rock_dies() {
+        let sim = HeadlessSim::new(floor_level(RockKind::SimpleKill));
+        let result = sim.launch(IVec2::new(0, 10), Vec2::new(0.0, -1.0));
+        assert_eq!(result.outcome, SimOutcome::KillRock);
+    }
+
+    #[test]
+    fn resting_on_rock_runs_out_of_shots() {
+        let sim = HeadlessSim::new(floor_level(RockKind::Normal));
+        let result = sim.launch(IVec2::new(0, 10), Vec2::new(0.0, -1.0));
+        assert!(matches!(result.outcome, SimOutcome::OutOfShots { .. }));
+        assert_eq!(result.shot_ticks, vec![0]);
+    }
+
+    #[test]
+    fn starting_in_goal_wins() {
+        let level_data = floor_level(RockKind::Normal);
+        let sim = HeadlessSim::new(level_data.clone());
+        let result = sim.launch(level_data.goal, Vec2::ZERO);
+        assert_eq!(result.outcome, SimOutcome::Goal);
+    }
+}
@@ -1,16 +1,21 @@
 use self::{
     collider::{materialize_collider_stubs, trickle_active},
     dyno::{move_int_dynos, register_int_dynos, IntDyno},
+    preview::{draw_shot_preview, update_shot_preview, AssistSettings, ShotPreview},
 };
 use crate::{
-    environment::convo::Convo,
-    input::MouseState,
+    environment::{convo::Convo, rock::move_rocks, switch::apply_switches},
+    input::{watch_mouse, MouseState},
     meta::game_state::{EditorState, GameState, MetaState},
+    replay::ReplayState,
 };
 use bevy::prelude::*;
 
 pub mod collider;
 pub mod dyno;
+pub mod headless;
+pub mod preview;
+pub mod solver;
 
 #[derive(Resource)]
 pub struct BulletTime {
@@ -54,11 +59,15 @@ pub fn should_apply_physics(gs: Res<GameState>, convos: Query<&Convo>) -> bool {
 pub fn update_bullet_time(
     mut bullet_time: ResMut<BulletTime>,
     mouse_state: Res<MouseState>,
+    replay_state: Res<ReplayState>,
     mut dynos: Query<&mut IntDyno>,
 ) {
-    let in_bullet_time = match mouse_state.pending_launch.as_ref() {
-        Some(pending) => pending.timer.is_some(),
-        None => false,
+    let in_bullet_time = match replay_state.playback_bullet_time() {
+        Some(slow) => slow,
+        None => match mouse_state.pending_launch.as_ref() {
+            Some(pending) => pending.timer.is_some(),
+            None => false,
+        },
     };
     let mut scale_dyno_vels = |scale: f32| {
         for mut dyno in dynos.iter_mut() {
@@ -91,7 +100,24 @@ impl Plugin for PhysicsPlugin {
         app.insert_resource(BulletTime::new());
         app.register_type::<IntDyno>();
         app.add_systems(Update, materialize_collider_stubs);
-        app.add_systems(Update, trickle_active);
+        // Doors are switched in FixedUpdate, so their colliders need to follow before the next tick
+        app.add_systems(FixedUpdate, trickle_active.after(apply_switches));
         app.add_systems(FixedUpdate, update_bullet_time.before(move_int_dynos));
+        app.add_systems(
+            FixedUpdate,
+            move_rocks
+                .after(update_bullet_time)
+                .before(move_int_dynos)
+                .run_if(should_apply_physics),
+        );
+        app.insert_resource(AssistSettings::default());
+        app.insert_resource(ShotPreview::default());
+        app.add_systems(
+            Update,
+            (update_shot_preview, draw_shot_preview)
+                .chain()
+                .after(watch_mouse)
+                .run_if(should_apply_physics),
+        );
     }
 }
@@ -0,0 +1,201 @@
+//! Predicting where the pending launch will send the ship, by running the real physics forward a
+//! few seconds without touching anything. Always on while testing in the editor, and an assist
+//! option everywhere else.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    environment::{
+        field::Field,
+        goal::GoalMarker,
+        portal::{teleport_helper, Portal},
+        rock::{Rock, RockKind},
+        segment::{Segment, SegmentKind},
+    },
+    input::MouseState,
+    meta::{
+        consts::FRAMERATE,
+        game_state::{EditorState, GameState},
+    },
+    ship::{Dead, Ship},
+};
+
+use super::{
+    collider::{ColliderActive, ColliderBoundary, ColliderStatic, ColliderTrigger},
+    dyno::{apply_fields_helper, move_int_dyno_helper, IntDyno},
+    BulletTime,
+};
+
+/// How far ahead the preview looks
+const PREVIEW_TICKS: usize = FRAMERATE as usize * 3;
+
+#[derive(Resource, Debug, Default)]
+pub struct AssistSettings {
+    /// Show the predicted path of the shot being aimed (outside of the editor)
+    pub shot_preview: bool,
+}
+
+/// Where the ship would go if the pending launch happened right now
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct ShotPreview {
+    /// Position of the ship at the end of every tick, in world space
+    pub path: Vec<Vec2>,
+    /// Where the ship would bounce off of rocks (or springs)
+    pub bounces: Vec<Vec2>,
+}
+
+/// Everything the ship can run into. Read-only, so predicting doesn't disturb the real thing
+#[derive(SystemParam)]
+pub struct PreviewColliders<'w, 's> {
+    statics: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static ColliderBoundary,
+            &'static ColliderStatic,
+            &'static Parent,
+        ),
+        With<ColliderActive>,
+    >,
+    triggers: Query<
+        'w,
+        's,
+        (
+            &'static ColliderBoundary,
+            &'static ColliderTrigger,
+            &'static Parent,
+        ),
+        With<ColliderActive>,
+    >,
+    segments: Query<'w, 's, &'static Segment>,
+    rocks: Query<'w, 's, &'static Rock>,
+    fields: Query<'w, 's, &'static Field>,
+    portals: Query<'w, 's, &'static Portal>,
+    goals: Query<'w, 's, &'static GlobalTransform, With<GoalMarker>>,
+}
+
+/// Runs the same steps as a `FixedUpdate` tick (`move_int_dynos`, `apply_fields`, then
+/// `teleport_ships`) on a copy of the ship, stopping early if the ship would die or finish.
+/// `offset` takes the ship's local position to world space. Moving (and spinning) rocks are
+/// treated as if they stopped where they are, and switches don't flip anything.
+pub fn predict_shot(
+    ship: &Ship,
+    real_dyno: &IntDyno,
+    offset: Vec2,
+    vel: Vec2,
+    colliders: &PreviewColliders,
+) -> ShotPreview {
+    let mut ship = ship.clone();
+    let mut dyno = IntDyno::new(real_dyno.get_ipos(), real_dyno.radius);
+    dyno.fpos = real_dyno.fpos;
+    dyno.vel = vel;
+    let bullet_time = BulletTime::new();
+    let mut preview = ShotPreview::default();
+    for _ in 0..PREVIEW_TICKS {
+        dyno.statics.clear();
+        dyno.triggers.clear();
+        let springs = move_int_dyno_helper(
+            &mut dyno,
+            &colliders.statics,
+            &colliders.triggers,
+            &colliders.segments,
+            &bullet_time,
+        );
+        let bounces = dyno
+            .statics
+            .values()
+            .map(|coll| coll.pos)
+            .chain(springs.iter().map(|(_, pos)| pos.truncate()));
+        preview.bounces.extend(bounces.map(|pos| pos + offset));
+        let dyno_pos = dyno.get_ipos().truncate().as_vec2() + offset;
+        apply_fields_helper(
+            &mut dyno,
+            dyno_pos,
+            &mut ship,
+            &colliders.fields,
+            &colliders.goals,
+            &bullet_time,
+        );
+        teleport_helper(&mut ship, &mut dyno, &colliders.portals);
+        preview.path.push(dyno.fpos.truncate() + offset);
+        // Mirrors the checks in `ship.rs`
+        let on_spike = dyno.triggers.keys().any(|eid| {
+            colliders
+                .segments
+                .get(*eid)
+                .is_ok_and(|seg| seg.kind == SegmentKind::Spike)
+        });
+        let on_kill_rock = dyno.statics.keys().any(|eid| {
+            colliders
+                .rocks
+                .get(*eid)
+                .is_ok_and(|rock| rock.kind == RockKind::SimpleKill)
+        });
+        if on_spike || on_kill_rock || ship.is_settled_in_goal(&dyno) {
+            break;
+        }
+    }
+    preview
+}
+
+pub(super) fn update_shot_preview(
+    gs: Res<GameState>,
+    settings: Res<AssistSettings>,
+    mouse: Res<MouseState>,
+    ship_q: Query<(&Ship, &IntDyno, &GlobalTransform), Without<Dead>>,
+    colliders: PreviewColliders,
+    mut preview: ResMut<ShotPreview>,
+) {
+    let wanted = settings.shot_preview || gs.get_editor_state() == Some(EditorState::Testing);
+    let pending = mouse.pending_launch.as_ref().filter(|_| wanted);
+    let (Some(pending), Ok((ship, dyno, gtran))) = (pending, ship_q.get_single()) else {
+        *preview = ShotPreview::default();
+        return;
+    };
+    if !ship.can_shoot {
+        *preview = ShotPreview::default();
+        return;
+    }
+    let offset = gtran.translation().truncate() - dyno.get_ipos().truncate().as_vec2();
+    *preview = predict_shot(ship, dyno, offset, pending.launch_vel, &colliders);
+}
+
+pub(super) fn draw_shot_preview(preview: Res<ShotPreview>, mut gizmos: Gizmos) {
+    let color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+    gizmos.linestrip_2d(preview.path.iter().copied(), color);
+    for bounce in preview.bounces.iter() {
+        gizmos.circle_2d(*bounce, 1.5, color);
+    }
+}
+
+#[cfg(test)]
+mod preview_tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{meta::old_level_data::LevelData, physics::headless::HeadlessSim};
+
+    #[test]
+    fn preview_matches_the_real_physics() {
+        let level_data = LevelData {
+            goal: IVec2::new(200, 200),
+            ..LevelData::floor_level()
+        };
+        let (start, vel) = (IVec2::new(-20, 20), Vec2::new(1.0, 1.5));
+        let sim = HeadlessSim::new(level_data);
+        let real = sim.launch(start, vel);
+        let mut app = sim.build_app(start);
+        let preview = app.world.run_system_once_with(
+            vel,
+            |In(vel): In<Vec2>, ship_q: Query<(&Ship, &IntDyno)>, colliders: PreviewColliders| {
+                let (ship, dyno) = ship_q.single();
+                predict_shot(ship, dyno, Vec2::ZERO, vel, &colliders)
+            },
+        );
+        let len = preview.path.len().min(real.trajectory.len());
+        assert!(len > 10);
+        assert!(!preview.bounces.is_empty());
+        assert_eq!(preview.path[..len], real.trajectory[..len]);
+    }
+}
@@ -0,0 +1,181 @@
+//! Brute-force solver that searches the launch space for shot sequences that win a level.
+//! Runs on top of `HeadlessSim`, so whatever it finds is what the real physics would do.
+//! Mostly useful for figuring out par values and catching levels that tuning made unwinnable.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::input::max_launch_speed;
+
+use super::headless::{HeadlessSim, SimOutcome};
+
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    /// How many evenly spaced launch directions to try
+    pub num_angles: u32,
+    /// How many evenly spaced launch speeds to try, up to `input::max_launch_speed`
+    pub num_powers: u32,
+    /// Give up after this many shots
+    pub max_shots: usize,
+    /// Max number of distinct resting spots to keep exploring from after each shot
+    pub max_frontier: usize,
+}
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            num_angles: 32,
+            num_powers: 12,
+            max_shots: 3,
+            max_frontier: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// Launch velocities, in order
+    pub shots: Vec<Vec2>,
+    /// Position of the ship at the end of every tick of the winning run
+    pub trajectory: Vec<Vec2>,
+}
+impl Solution {
+    pub fn par(&self) -> usize {
+        self.shots.len()
+    }
+}
+
+/// Every launch velocity the solver will try from any one resting spot
+pub fn launch_space(config: &SolverConfig) -> Vec<Vec2> {
+    let max_speed = max_launch_speed();
+    let mut result = vec![];
+    for a in 0..config.num_angles {
+        let angle = a as f32 / config.num_angles as f32 * std::f32::consts::TAU;
+        let dir = Vec2::from_angle(angle);
+        for p in 1..=config.num_powers {
+            let speed = p as f32 / config.num_powers as f32 * max_speed;
+            result.push(dir * speed);
+        }
+    }
+    result
+}
+
+/// Breadth-first search over shot sequences, so the first solution found uses the fewest shots
+/// (within the resolution of the launch space).
+/// Sequences that come to rest in the same spot are only explored once.
+pub fn solve(sim: &HeadlessSim, config: &SolverConfig) -> Option<Solution> {
+    let start = sim.level_data().start;
+    let candidates = launch_space(config);
+    let mut seen = HashSet::new();
+    seen.insert(start);
+    let mut frontier: Vec<Vec<Vec2>> = vec![vec![]];
+    for _ in 0..config.max_shots {
+        let mut next_frontier = vec![];
+        for prefix in frontier.iter() {
+            for vel in candidates.iter() {
+                let mut shots = prefix.clone();
+                shots.push(*vel);
+                let run = sim.run(start, &shots);
+                match run.outcome {
+                    SimOutcome::Goal => {
+                        return Some(Solution {
+                            shots,
+                            trajectory: run.trajectory,
+                        });
+                    }
+                    SimOutcome::OutOfShots { pos }
+                        if next_frontier.len() < config.max_frontier && seen.insert(pos) =>
+                    {
+                        next_frontier.push(shots);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+    None
+}
+
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use crate::meta::{
+        old_level_data::{ExportedRock, LevelData},
+        progress::Galaxies,
+    };
+
+    /// A floor with gravity, and a wall between the start and the goal
+    fn wall_level() -> LevelData {
+        let mut level_data = LevelData::floor_level();
+        level_data.rocks.push(ExportedRock::from_points(&[
+            (-5, 0),
+            (-5, 30),
+            (5, 30),
+            (5, 0),
+        ]));
+        level_data
+    }
+
+    #[test]
+    fn launch_space_respects_input_limits() {
+        let config = SolverConfig::default();
+        let space = launch_space(&config);
+        assert_eq!(
+            space.len(),
+            (config.num_angles * config.num_powers) as usize
+        );
+        assert!(space
+            .iter()
+            .all(|vel| vel.length() <= max_launch_speed() + 0.001));
+    }
+
+    #[test]
+    fn solves_wall_level() {
+        let sim = HeadlessSim::new(wall_level());
+        let solution =
+            solve(&sim, &SolverConfig::default()).expect("wall level should be winnable");
+        // Replaying the solution should win too
+        let replay = sim.run(sim.level_data().start, &solution.shots);
+        assert_eq!(replay.outcome, SimOutcome::Goal);
+        assert!(solution.par() >= 1);
+    }
+
+    #[test]
+    fn shipped_levels_make_par() {
+        let galaxies = Galaxies::read_from_disk().unwrap();
+        for galaxy in galaxies.galaxies.iter() {
+            for meta in galaxy.levels.iter() {
+                let Some(par) = meta.par else {
+                    continue;
+                };
+                let config = SolverConfig {
+                    max_shots: par as usize,
+                    ..default()
+                };
+                let sim = HeadlessSim::new(LevelData::read_from_disk(&meta.id).unwrap());
+                assert!(solve(&sim, &config).is_some(), "{} can't make par", meta.id);
+            }
+        }
+    }
+
+    #[test]
+    fn sealed_goal_is_unsolvable() {
+        let mut level_data = wall_level();
+        level_data.rocks[1].points = vec![
+            IVec2::new(-5, 0),
+            IVec2::new(-5, 500),
+            IVec2::new(5, 500),
+            IVec2::new(5, 0),
+        ];
+        let config = SolverConfig {
+            num_angles: 8,
+            num_powers: 4,
+            max_shots: 2,
+            max_frontier: 4,
+        };
+        let sim = HeadlessSim::new(level_data);
+        assert_eq!(solve(&sim, &config), None);
+    }
+}
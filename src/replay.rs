@@ -0,0 +1,408 @@
+//! Recording and playing back level attempts.
+//! Every launch, respawn and bullet time toggle is stamped with the `FixedUpdate` tick it took
+//! effect on, so feeding them back in at the same ticks reproduces the run.
+//! The best winning attempt at each level is also kept around and raced as a ghost.
+
+use std::{fs::File, io::Write};
+
+use bevy::prelude::*;
+
+use crate::{
+    cutscenes::is_not_in_cutscene,
+    input::{update_long_presses, LaunchEvent, LongKeyPress},
+    leveler::load::did_level_change,
+    meta::{
+        game_state::{left_level, GameState, LevelState, MetaState, SetMetaState},
+        old_level_data::LevelRoot,
+        progress::GalaxyKind,
+    },
+    physics::{dyno::IntDyno, should_apply_physics, update_bullet_time, BulletTime},
+    ship::{launch_ship, GhostShip, GhostShipBundle, Ship},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ReplayEventKind {
+    /// The ship launched with this (pre bullet time) velocity
+    Launch(Vec2),
+    /// The respawn `LongKeyPress` activated
+    Respawn,
+    /// Bullet time turned on (true) or off (false)
+    BulletTime(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReplayEvent {
+    pub tick: u32,
+    pub kind: ReplayEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct Replay {
+    pub kind: GalaxyKind,
+    pub level_id: String,
+    pub events: Vec<ReplayEvent>,
+    /// How many ticks the attempt lasted
+    pub num_ticks: u32,
+    pub won: bool,
+    /// Position of the ship at the start of every tick, for ghosts
+    #[serde(default)]
+    pub trajectory: Vec<IVec2>,
+}
+impl Replay {
+    pub fn new(kind: GalaxyKind, level_id: String) -> Self {
+        Self {
+            kind,
+            level_id,
+            ..default()
+        }
+    }
+
+    /// Where the most recent attempt at a level is saved
+    pub fn last_path(level_id: &str) -> String {
+        format!("assets/saves/{level_id}.last.replay.ron")
+    }
+
+    /// Where the best winning attempt at a level is saved
+    pub fn best_path(level_id: &str) -> String {
+        format!("assets/saves/{level_id}.best.replay.ron")
+    }
+
+    pub fn num_shots(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event.kind, ReplayEventKind::Launch(_)))
+            .count()
+    }
+
+    /// Is this a better run than `other`? Only wins count, then fewer shots, then less time
+    pub fn beats(&self, other: &Self) -> bool {
+        if !self.won {
+            return false;
+        }
+        if !other.won {
+            return true;
+        }
+        (self.num_shots(), self.num_ticks) < (other.num_shots(), other.num_ticks)
+    }
+
+    /// Loads either a path to a `.replay.ron` file, or the last attempt at a level id
+    pub fn read_from_disk(path_or_id: &str) -> Result<Self, String> {
+        let path = if path_or_id.ends_with(".ron") {
+            path_or_id.to_string()
+        } else {
+            Self::last_path(path_or_id)
+        };
+        let contents = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+        ron::from_str(&contents).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn write_to_disk(&self, path: &str) -> Result<(), String> {
+        let contents = ron::to_string(self).map_err(|e| format!("{path}: {e}"))?;
+        let mut file = File::create(path).map_err(|e| format!("{path}: {e}"))?;
+        file.write_all(contents.as_bytes())
+            .map_err(|e| format!("{path}: {e}"))
+    }
+
+    /// Bullet time as of the last recorded toggle
+    fn ends_slow(&self) -> bool {
+        self.events
+            .iter()
+            .rev()
+            .find_map(|event| match event.kind {
+                ReplayEventKind::BulletTime(slow) => Some(slow),
+                _ => None,
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum ReplayMode {
+    #[default]
+    Idle,
+    Recording(Replay),
+    /// Waiting for the level to start before playing back
+    Queued(Replay),
+    Playing {
+        replay: Replay,
+        /// Index of the next event to play
+        cursor: usize,
+        slow: bool,
+    },
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ReplayState {
+    /// `FixedUpdate` ticks since the current attempt started
+    pub tick: u32,
+    pub mode: ReplayMode,
+    /// Trajectory of the best run, waiting for the attempt to start so it can be raced
+    pub pending_ghost: Option<Vec<IVec2>>,
+}
+impl ReplayState {
+    /// Start the game by playing back this replay
+    pub fn queue(replay: Replay) -> Self {
+        Self {
+            mode: ReplayMode::Queued(replay),
+            ..default()
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, ReplayMode::Playing { .. })
+    }
+
+    /// Records an event on the current tick (if we're recording)
+    pub fn record(&mut self, kind: ReplayEventKind) {
+        let tick = self.tick;
+        if let ReplayMode::Recording(replay) = &mut self.mode {
+            replay.events.push(ReplayEvent { tick, kind });
+        }
+    }
+
+    /// While playing back, bullet time comes from the replay instead of the mouse
+    pub fn playback_bullet_time(&self) -> Option<bool> {
+        match self.mode {
+            ReplayMode::Playing { slow, .. } => Some(slow),
+            _ => None,
+        }
+    }
+}
+
+/// Saves a finished attempt as the last attempt, and as the best attempt if it beat the old one
+fn save_finished_replay(replay: &Replay) -> Result<(), String> {
+    if replay.events.is_empty() {
+        return Ok(());
+    }
+    replay.write_to_disk(&Replay::last_path(&replay.level_id))?;
+    let best_path = Replay::best_path(&replay.level_id);
+    let is_best = match Replay::read_from_disk(&best_path) {
+        Ok(best) => replay.beats(&best),
+        Err(_) => replay.won,
+    };
+    if is_best {
+        replay.write_to_disk(&best_path)?;
+    }
+    Ok(())
+}
+
+/// Saves the attempt that just ended (if any) and starts recording/playing the next one
+pub(crate) fn cycle_replay_runs(mut replay_state: ResMut<ReplayState>, gs: Res<GameState>) {
+    replay_state.tick = 0;
+    replay_state.pending_ghost = None;
+    let old_mode = std::mem::take(&mut replay_state.mode);
+    if let ReplayMode::Recording(replay) = &old_mode {
+        if let Err(e) = save_finished_replay(replay) {
+            warn!("Can't save replay: {e}");
+        }
+    }
+    let Some(level_state) = gs.get_level_state() else {
+        return;
+    };
+    if !matches!(old_mode, ReplayMode::Queued(_)) {
+        replay_state.pending_ghost = Replay::read_from_disk(&Replay::best_path(&level_state.id))
+            .ok()
+            .map(|best| best.trajectory)
+            .filter(|trajectory| !trajectory.is_empty());
+    }
+    replay_state.mode = match old_mode {
+        ReplayMode::Queued(replay) if replay.level_id == level_state.id => ReplayMode::Playing {
+            replay,
+            cursor: 0,
+            slow: false,
+        },
+        _ => ReplayMode::Recording(Replay::new(level_state.kind, level_state.id)),
+    };
+}
+
+/// Kicks off the level a queued replay needs. Only asks once, the level takes a few frames to
+/// start and the replay stays queued until it does
+fn start_queued_replay(
+    replay_state: Res<ReplayState>,
+    mut gs_writer: EventWriter<SetMetaState>,
+    mut sent: Local<bool>,
+) {
+    let ReplayMode::Queued(replay) = &replay_state.mode else {
+        *sent = false;
+        return;
+    };
+    if *sent {
+        return;
+    }
+    *sent = true;
+    gs_writer.send(SetMetaState(MetaState::Level(
+        LevelState::from_galaxy_n_level(replay.kind.clone(), replay.level_id.clone()),
+    )));
+}
+
+/// Advances the clock, but only while there's a ship that's still trying to win
+fn tick_replay_clock(mut replay_state: ResMut<ReplayState>, ships: Query<(&Ship, &IntDyno)>) {
+    let Some((_, dyno)) = ships.iter().next() else {
+        return;
+    };
+    if ships.iter().any(|(ship, _)| ship.finished) {
+        if let ReplayMode::Recording(replay) = &mut replay_state.mode {
+            replay.won = true;
+        }
+        return;
+    }
+    replay_state.tick += 1;
+    let tick = replay_state.tick;
+    if let ReplayMode::Recording(replay) = &mut replay_state.mode {
+        replay.num_ticks = tick;
+        replay.trajectory.push(dyno.get_ipos().truncate());
+    }
+}
+
+/// Spawns the ghost of the best run once the attempt starts, then keeps it in lockstep
+fn update_ghosts(
+    mut replay_state: ResMut<ReplayState>,
+    mut ghosts: Query<(&GhostShip, &mut Transform)>,
+    level_root: Query<Entity, With<LevelRoot>>,
+    mut commands: Commands,
+) {
+    if replay_state.tick == 0 {
+        return;
+    }
+    if let Some(trajectory) = replay_state.pending_ghost.take() {
+        if let Ok(level_root) = level_root.get_single() {
+            commands.entity(level_root).with_children(|parent| {
+                parent.spawn(GhostShipBundle::new(trajectory));
+            });
+        }
+        return;
+    }
+    let ix = replay_state.tick as usize - 1;
+    for (ghost, mut tran) in ghosts.iter_mut() {
+        let Some(pos) = ghost.trajectory.get(ix).or(ghost.trajectory.last()) else {
+            continue;
+        };
+        tran.translation.x = pos.x as f32;
+        tran.translation.y = pos.y as f32;
+    }
+}
+
+/// Replaces live input with whatever the replay did on this tick
+pub(crate) fn play_replay_events(
+    mut replay_state: ResMut<ReplayState>,
+    mut launch_events: ResMut<Events<LaunchEvent>>,
+    mut long_presses: Query<&mut LongKeyPress, With<Ship>>,
+) {
+    let tick = replay_state.tick;
+    let ReplayMode::Playing {
+        replay,
+        cursor,
+        slow,
+    } = &mut replay_state.mode
+    else {
+        return;
+    };
+    launch_events.clear();
+    for mut lp in long_presses.iter_mut() {
+        lp.reset();
+    }
+    while let Some(event) = replay.events.get(*cursor) {
+        if event.tick > tick {
+            break;
+        }
+        match event.kind {
+            ReplayEventKind::Launch(vel) => {
+                launch_events.send(LaunchEvent { vel });
+            }
+            ReplayEventKind::Respawn => {
+                for mut lp in long_presses.iter_mut() {
+                    lp.force_activate();
+                }
+            }
+            ReplayEventKind::BulletTime(is_slow) => {
+                *slow = is_slow;
+            }
+        }
+        *cursor += 1;
+    }
+}
+
+fn record_bullet_time(mut replay_state: ResMut<ReplayState>, bullet_time: Res<BulletTime>) {
+    let ReplayMode::Recording(replay) = &replay_state.mode else {
+        return;
+    };
+    if replay.ends_slow() != bullet_time.is_slow {
+        replay_state.record(ReplayEventKind::BulletTime(bullet_time.is_slow));
+    }
+}
+
+pub fn register_replay(app: &mut App) {
+    app.insert_resource(ReplayState::default());
+    app.add_systems(
+        Update,
+        cycle_replay_runs.run_if(did_level_change.or_else(left_level)),
+    );
+    app.add_systems(Update, start_queued_replay);
+    app.add_systems(
+        FixedUpdate,
+        (tick_replay_clock, play_replay_events, update_ghosts)
+            .chain()
+            .run_if(should_apply_physics)
+            .run_if(is_not_in_cutscene)
+            .after(update_long_presses)
+            .before(launch_ship),
+    );
+    app.add_systems(
+        FixedUpdate,
+        record_bullet_time
+            .run_if(should_apply_physics)
+            .run_if(is_not_in_cutscene)
+            .after(update_bullet_time),
+    );
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+
+    #[test]
+    fn replays_round_trip_through_ron() {
+        let mut state = ReplayState {
+            tick: 3,
+            mode: ReplayMode::Recording(Replay::new(
+                GalaxyKind::new("Springy"),
+                "springy_1".into(),
+            )),
+            ..default()
+        };
+        state.record(ReplayEventKind::BulletTime(true));
+        state.tick = 40;
+        state.record(ReplayEventKind::BulletTime(false));
+        state.record(ReplayEventKind::Launch(Vec2::new(1.5, -2.25)));
+        state.tick = 90;
+        state.record(ReplayEventKind::Respawn);
+        let ReplayMode::Recording(replay) = state.mode else {
+            panic!("stopped recording");
+        };
+        assert!(!replay.ends_slow());
+        let text = ron::to_string(&replay).unwrap();
+        let parsed: Replay = ron::from_str(&text).unwrap();
+        assert_eq!(parsed, replay);
+        assert_eq!(parsed.events[2].tick, 40);
+    }
+
+    #[test]
+    fn fewer_shots_beat_faster_runs() {
+        let run = |shots: u32, num_ticks: u32, won: bool| Replay {
+            events: (0..shots)
+                .map(|tick| ReplayEvent {
+                    tick,
+                    kind: ReplayEventKind::Launch(Vec2::X),
+                })
+                .collect(),
+            num_ticks,
+            won,
+            ..default()
+        };
+        assert!(run(1, 500, true).beats(&run(2, 100, true)));
+        assert!(run(2, 100, true).beats(&run(2, 200, true)));
+        assert!(run(5, 900, true).beats(&run(1, 10, false)));
+        assert!(!run(1, 10, false).beats(&run(5, 900, true)));
+        assert!(!run(2, 100, true).beats(&run(2, 100, true)));
+    }
+}
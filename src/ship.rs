@@ -12,6 +12,7 @@ use crate::environment::particle::{
 };
 use crate::environment::replenish::{ReplenishCharging, ReplenishMarker};
 use crate::environment::rock::{Rock, RockKind};
+use crate::environment::segment::{Segment, SegmentKind};
 use crate::input::LaunchEvent;
 use crate::input::LongKeyPress;
 use crate::leveler::load::destroy_level;
@@ -21,28 +22,52 @@ use crate::meta::game_state::{GameState, MetaState, SetMetaState};
 use crate::meta::old_level_data::LevelRoot;
 use crate::physics::collider::ColliderActive;
 use crate::physics::dyno::{apply_fields, IntDyno};
-use crate::physics::{should_apply_physics, BulletTime};
+use crate::physics::{should_apply_physics, update_bullet_time, BulletTime};
+use crate::replay::{play_replay_events, ReplayEventKind, ReplayState};
 use crate::sound::effect::SoundEffect;
 use bevy::prelude::*;
 use rand::{thread_rng, Rng};
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Ship {
     pub can_shoot: bool,
     pub last_safe_location: IVec2,
     pub time_in_goal: f32,
     pub dist_to_goal_center_sq: f32,
     pub finished: bool,
+    /// The portal end the ship last came out of. It stays shut until the ship has left it
+    pub portal_cooldown: Option<IVec2>,
 }
 impl Ship {
     pub const fn radius() -> f32 {
         4.0
     }
+
+    /// Has the ship spent long enough (and come to rest close enough) in the goal to finish?
+    pub fn is_settled_in_goal(&self, dyno: &IntDyno) -> bool {
+        let enough_time = !self.finished && self.time_in_goal > FRAMERATE as f32 * 0.75;
+        let close_enough = self.dist_to_goal_center_sq < 1.0;
+        let slow_enough = dyno.vel.length_squared() < 0.05;
+        enough_time && slow_enough && close_enough
+    }
+}
+
+/// Why a ship died
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, serde::Serialize, serde::Deserialize,
+)]
+pub enum DeathCause {
+    KillRock,
+    /// Landed on a spike segment
+    Spike,
+    OutOfBounds,
+    /// The player held down r
+    Respawn,
 }
 
 #[derive(Component)]
 pub enum Dead {
-    Explosion,
+    Explosion(DeathCause),
 }
 
 #[derive(Component)]
@@ -61,6 +86,27 @@ pub struct ShipBundle {
 }
 impl ShipBundle {
     pub fn new(pos: IVec2) -> Self {
+        Self {
+            ship: Ship {
+                can_shoot: true,
+                last_safe_location: pos,
+                time_in_goal: 0.0,
+                dist_to_goal_center_sq: f32::MAX,
+                finished: false,
+                portal_cooldown: None,
+            },
+            respawn_watcher: LongKeyPress::new(KeyCode::KeyR, (FRAMERATE * 0.36) as u32),
+            dyno: IntDyno::new(pos.extend(10), 4.0),
+            spatial: SpatialBundle::from_transform(Transform::from_translation(
+                pos.as_vec2().extend(100.0),
+            )),
+            anim: Self::animation(),
+            name: Name::new("Ship"),
+        }
+    }
+
+    /// The ship sprite ("ship") and its light ("light")
+    pub fn animation() -> MultiAnimationManager {
         let ship = AnimationManager::from_static_pairs(vec![
             (
                 "full",
@@ -119,21 +165,41 @@ impl ShipBundle {
             ),
         ])
         .force_render_layer(light_layer_u8());
+        MultiAnimationManager::from_pairs(vec![("ship", ship), ("light", light)])
+    }
+}
+
+/// A translucent ship that follows the trajectory of a previous attempt.
+/// Purely cosmetic, it has no `IntDyno` and so never collides with anything.
+#[derive(Component)]
+pub struct GhostShip {
+    /// Position of the ship at every tick of the attempt
+    pub trajectory: Vec<IVec2>,
+}
+
+#[derive(Bundle)]
+pub struct GhostShipBundle {
+    pub ghost: GhostShip,
+    pub spatial: SpatialBundle,
+    pub anim: MultiAnimationManager,
+    pub name: Name,
+}
+impl GhostShipBundle {
+    pub fn new(trajectory: Vec<IVec2>) -> Self {
+        let pos = trajectory.first().copied().unwrap_or_default();
+        let mut anim = ShipBundle::animation();
+        for manager in anim.map.values_mut() {
+            for node in manager.map.values_mut() {
+                node.sprite.color = node.sprite.color.with_a(0.35);
+            }
+        }
         Self {
-            ship: Ship {
-                can_shoot: true,
-                last_safe_location: pos,
-                time_in_goal: 0.0,
-                dist_to_goal_center_sq: f32::MAX,
-                finished: false,
-            },
-            respawn_watcher: LongKeyPress::new(KeyCode::KeyR, (FRAMERATE * 0.36) as u32),
-            dyno: IntDyno::new(pos.extend(10), 4.0),
+            ghost: GhostShip { trajectory },
             spatial: SpatialBundle::from_transform(Transform::from_translation(
-                pos.as_vec2().extend(100.0),
+                pos.as_vec2().extend(99.0),
             )),
-            anim: MultiAnimationManager::from_pairs(vec![("ship", ship), ("light", light)]),
-            name: Name::new("Ship"),
+            anim,
+            name: Name::new("GhostShip"),
         }
     }
 }
@@ -144,6 +210,7 @@ pub fn launch_ship(
     gs: Res<GameState>,
     mut gs_writer: EventWriter<SetMetaState>,
     bullet_time: Res<BulletTime>,
+    mut replay_state: ResMut<ReplayState>,
     mut commands: Commands,
 ) {
     let level_state = gs.get_level_state();
@@ -154,6 +221,7 @@ pub fn launch_ship(
             }
             dyno.vel = launch.vel * bullet_time.factor();
             ship.can_shoot = false;
+            replay_state.record(ReplayEventKind::Launch(launch.vel));
             if let Some(mut ls) = level_state.clone() {
                 ls.num_shots += 1;
                 gs_writer.send(SetMetaState(MetaState::Level(ls.clone())));
@@ -168,10 +236,17 @@ pub fn launch_ship(
 }
 
 /// Checks if the user held down r to respawn
-fn watch_for_respawn(mut commands: Commands, mut entity_n_lp: Query<(Entity, &mut LongKeyPress)>) {
+fn watch_for_respawn(
+    mut commands: Commands,
+    mut entity_n_lp: Query<(Entity, &mut LongKeyPress)>,
+    mut replay_state: ResMut<ReplayState>,
+) {
     for (id, mut lp) in entity_n_lp.iter_mut() {
         if lp.was_activated() {
-            commands.entity(id).insert(Dead::Explosion);
+            replay_state.record(ReplayEventKind::Respawn);
+            commands
+                .entity(id)
+                .insert(Dead::Explosion(DeathCause::Respawn));
         }
     }
 }
@@ -188,13 +263,36 @@ fn watch_simple_kill_collisions(
                 continue;
             };
             if rock.kind == RockKind::SimpleKill {
-                commands.entity(id).insert(Dead::Explosion);
+                commands
+                    .entity(id)
+                    .insert(Dead::Explosion(DeathCause::KillRock));
                 break;
             }
         }
     }
 }
 
+/// Checks if the ship is touching a spike. The dyno stops the ship dead on the spike and leaves
+/// the trigger around for this to find
+fn watch_spike_collisions(
+    mut commands: Commands,
+    ship_q: Query<(Entity, &IntDyno), Without<Dead>>,
+    segments: Query<&Segment>,
+) {
+    for (id, dyno) in ship_q.iter() {
+        let on_spike = dyno.triggers.keys().any(|eid| {
+            segments
+                .get(*eid)
+                .is_ok_and(|seg| seg.kind == SegmentKind::Spike)
+        });
+        if on_spike {
+            commands
+                .entity(id)
+                .insert(Dead::Explosion(DeathCause::Spike));
+        }
+    }
+}
+
 /// Checks if there are ready LivePoly's that the ship is not colliding with.
 /// If so we assume we're "out of bounds" and kill the ship
 fn watch_oob(
@@ -211,13 +309,15 @@ fn watch_oob(
             }
         }
         if oob {
-            commands.entity(eid).insert(Dead::Explosion);
+            commands
+                .entity(eid)
+                .insert(Dead::Explosion(DeathCause::OutOfBounds));
         }
     }
 }
 
 /// Checks if the shot can be replenished and also updates the sprite
-fn replenish_shot(
+pub(crate) fn replenish_shot(
     mut ship_q: Query<
         (&mut Ship, &mut IntDyno, &mut MultiAnimationManager),
         Without<ReplenishMarker>,
@@ -277,27 +377,21 @@ fn replenish_shot(
     }
 }
 
-/// Spawns a fun trail behind the ball
-pub fn spawn_trail(
-    mut commands: Commands,
-    ship: Query<&GlobalTransform, (With<Ship>, Without<Dead>)>,
-    level_root: Query<Entity, With<LevelRoot>>,
+/// Spawns one particle of a ship trail under the level root
+fn spawn_trail_particle(
+    commands: &mut Commands,
+    level_root: Entity,
+    pos: Vec3,
+    color: Color,
+    end_color: Color,
 ) {
-    let Ok(tran) = ship.get_single() else {
-        return;
-    };
-    // TODO: I should attach a particle spawner to the ship, and then have the particle spawner
-    // handle doing this
-    let Ok(level_root) = level_root.get_single() else {
-        return;
-    };
     let id = ParticleBundle::spawn_options(
-        &mut commands,
+        commands,
         ParticleBody {
-            pos: tran.translation() - Vec3::Z,
+            pos: pos - Vec3::Z,
             vel: Vec2::ZERO,
             size: Ship::radius(),
-            color: Color::YELLOW,
+            color,
             ..default()
         },
         0.5,
@@ -306,7 +400,7 @@ pub fn spawn_trail(
                 spleen: Spleen::EaseInQuad,
             }),
             coloring: Some(ParticleColoring {
-                end_color: Color::BLUE,
+                end_color,
                 spleen: Spleen::EaseInQuad,
             }),
             ..default()
@@ -315,6 +409,49 @@ pub fn spawn_trail(
     commands.entity(level_root).add_child(id);
 }
 
+/// Spawns a fun trail behind the ball
+pub fn spawn_trail(
+    mut commands: Commands,
+    ship: Query<&GlobalTransform, (With<Ship>, Without<Dead>)>,
+    level_root: Query<Entity, With<LevelRoot>>,
+) {
+    let Ok(tran) = ship.get_single() else {
+        return;
+    };
+    // TODO: I should attach a particle spawner to the ship, and then have the particle spawner
+    // handle doing this
+    let Ok(level_root) = level_root.get_single() else {
+        return;
+    };
+    spawn_trail_particle(
+        &mut commands,
+        level_root,
+        tran.translation(),
+        Color::YELLOW,
+        Color::BLUE,
+    );
+}
+
+/// Same as `spawn_trail`, but fainter, for ghosts
+fn spawn_ghost_trail(
+    mut commands: Commands,
+    ghosts: Query<&GlobalTransform, With<GhostShip>>,
+    level_root: Query<Entity, With<LevelRoot>>,
+) {
+    let Ok(level_root) = level_root.get_single() else {
+        return;
+    };
+    for tran in ghosts.iter() {
+        spawn_trail_particle(
+            &mut commands,
+            level_root,
+            tran.translation(),
+            Color::YELLOW.with_a(0.25),
+            Color::BLUE.with_a(0.0),
+        );
+    }
+}
+
 /// Checks to see if the ship has been marked for death. If so, start the death effect.
 fn watch_for_dead_ships(
     mut ships: Query<
@@ -332,7 +469,7 @@ fn watch_for_dead_ships(
     let mut rng = thread_rng();
     for (eid, cause, mut anim, dyno, gtran) in ships.iter_mut() {
         match cause {
-            Dead::Explosion => {
+            Dead::Explosion(_) => {
                 commands.spawn(SoundEffect::spatial(
                     "sound_effects/explosion.ogg",
                     1.0,
@@ -441,17 +578,22 @@ fn update_dying_ships(
 }
 
 pub fn register_ship(app: &mut App) {
+    // Launches happen on fixed ticks so that replays can reproduce them exactly
     app.add_systems(
-        Update,
+        FixedUpdate,
         launch_ship
             .run_if(should_apply_physics)
-            .run_if(is_not_in_cutscene),
+            .run_if(is_not_in_cutscene)
+            .before(update_bullet_time),
     );
     app.add_systems(
         FixedUpdate,
         (
             replenish_shot,
+            // Respawns played back from a replay have to be seen on the tick they were recorded on
+            watch_for_respawn.after(play_replay_events),
             watch_simple_kill_collisions,
+            watch_spike_collisions,
             watch_oob,
             watch_for_dead_ships,
             update_dying_ships,
@@ -463,7 +605,7 @@ pub fn register_ship(app: &mut App) {
 
     app.add_systems(
         Update,
-        (watch_for_respawn, spawn_trail)
+        (spawn_trail, spawn_ghost_trail)
             .run_if(should_apply_physics)
             .run_if(is_not_in_cutscene)
             .after(apply_fields)